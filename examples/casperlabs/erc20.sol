@@ -8,15 +8,15 @@ contract ERC20 {
     string private _name;
     string private _symbol;
     uint8 private _decimals;
-    
-    // phantom
-    address msg_sender;
+
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event Approval(address indexed owner, address indexed spender, uint256 value);
 
     constructor (string name, string symbol, uint256 totalSupply) public {
         _name = name;
         _symbol = symbol;
         _decimals = 18;
-        _balances[msg_sender] = totalSupply;
+        _balances[msg.sender] = totalSupply;
         _totalSupply = totalSupply;
     }
 
@@ -41,7 +41,7 @@ contract ERC20 {
     }
 
     function transfer(address recipient, uint256 amount) public returns (bool) {
-        _transfer(msg_sender, recipient, amount);
+        _transfer(msg.sender, recipient, amount);
         return true;
     }
 
@@ -50,22 +50,24 @@ contract ERC20 {
     }
 
     function approve(address spender, uint256 amount) public returns (bool) {
-        _approve(msg_sender, spender, amount);
+        _approve(msg.sender, spender, amount);
         return true;
     }
 
     function transferFrom(address sender, address recipient, uint256 amount) public returns (bool) {
         _transfer(sender, recipient, amount);
-        _approve(sender, msg_sender, _allowances[sender][msg_sender] - amount);
+        _approve(sender, msg.sender, _allowances[sender][msg.sender] - amount);
         return true;
     }
 
     function _transfer(address sender, address recipient, uint256 amount) internal {
         _balances[sender] = _balances[sender] - amount;
         _balances[recipient] = _balances[recipient] + amount;
+        emit Transfer(sender, recipient, amount);
     }
 
     function _approve(address owner, address spender, uint256 amount) internal {
         _allowances[owner][spender] = amount;
+        emit Approval(owner, spender, amount);
     }
 }
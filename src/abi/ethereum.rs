@@ -1,5 +1,4 @@
 // ethereum style ABIs
-use parser::pt;
 use resolver::{Namespace, Type};
 use serde::Serialize;
 
@@ -61,10 +60,7 @@ pub fn gen_abi(contract_no: usize, ns: &Namespace) -> Vec<ABI> {
     ns.contracts[contract_no]
         .functions
         .iter()
-        .filter(|f| match f.visibility {
-            pt::Visibility::Public(_) | pt::Visibility::External(_) => true,
-            _ => false,
-        })
+        .filter(|f| f.is_public())
         .map(|f| ABI {
             name: f.name.to_owned(),
             mutability: match &f.mutability {
@@ -6,7 +6,7 @@ pub mod substrate;
 
 pub fn generate_abi(contract_no: usize, ns: &Namespace, verbose: bool) -> (String, &'static str) {
     match ns.target {
-        Target::Ewasm | Target::Sabre => {
+        Target::Ewasm | Target::Sabre | Target::Casperlabs => {
             if verbose {
                 eprintln!(
                     "info: Generating Ethereum ABI for contract {}",
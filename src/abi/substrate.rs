@@ -422,12 +422,7 @@ pub fn gen_abi(contract_no: usize, ns: &resolver::Namespace) -> Metadata {
     let messages = ns.contracts[contract_no]
         .functions
         .iter()
-        .filter(|f| match f.visibility {
-            pt::Visibility::Public(_) | pt::Visibility::External(_) => {
-                f.ty == pt::FunctionTy::Function
-            }
-            _ => false,
-        })
+        .filter(|f| f.is_public() && f.ty == pt::FunctionTy::Function)
         .map(|f| Message {
             name: registry.string(&f.name),
             mutates: f.mutability.is_none(),
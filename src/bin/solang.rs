@@ -5,11 +5,12 @@ extern crate solang;
 use clap::{App, Arg, ArgMatches};
 use serde::Serialize;
 use std::collections::HashMap;
-use std::fs::File;
+use std::fs::{create_dir_all, File};
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
 
 use solang::abi;
+use solang::file_cache::FileCache;
 use solang::output;
 use solang::resolver::casperlabs;
 
@@ -46,7 +47,6 @@ fn main() {
                 .help("Emit compiler state at early stage")
                 .long("emit")
                 .takes_value(true)
-                .default_value("casperlabs")
                 .possible_values(&["cfg", "llvm", "bc", "object", "casperlabs"]),
         )
         .arg(
@@ -62,8 +62,8 @@ fn main() {
                 .help("Target to build for")
                 .long("target")
                 .takes_value(true)
-                .possible_values(&["substrate", "ewasm", "sabre"])
-                .default_value("substrate"),
+                .possible_values(&["substrate", "ewasm", "sabre", "casperlabs"])
+                .default_value("casperlabs"),
         )
         .arg(
             Arg::with_name("STD-JSON")
@@ -83,6 +83,29 @@ fn main() {
                 .long("output")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("NO-CONTRACT-MACRO")
+                .help("casperlabs: generate the entry points without casperlabs_contract_macro")
+                .long("no-contract-macro"),
+        )
+        .arg(
+            Arg::with_name("IMPORTPATH")
+                .help("Directory to search for solidity files")
+                .short("I")
+                .long("importpath")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("IMPORTMAP")
+                .help("Map import prefix to path, e.g. openzeppelin=node_modules/openzeppelin")
+                .short("m")
+                .long("importmap")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
         .get_matches();
 
     let mut json = JsonResult {
@@ -90,13 +113,39 @@ fn main() {
         contracts: HashMap::new(),
     };
 
-    let target = match matches.value_of("TARGET") {
+    let mut target = match matches.value_of("TARGET") {
         Some("substrate") => solang::Target::Substrate,
         Some("ewasm") => solang::Target::Ewasm,
         Some("sabre") => solang::Target::Sabre,
+        Some("casperlabs") => solang::Target::Casperlabs,
         _ => unreachable!(),
     };
 
+    // casperlabs used to be selected with --emit rather than --target
+    if let Some("casperlabs") = matches.value_of("EMIT") {
+        if matches.occurrences_of("TARGET") > 0 && target != solang::Target::Casperlabs {
+            eprintln!(
+                "error: --emit casperlabs is not supported for target {}",
+                target
+            );
+            std::process::exit(1);
+        }
+        eprintln!("warning: --emit casperlabs is deprecated, use --target casperlabs");
+        target = solang::Target::Casperlabs;
+    }
+
+    if target == solang::Target::Casperlabs {
+        if let Some(emit) = matches.value_of("EMIT") {
+            if emit != "cfg" && emit != "casperlabs" {
+                eprintln!(
+                    "error: --emit {} is not supported for target {}",
+                    emit, target
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
     if matches.is_present("VERBOSE") {
         eprintln!("info: Solang version {}", env!("GIT_HASH"));
     }
@@ -131,30 +180,46 @@ fn process_filename(
 
     let mut json_contracts = HashMap::new();
 
-    let mut f = match File::open(&filename) {
-        Err(err_info) => {
-            eprintln!(
-                "error: cannot open {:?}: {}",
-                &filename,
-                err_info.to_string()
-            );
+    let mut cache = FileCache::new();
+
+    if let Some(paths) = matches.values_of("IMPORTPATH") {
+        for path in paths {
+            cache.add_import_path(PathBuf::from(path));
+        }
+    }
+
+    if let Some(maps) = matches.values_of("IMPORTMAP") {
+        for map in maps {
+            let mut parts = map.splitn(2, '=');
+
+            match (parts.next(), parts.next()) {
+                (Some(prefix), Some(path)) if !prefix.is_empty() => {
+                    cache.add_remapping(prefix, path);
+                }
+                _ => {
+                    eprintln!("error: import map ‘{}’ should be prefix=path", map);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    let file_no = match cache.load_file(Path::new(filename)) {
+        Ok(file_no) => file_no,
+        Err(message) => {
+            eprintln!("error: {}", message);
             std::process::exit(1);
         }
-        Ok(file) => file,
     };
 
-    let mut contents = String::new();
-    f.read_to_string(&mut contents)
-        .expect("something went wrong reading the file");
-
     // resolve phase
-    let (ns, errors) = solang::parse_and_resolve(&contents, target);
+    let (ns, errors) = solang::parse_and_resolve_file(file_no, &mut cache, target);
 
     if matches.is_present("STD-JSON") {
-        let mut out = output::message_as_json(filename, &contents, &errors);
+        let mut out = output::message_as_json(&cache, &errors);
         json.errors.append(&mut out);
     } else {
-        output::print_messages(filename, &contents, &errors, verbose);
+        output::print_messages(&cache, &errors, verbose);
     }
 
     let ns = match ns {
@@ -174,9 +239,66 @@ fn process_filename(
             continue;
         }
 
-        if let Some("casperlabs") = matches.value_of("EMIT") {
-            let contract = casperlabs::CasperlabsContract::new(&resolved_contract);
-            println!("{}", contract.render());
+        // an interface has no code to deploy
+        if resolved_contract.is_interface() {
+            continue;
+        }
+
+        if target == solang::Target::Casperlabs {
+            let mut contract = casperlabs::CasperlabsContract::new(&resolved_contract, &ns);
+
+            contract.contract_macro = !matches.is_present("NO-CONTRACT-MACRO");
+
+            match contract.render_crate() {
+                Ok(mut files) => match matches.value_of("OUTPUT") {
+                    Some(dir) => {
+                        let crate_dir = Path::new(dir).join(&resolved_contract.name);
+
+                        if verbose {
+                            eprintln!(
+                                "info: Saving crate {} for contract {}",
+                                crate_dir.display(),
+                                resolved_contract.name
+                            );
+                        }
+
+                        // maps the error codes the contract reverts with to their reasons
+                        files.push((
+                            "errors.json".to_string(),
+                            contract.render_error_manifest(&cache),
+                        ));
+                        // describes the entry points, events and enums of the crate
+                        files.push(("schema.json".to_string(), contract.render_schema()));
+
+                        for (name, contents) in files {
+                            let path = crate_dir.join(name);
+
+                            create_dir_all(path.parent().unwrap()).unwrap();
+
+                            let mut file = File::create(path).unwrap();
+                            file.write_all(contents.as_bytes()).unwrap();
+                        }
+                    }
+                    // the json output is the only output on stdout
+                    None if matches.is_present("STD-JSON") => (),
+                    None => {
+                        for (name, contents) in files {
+                            if name == "src/main.rs" {
+                                print!("{}", contents);
+                            }
+                        }
+                    }
+                },
+                Err(errors) => {
+                    if matches.is_present("STD-JSON") {
+                        let mut out = output::message_as_json(&cache, &errors);
+                        json.errors.append(&mut out);
+                    } else {
+                        output::print_messages(&cache, &errors, verbose);
+                        std::process::exit(1);
+                    }
+                }
+            }
             continue;
         }
 
@@ -248,6 +248,8 @@ impl<'a> Contract<'a> {
             }
             super::Target::Ewasm => ewasm::EwasmTarget::build(context, contract, ns, filename, opt),
             super::Target::Sabre => sabre::SabreTarget::build(context, contract, ns, filename, opt),
+            // casperlabs contracts are transpiled to rust by resolver::casperlabs
+            super::Target::Casperlabs => unreachable!(),
         }
     }
 
@@ -3179,6 +3181,9 @@ impl<'a> Contract<'a> {
                             )
                             .into();
                     }
+                    cfg::Instr::EmitEvent { .. } => {
+                        // events are only supported on casperlabs; the resolver has warned
+                    }
                 }
             }
         }
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+/// All the source files the compiler has seen. Files are either provided in memory or loaded
+/// from the filesystem; the index of a file in the cache is the file number used in `pt::Loc`.
+///
+/// Imports are resolved by first applying the remappings (`prefix=path`), and then looking for
+/// the file in memory, next to the importing file for `./` and `../` imports, and finally in
+/// each of the import paths in order. If no import paths are given, the current directory is
+/// used.
+#[derive(Default)]
+pub struct FileCache {
+    import_paths: Vec<PathBuf>,
+    remappings: Vec<(String, String)>,
+    files: Vec<(String, Rc<String>)>,
+    by_name: HashMap<String, usize>,
+}
+
+impl FileCache {
+    pub fn new() -> Self {
+        FileCache::default()
+    }
+
+    /// Add a directory where imported files are searched for
+    pub fn add_import_path(&mut self, path: PathBuf) {
+        self.import_paths.push(path);
+    }
+
+    /// Any import which starts with prefix has the prefix replaced with path
+    pub fn add_remapping(&mut self, prefix: &str, path: &str) {
+        self.remappings.push((prefix.to_owned(), path.to_owned()));
+    }
+
+    /// Provide the contents of a file, rather than reading it from the filesystem. This returns
+    /// the file number.
+    pub fn set_file_contents(&mut self, name: &str, contents: String) -> usize {
+        match self.by_name.get(name) {
+            Some(file_no) => {
+                self.files[*file_no].1 = Rc::new(contents);
+
+                *file_no
+            }
+            None => self.add_file(name.to_owned(), contents),
+        }
+    }
+
+    /// The contents of a file which is in the cache
+    pub fn get_file_contents(&self, file_no: usize) -> Rc<String> {
+        self.files[file_no].1.clone()
+    }
+
+    /// The name of a file which is in the cache
+    pub fn get_file_name(&self, file_no: usize) -> &str {
+        &self.files[file_no].0
+    }
+
+    /// Read a file from the filesystem, unless it is already in the cache. This returns the file
+    /// number, or an error message if the file cannot be read.
+    pub fn load_file(&mut self, path: &Path) -> Result<usize, String> {
+        let name = path.to_string_lossy().to_string();
+
+        if let Some(file_no) = self.by_name.get(&name) {
+            return Ok(*file_no);
+        }
+
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(self.add_file(name, contents)),
+            Err(err) => Err(format!("cannot read file ‘{}’: {}", name, err)),
+        }
+    }
+
+    /// Resolve the filename given in an import directive in the file `parent`, and load it into
+    /// the cache. This returns the file number, or an error message if the file is not found.
+    pub fn resolve_file(&mut self, parent: usize, filename: &str) -> Result<usize, String> {
+        let filename = self.remap(filename);
+
+        if filename.starts_with("./") || filename.starts_with("../") {
+            let path = Path::new(&self.files[parent].0)
+                .parent()
+                .unwrap_or_else(|| Path::new(""))
+                .join(&filename);
+
+            return self.find_file(&normalize(&path));
+        }
+
+        if let Some(file_no) = self.by_name.get(&filename) {
+            return Ok(*file_no);
+        }
+
+        if self.import_paths.is_empty() {
+            return self.find_file(Path::new(&filename));
+        }
+
+        for import_path in &self.import_paths {
+            let path = normalize(&import_path.join(&filename));
+
+            if let Some(file_no) = self.by_name.get(&*path.to_string_lossy()) {
+                return Ok(*file_no);
+            }
+
+            if path.is_file() {
+                return self.load_file(&path);
+            }
+        }
+
+        Err(format!("file ‘{}’ not found", filename))
+    }
+
+    fn find_file(&mut self, path: &Path) -> Result<usize, String> {
+        if let Some(file_no) = self.by_name.get(&*path.to_string_lossy()) {
+            return Ok(*file_no);
+        }
+
+        if !path.is_file() {
+            return Err(format!("file ‘{}’ not found", path.display()));
+        }
+
+        self.load_file(path)
+    }
+
+    /// Apply the remapping with the longest matching prefix
+    fn remap(&self, filename: &str) -> String {
+        match self
+            .remappings
+            .iter()
+            .filter(|(prefix, _)| filename.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+        {
+            Some((prefix, path)) => format!("{}{}", path, &filename[prefix.len()..]),
+            None => filename.to_owned(),
+        }
+    }
+
+    fn add_file(&mut self, name: String, contents: String) -> usize {
+        let file_no = self.files.len();
+
+        self.by_name.insert(name.clone(), file_no);
+        self.files.push((name, Rc::new(contents)));
+
+        file_no
+    }
+}
+
+/// Remove any `.` and `..` components from a path, so that the same file always has the same
+/// name in the cache
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                if normalized.file_name().is_some() {
+                    normalized.pop();
+                } else {
+                    normalized.push("..");
+                }
+            }
+            c => normalized.push(c.as_os_str()),
+        }
+    }
+
+    normalized
+}
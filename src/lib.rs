@@ -15,6 +15,7 @@ extern crate tiny_keccak;
 extern crate unicode_xid;
 
 pub mod abi;
+pub mod file_cache;
 pub mod link;
 pub mod output;
 
@@ -22,6 +23,7 @@ mod emit;
 mod parser;
 pub mod resolver;
 
+use file_cache::FileCache;
 use inkwell::OptimizationLevel;
 use std::fmt;
 
@@ -34,6 +36,8 @@ pub enum Target {
     Ewasm,
     /// Sawtooth Sabre, see https://github.com/hyperledger/sawtooth-sabre
     Sabre,
+    /// CasperLabs, see https://casperlabs.io/. Contracts are transpiled to Rust
+    Casperlabs,
 }
 
 impl fmt::Display for Target {
@@ -42,26 +46,38 @@ impl fmt::Display for Target {
             Target::Substrate => write!(f, "Substrate"),
             Target::Ewasm => write!(f, "ewasm"),
             Target::Sabre => write!(f, "Sawtooth Sabre"),
+            Target::Casperlabs => write!(f, "CasperLabs"),
         }
     }
 }
 
-/// Compile a solidity file to list of wasm files and their ABIs. The filename is only used for error messages;
-/// the contents of the file is provided in the `src` argument.
+/// Compile a solidity file in the file cache to list of wasm files and their ABIs. Any files it imports
+/// are resolved through the cache too; the locations in the compiler messages refer to files in the cache.
 ///
 /// This function only produces a single contract and abi, which is compiled for the `target` specified. Any
 /// compiler warnings, errors and informational messages are also provided.
 ///
-/// The ctx is the inkwell llvm context.
+/// The ctx is the inkwell llvm context. The casperlabs target does not produce wasm; use
+/// `parse_and_resolve` and `resolver::casperlabs` to generate the rust contract instead.
 pub fn compile(
-    src: &str,
-    filename: &str,
+    file_no: usize,
+    cache: &mut FileCache,
     opt: OptimizationLevel,
     target: Target,
 ) -> (Vec<(Vec<u8>, String)>, Vec<output::Output>) {
+    if target == Target::Casperlabs {
+        return (
+            Vec::new(),
+            vec![output::Output::error(
+                parser::pt::Loc(0, 0, 0),
+                format!("target {} does not generate wasm", target),
+            )],
+        );
+    }
+
     let ctx = inkwell::context::Context::create();
 
-    let ast = match parser::parse(src) {
+    let ast = match parser::parse_with_imports(file_no, cache) {
         Ok(s) => s,
         Err(errors) => {
             return (Vec::new(), errors);
@@ -76,6 +92,8 @@ pub fn compile(
         (Some(ns), errors) => (ns, errors),
     };
 
+    let filename = cache.get_file_name(file_no);
+
     let results = (0..ns.contracts.len())
         .map(|c| {
             let (abistr, _) = abi::generate_abi(c, &ns, false);
@@ -101,7 +119,21 @@ pub fn parse_and_resolve(
     src: &str,
     target: Target,
 ) -> (Option<resolver::Namespace>, Vec<output::Output>) {
-    let ast = match parser::parse(src) {
+    let mut cache = FileCache::new();
+    let file_no = cache.set_file_contents("", src.to_owned());
+
+    parse_and_resolve_file(file_no, &mut cache, target)
+}
+
+/// Parse and resolve a file in the file cache, together with all the files it imports. The file_no is the
+/// number returned by `FileCache::set_file_contents` or `FileCache::load_file`. The locations in the compiler
+/// messages refer to files in the cache.
+pub fn parse_and_resolve_file(
+    file_no: usize,
+    cache: &mut FileCache,
+    target: Target,
+) -> (Option<resolver::Namespace>, Vec<output::Output>) {
+    let ast = match parser::parse_with_imports(file_no, cache) {
         Ok(s) => s,
         Err(errors) => {
             return (None, errors);
@@ -31,6 +31,7 @@ pub fn link(input: &[u8], target: Target) -> Vec<u8> {
         Target::Ewasm => name == "main",
         Target::Substrate => name == "deploy" || name == "call",
         Target::Sabre => name == "entrypoint",
+        Target::Casperlabs => unreachable!(),
     };
 
     for c in module.custom_sections() {
@@ -99,6 +100,7 @@ pub fn link(input: &[u8], target: Target) -> Vec<u8> {
                 elements::External::Memory(elements::MemoryType::new(16, Some(16))),
             )),
             Target::Sabre => exports.push(ExportEntry::new("memory".into(), Internal::Memory(0))),
+            Target::Casperlabs => unreachable!(),
         }
     }
 
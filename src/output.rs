@@ -1,3 +1,4 @@
+use file_cache::FileCache;
 use parser::pt;
 use serde::Serialize;
 
@@ -155,23 +156,20 @@ impl Output {
         }
     }
 
-    fn formated_message(&self, filename: &str, pos: &FilePostitions) -> String {
-        let loc = pos.to_string(self.pos);
-
+    fn formated_message(&self, cache: &FileCache) -> String {
         let mut s = format!(
-            "{}:{}: {}: {}",
-            filename,
-            loc,
+            "{}: {}: {}",
+            location(cache, self.pos),
             self.level.to_string(),
             self.message
         );
 
         for note in &self.notes {
-            let loc = pos.to_string(note.pos);
-
             s.push_str(&format!(
-                "\n\t{}:{}: {}: {}",
-                filename, loc, "note", note.message
+                "\n\t{}: {}: {}",
+                location(cache, note.pos),
+                "note",
+                note.message
             ));
         }
 
@@ -179,15 +177,20 @@ impl Output {
     }
 }
 
-pub fn print_messages(filename: &str, src: &str, messages: &[Output], verbose: bool) {
-    let pos = FilePostitions::new(src);
+/// The filename, line and column of a location, e.g. `flipper.sol:5:10-15`
+pub fn location(cache: &FileCache, loc: pt::Loc) -> String {
+    let pos = FilePostitions::new(&cache.get_file_contents(loc.0));
 
+    format!("{}:{}", cache.get_file_name(loc.0), pos.to_string(loc))
+}
+
+pub fn print_messages(cache: &FileCache, messages: &[Output], verbose: bool) {
     for msg in messages {
         if !verbose && msg.level == Level::Info {
             continue;
         }
 
-        eprintln!("{}", msg.formated_message(filename, &pos));
+        eprintln!("{}", msg.formated_message(cache));
     }
 }
 
@@ -215,11 +218,9 @@ pub struct OutputJson {
     pub formattedMessage: String,
 }
 
-pub fn message_as_json(filename: &str, src: &str, messages: &[Output]) -> Vec<OutputJson> {
+pub fn message_as_json(cache: &FileCache, messages: &[Output]) -> Vec<OutputJson> {
     let mut json = Vec::new();
 
-    let pos = FilePostitions::new(src);
-
     for msg in messages {
         if msg.level == Level::Info {
             continue;
@@ -227,15 +228,15 @@ pub fn message_as_json(filename: &str, src: &str, messages: &[Output]) -> Vec<Ou
 
         json.push(OutputJson {
             sourceLocation: LocJson {
-                file: filename.to_string(),
-                start: msg.pos.0,
-                end: msg.pos.1,
+                file: cache.get_file_name(msg.pos.0).to_string(),
+                start: msg.pos.1,
+                end: msg.pos.2,
             },
             ty: format!("{:?}", msg.ty),
             component: "general".to_owned(),
             severity: msg.level.to_string().to_owned(),
             message: msg.message.to_owned(),
-            formattedMessage: msg.formated_message(filename, &pos),
+            formattedMessage: msg.formated_message(cache),
         });
     }
 
@@ -258,8 +259,8 @@ impl FilePostitions {
     }
 
     fn to_string(&self, loc: pt::Loc) -> String {
-        let (from_line, from_column) = self.convert(loc.0);
-        let (to_line, to_column) = self.convert(loc.1);
+        let (from_line, from_column) = self.convert(loc.1);
+        let (to_line, to_column) = self.convert(loc.2);
 
         if from_line == to_line && from_column == to_column {
             format!("{}:{}", from_line, from_column)
@@ -32,6 +32,7 @@ pub enum Token<'input> {
     Library,
     Interface,
     Function,
+    Modifier,
     Pragma,
     Import,
 
@@ -133,6 +134,9 @@ pub enum Token<'input> {
 
     Constructor,
     Indexed,
+    Is,
+    Virtual,
+    Override,
 
     Member,
     Colon,
@@ -231,6 +235,7 @@ impl<'input> fmt::Display for Token<'input> {
             Token::Library => write!(f, "library"),
             Token::Interface => write!(f, "interface"),
             Token::Function => write!(f, "function"),
+            Token::Modifier => write!(f, "modifier"),
             Token::Pragma => write!(f, "pragma"),
             Token::Import => write!(f, "import"),
             Token::Struct => write!(f, "struct"),
@@ -265,6 +270,9 @@ impl<'input> fmt::Display for Token<'input> {
             Token::If => write!(f, "if"),
             Token::Constructor => write!(f, "constructor"),
             Token::Indexed => write!(f, "indexed"),
+            Token::Is => write!(f, "is"),
+            Token::Virtual => write!(f, "virtual"),
+            Token::Override => write!(f, "override"),
             Token::Mapping => write!(f, "mapping"),
             Token::Arrow => write!(f, "=>"),
             Token::Try => write!(f, "try"),
@@ -321,14 +329,14 @@ impl fmt::Display for LexicalError {
 }
 
 impl LexicalError {
-    pub fn loc(&self) -> Loc {
+    pub fn loc(&self, file_no: usize) -> Loc {
         match self {
-            LexicalError::EndOfFileInComment(start, end) => Loc(*start, *end),
-            LexicalError::EndOfFileInString(start, end) => Loc(*start, *end),
-            LexicalError::EndofFileInHex(start, end) => Loc(*start, *end),
-            LexicalError::MissingNumber(start, end) => Loc(*start, *end),
-            LexicalError::InvalidCharacterInHexLiteral(pos, _) => Loc(*pos, *pos),
-            LexicalError::UnrecognisedToken(start, end, _) => Loc(*start, *end),
+            LexicalError::EndOfFileInComment(start, end) => Loc(file_no, *start, *end),
+            LexicalError::EndOfFileInString(start, end) => Loc(file_no, *start, *end),
+            LexicalError::EndofFileInHex(start, end) => Loc(file_no, *start, *end),
+            LexicalError::MissingNumber(start, end) => Loc(file_no, *start, *end),
+            LexicalError::InvalidCharacterInHexLiteral(pos, _) => Loc(file_no, *pos, *pos),
+            LexicalError::UnrecognisedToken(start, end, _) => Loc(file_no, *start, *end),
         }
     }
 }
@@ -390,6 +398,7 @@ static KEYWORDS: phf::Map<&'static str, Token> = phf_map! {
     "if" => Token::If,
     "import" => Token::Import,
     "indexed" => Token::Indexed,
+    "is" => Token::Is,
     "int8" => Token::Int(8),
     "int16" => Token::Int(16),
     "int24" => Token::Int(24),
@@ -428,7 +437,9 @@ static KEYWORDS: phf::Map<&'static str, Token> = phf_map! {
     "library" => Token::Library,
     "mapping" => Token::Mapping,
     "memory" => Token::Memory,
+    "modifier" => Token::Modifier,
     "new" => Token::New,
+    "override" => Token::Override,
     "payable" => Token::Payable,
     "pragma" => Token::Pragma,
     "private" => Token::Private,
@@ -476,6 +487,7 @@ static KEYWORDS: phf::Map<&'static str, Token> = phf_map! {
     "uint256" => Token::Uint(256),
     "uint" => Token::Uint(256),
     "view" => Token::View,
+    "virtual" => Token::Virtual,
     "while" => Token::While,
     "try" => Token::Try,
     "catch" => Token::Catch,
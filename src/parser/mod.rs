@@ -5,40 +5,45 @@ pub mod pt;
 #[cfg_attr(rustfmt, rustfmt_skip)]
 pub mod solidity;
 
+use file_cache::FileCache;
 use lalrpop_util::ParseError;
 use output::Output;
+use std::collections::HashSet;
 
-pub fn parse(src: &str) -> Result<pt::SourceUnit, Vec<Output>> {
+pub fn parse(src: &str, file_no: usize) -> Result<pt::SourceUnit, Vec<Output>> {
     // parse phase
     let lex = lexer::Lexer::new(src);
 
-    let s = solidity::SourceUnitParser::new().parse(src, lex);
+    let s = solidity::SourceUnitParser::new().parse(src, file_no, lex);
 
     let mut errors = Vec::new();
 
     if let Err(e) = s {
         errors.push(match e {
-            ParseError::InvalidToken { location } => {
-                Output::parser_error(pt::Loc(location, location), "invalid token".to_string())
-            }
+            ParseError::InvalidToken { location } => Output::parser_error(
+                pt::Loc(file_no, location, location),
+                "invalid token".to_string(),
+            ),
             ParseError::UnrecognizedToken {
                 token: (l, token, r),
                 expected,
             } => Output::parser_error(
-                pt::Loc(l, r),
+                pt::Loc(file_no, l, r),
                 format!(
                     "unrecognised token `{}', expected {}",
                     token,
                     expected.join(", ")
                 ),
             ),
-            ParseError::User { error } => Output::parser_error(error.loc(), error.to_string()),
+            ParseError::User { error } => {
+                Output::parser_error(error.loc(file_no), error.to_string())
+            }
             ParseError::ExtraToken { token } => Output::parser_error(
-                pt::Loc(token.0, token.2),
+                pt::Loc(file_no, token.0, token.2),
                 format!("extra token `{}' encountered", token.0),
             ),
             ParseError::UnrecognizedEOF { location, expected } => Output::parser_error(
-                pt::Loc(location, location),
+                pt::Loc(file_no, location, location),
                 format!("unexpected end of file, expecting {}", expected.join(", ")),
             ),
         });
@@ -49,6 +54,64 @@ pub fn parse(src: &str) -> Result<pt::SourceUnit, Vec<Output>> {
     }
 }
 
+/// Parse a file and all the files it imports. The parts of an imported file are included in
+/// place of its import directive, so anything imported is declared before it is used. Each file
+/// is only included once, so circular imports are harmless.
+pub fn parse_with_imports(
+    file_no: usize,
+    cache: &mut FileCache,
+) -> Result<pt::SourceUnit, Vec<Output>> {
+    let mut parts = Vec::new();
+    let mut included = HashSet::new();
+    let mut errors = Vec::new();
+
+    include_file(file_no, cache, &mut included, &mut parts, &mut errors);
+
+    if errors.is_empty() {
+        Ok(pt::SourceUnit(parts))
+    } else {
+        Err(errors)
+    }
+}
+
+fn include_file(
+    file_no: usize,
+    cache: &mut FileCache,
+    included: &mut HashSet<usize>,
+    parts: &mut Vec<pt::SourceUnitPart>,
+    errors: &mut Vec<Output>,
+) {
+    included.insert(file_no);
+
+    let src = cache.get_file_contents(file_no);
+
+    let unit = match parse(&src, file_no) {
+        Ok(unit) => unit,
+        Err(mut e) => {
+            errors.append(&mut e);
+            return;
+        }
+    };
+
+    for part in unit.0 {
+        if let pt::SourceUnitPart::ImportDirective(import) = &part {
+            match cache.resolve_file(file_no, &import.string) {
+                Ok(import_no) => {
+                    if !included.contains(&import_no) {
+                        include_file(import_no, cache, included, parts, errors);
+                    }
+                }
+                Err(message) => {
+                    errors.push(Output::error(import.loc, message));
+                }
+            }
+            continue;
+        }
+
+        parts.push(part);
+    }
+}
+
 pub fn box_option<T>(o: Option<T>) -> Option<Box<T>> {
     match o {
         None => None,
@@ -77,54 +140,57 @@ mod test {
 
         let lex = lexer::Lexer::new(&src);
 
-        let e = solidity::SourceUnitParser::new().parse(&src, lex).unwrap();
+        let e = solidity::SourceUnitParser::new()
+            .parse(&src, 0, lex)
+            .unwrap();
 
         let a = SourceUnit(vec![SourceUnitPart::ContractDefinition(Box::new(
             ContractDefinition {
                 doc: vec![],
-                loc: Loc(0, 325),
+                loc: Loc(0, 0, 325),
                 ty: ContractType::Contract,
                 name: Identifier {
-                    loc: Loc(9, 12),
+                    loc: Loc(0, 9, 12),
                     name: "foo".to_string(),
                 },
+                base: Vec::new(),
                 parts: vec![
                     ContractPart::StructDefinition(Box::new(StructDefinition {
                         doc: vec![],
                         name: Identifier {
-                            loc: Loc(42, 54),
+                            loc: Loc(0, 42, 54),
                             name: "Jurisdiction".to_string(),
                         },
                         fields: vec![
                             VariableDeclaration {
-                                ty: Expression::Type(Loc(81, 85), Type::Bool),
+                                ty: Expression::Type(Loc(0, 81, 85), Type::Bool),
                                 storage: None,
                                 name: Identifier {
-                                    loc: Loc(86, 92),
+                                    loc: Loc(0, 86, 92),
                                     name: "exists".to_string(),
                                 },
                             },
                             VariableDeclaration {
-                                ty: Expression::Type(Loc(118, 122), Type::Uint(256)),
+                                ty: Expression::Type(Loc(0, 118, 122), Type::Uint(256)),
                                 storage: None,
                                 name: Identifier {
-                                    loc: Loc(123, 129),
+                                    loc: Loc(0, 123, 129),
                                     name: "keyIdx".to_string(),
                                 },
                             },
                             VariableDeclaration {
-                                ty: Expression::Type(Loc(155, 161), Type::Bytes(2)),
+                                ty: Expression::Type(Loc(0, 155, 161), Type::Bytes(2)),
                                 storage: None,
                                 name: Identifier {
-                                    loc: Loc(162, 169),
+                                    loc: Loc(0, 162, 169),
                                     name: "country".to_string(),
                                 },
                             },
                             VariableDeclaration {
-                                ty: Expression::Type(Loc(195, 202), Type::Bytes(32)),
+                                ty: Expression::Type(Loc(0, 195, 202), Type::Bytes(32)),
                                 storage: None,
                                 name: Identifier {
-                                    loc: Loc(203, 209),
+                                    loc: Loc(0, 203, 209),
                                     name: "region".to_string(),
                                 },
                             },
@@ -133,26 +199,26 @@ mod test {
                     ContractPart::ContractVariableDefinition(Box::new(
                         ContractVariableDefinition {
                             doc: vec![],
-                            ty: Expression::Type(Loc(253, 259), Type::String),
+                            ty: Expression::Type(Loc(0, 253, 259), Type::String),
                             attrs: vec![],
                             name: Identifier {
-                                loc: Loc(260, 268),
+                                loc: Loc(0, 260, 268),
                                 name: "__abba_$".to_string(),
                             },
-                            loc: Loc(253, 268),
+                            loc: Loc(0, 253, 268),
                             initializer: None,
                         },
                     )),
                     ContractPart::ContractVariableDefinition(Box::new(
                         ContractVariableDefinition {
                             doc: vec![],
-                            ty: Expression::Type(Loc(290, 295), Type::Int(64)),
+                            ty: Expression::Type(Loc(0, 290, 295), Type::Int(64)),
                             attrs: vec![],
                             name: Identifier {
-                                loc: Loc(296, 306),
+                                loc: Loc(0, 296, 306),
                                 name: "$thing_102".to_string(),
                             },
-                            loc: Loc(290, 306),
+                            loc: Loc(0, 290, 306),
                             initializer: None,
                         },
                     )),
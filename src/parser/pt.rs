@@ -1,8 +1,9 @@
 use num_bigint::BigInt;
 use std::fmt;
 
+/// Location in the source: file number, start offset and end offset
 #[derive(Debug, PartialEq, Clone, Copy)]
-pub struct Loc(pub usize, pub usize);
+pub struct Loc(pub usize, pub usize, pub usize);
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Identifier {
@@ -104,7 +105,7 @@ pub enum ContractPart {
     FunctionDefinition(Box<FunctionDefinition>),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ContractType {
     Contract,
     Interface,
@@ -117,6 +118,7 @@ pub struct ContractDefinition {
     pub loc: Loc,
     pub ty: ContractType,
     pub name: Identifier,
+    pub base: Vec<Base>,
     pub parts: Vec<ContractPart>,
 }
 
@@ -375,10 +377,20 @@ impl Visibility {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct Base {
+    pub loc: Loc,
+    pub name: Identifier,
+    pub args: Option<Vec<Expression>>,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum FunctionAttribute {
     StateMutability(StateMutability),
     Visibility(Visibility),
+    BaseOrModifier(Base),
+    Virtual(Loc),
+    Override(Loc, Vec<Identifier>),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -387,6 +399,7 @@ pub enum FunctionTy {
     Function,
     Fallback,
     Receive,
+    Modifier,
 }
 
 impl fmt::Display for FunctionTy {
@@ -396,6 +409,7 @@ impl fmt::Display for FunctionTy {
             FunctionTy::Function => write!(f, "function"),
             FunctionTy::Fallback => write!(f, "fallback"),
             FunctionTy::Receive => write!(f, "receive"),
+            FunctionTy::Modifier => write!(f, "modifier"),
         }
     }
 }
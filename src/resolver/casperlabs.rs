@@ -1,692 +1,5441 @@
-use std::collections::BTreeSet;
-use num_traits::ToPrimitive;
-use num_bigint::BigInt;
-use crate::resolver::{Contract, FunctionDecl, Type,
-    cfg::{ControlFlowGraph, Instr, Variable, BasicBlock},
-    expression::Expression
+use crate::file_cache::FileCache;
+use crate::output::{location, LocJson, Output};
+use crate::parser::pt;
+use crate::resolver::{
+    cfg::{ControlFlowGraph, HashTy, Instr, Variable},
+    expression::Expression,
+    rust::{
+        self, print_file, Arm, Attribute, BinOp, Block, Delimiter, Enum, Expr, Function, Generic,
+        Impl, Item, Linkage, Lit, MacroArgs, Module, Param, Pat, Path, Struct, UnOp,
+    },
+    structure::{structure, Stmt},
+    Contract, ContractVariableType, EnumDecl, FunctionDecl, Namespace, Parameter, Type,
 };
+use crate::Target;
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::TryInto;
 
-const MSG_SENDER: &str = "msg_sender";
-const GET_CALLER: &str = "runtime::get_caller()";
+// The generated contracts are built against these versions
+const CASPERLABS_CONTRACT_VERSION: &str = "0.6.1";
+const CASPERLABS_TYPES_VERSION: &str = "0.6.1";
+const CASPERLABS_CONTRACT_MACRO_VERSION: &str = "0.1.0";
+const RUST_TOOLCHAIN: &str = "nightly-2020-03-19";
+// The crates of the hash functions
+const DIGEST_VERSION: &str = "0.9.0";
+const BLAKE2_VERSION: &str = "0.9.0";
+const SHA2_VERSION: &str = "0.9.0";
+const SHA3_VERSION: &str = "0.9.0";
+const RIPEMD160_VERSION: &str = "0.9.0";
+// The reason strings of a crate are numbered from 2 to u16::MAX, as code 0 is
+// for the failures without a call to require(), revert() or assert(), and
+// code 1 for the calls without a reason
+const MAX_REASONS: usize = u16::MAX as usize - 1;
+// The packages in the lock file of the generated crates, as the name, the
+// version, the checksum and the dependencies of each. Every release of
+// casperlabs-contract and casperlabs-types is yanked, and cargo only resolves
+// a yanked release if it is in the lock file.
+type LockedPackage = (
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static [&'static str],
+);
+const LOCKED_PACKAGES: &[LockedPackage] = &[
+    (
+        "autocfg",
+        "1.0.0",
+        "f8aac770f1885fd7e387acedd76065302551364496e46b3dd00860b2f8359b9d",
+        &[],
+    ),
+    (
+        "base16",
+        "0.2.1",
+        "d27c3610c36aee21ce8ac510e6224498de4228ad772a171ed65643a24693a5a8",
+        &[],
+    ),
+    (
+        "bitflags",
+        "1.2.1",
+        "cf1de2fe8c75bc145a2f577add951f8134889b4795d47466a54a5c846d691693",
+        &[],
+    ),
+    (
+        "blake2",
+        "0.8.1",
+        "94cb07b0da6a73955f8fb85d24c466778e70cda767a568229b104f0264089330",
+        &[
+            "byte-tools 0.3.1",
+            "crypto-mac 0.7.0",
+            "digest 0.8.1",
+            "opaque-debug 0.2.3",
+        ],
+    ),
+    (
+        "blake2",
+        "0.9.0",
+        "84ce5b6108f8e154604bd4eb76a2f726066c3464d5a552a4229262a18c9bb471",
+        &[
+            "byte-tools 0.3.1",
+            "byteorder 1.3.4",
+            "crypto-mac 0.8.0",
+            "digest 0.9.0",
+            "opaque-debug 0.2.3",
+        ],
+    ),
+    (
+        "block-buffer",
+        "0.8.0",
+        "dbcf92448676f82bb7a334c58bbce8b0d43580fb5362a9d608b18879d12a3d31",
+        &[
+            "block-padding 0.1.5",
+            "byte-tools 0.3.1",
+            "byteorder 1.3.4",
+            "generic-array 0.14.2",
+        ],
+    ),
+    (
+        "block-padding",
+        "0.1.5",
+        "fa79dedbb091f449f1f39e53edf88d5dbe95f895dae6135a8d7b881fb5af73f5",
+        &["byte-tools 0.3.1"],
+    ),
+    (
+        "byte-tools",
+        "0.3.1",
+        "e3b5ca7a04898ad4bcd41c90c5285445ff5b791899bb1b0abdd2a2aa791211d7",
+        &[],
+    ),
+    (
+        "byteorder",
+        "1.3.4",
+        "08c48aae112d48ed9f069b33538ea9e3e90aa263cfa3d1c24309612b1f7472de",
+        &[],
+    ),
+    (
+        "casperlabs-contract",
+        "0.6.1",
+        "e06dfa3416edfe7031817d5f0b1d10744f6613fe58db46d277b4f69a783c9fe4",
+        &[
+            "casperlabs-types 0.6.1",
+            "failure 0.1.7",
+            "hex_fmt 0.3.0",
+            "wee_alloc 0.4.5",
+        ],
+    ),
+    (
+        "casperlabs-types",
+        "0.6.1",
+        "d468c1c017e5ef6713bf73431a57fa744702be3c3ad5ef53e39cac2e26177e5c",
+        &[
+            "base16 0.2.1",
+            "bitflags 1.2.1",
+            "blake2 0.8.1",
+            "failure 0.1.7",
+            "hex_fmt 0.3.0",
+            "num-derive 0.3.0",
+            "num-integer 0.1.42",
+            "num-traits 0.2.11",
+            "uint 0.8.2",
+        ],
+    ),
+    (
+        "cfg-if",
+        "0.1.10",
+        "4785bdd1c96b2a846b2bd7cc02e86b6b3dbf14e7e53446c4f54c92a361040822",
+        &[],
+    ),
+    (
+        "crunchy",
+        "0.2.2",
+        "7a81dae078cea95a014a339291cec439d2f232ebe854a9d672b796c6afafa9b7",
+        &[],
+    ),
+    (
+        "crypto-mac",
+        "0.7.0",
+        "4434400df11d95d556bac068ddfedd482915eb18fe8bea89bc80b6e4b1c179e5",
+        &["generic-array 0.12.3", "subtle 1.0.0"],
+    ),
+    (
+        "crypto-mac",
+        "0.8.0",
+        "b584a330336237c1eecd3e94266efb216c56ed91225d634cb2991c5f3fd1aeab",
+        &["generic-array 0.14.2", "subtle 2.2.3"],
+    ),
+    (
+        "digest",
+        "0.8.1",
+        "f3d0c8c8752312f9713efd397ff63acb9f85585afbf179282e720e7704954dd5",
+        &["generic-array 0.12.3"],
+    ),
+    (
+        "digest",
+        "0.9.0",
+        "d3dd60d1080a57a05ab032377049e0591415d2b31afd7028356dbf3cc6dcb066",
+        &["generic-array 0.14.2"],
+    ),
+    (
+        "failure",
+        "0.1.7",
+        "b8529c2421efa3066a5cbd8063d2244603824daccb6936b079010bb2aa89464b",
+        &["failure_derive 0.1.7"],
+    ),
+    (
+        "failure_derive",
+        "0.1.7",
+        "030a733c8287d6213886dd487564ff5c8f6aae10278b3588ed177f9d18f8d231",
+        &[
+            "proc-macro2 1.0.9",
+            "quote 1.0.3",
+            "syn 1.0.17",
+            "synstructure 0.12.3",
+        ],
+    ),
+    (
+        "fake-simd",
+        "0.1.2",
+        "e88a8acf291dafb59c2d96e8f59828f3838bb1a70398823ade51a84de6a6deed",
+        &[],
+    ),
+    (
+        "generic-array",
+        "0.12.3",
+        "c68f0274ae0e023facc3c97b2e00f076be70e254bc851d972503b328db79b2ec",
+        &["typenum 1.12.0"],
+    ),
+    (
+        "generic-array",
+        "0.14.2",
+        "ac746a5f3bbfdadd6106868134545e684693d54d9d44f6e9588a7d54af0bf980",
+        &["typenum 1.12.0", "version_check 0.9.2"],
+    ),
+    (
+        "hex_fmt",
+        "0.3.0",
+        "b07f60793ff0a4d9cef0f18e63b5357e06209987153a64648c972c1e5aff336f",
+        &[],
+    ),
+    (
+        "keccak",
+        "0.1.0",
+        "67c21572b4949434e4fc1e1978b99c5f77064153c59d998bf13ecd96fb5ecba7",
+        &[],
+    ),
+    (
+        "libc",
+        "0.2.68",
+        "dea0c0405123bba743ee3f91f49b1c7cfb684eef0da0a50110f758ccf24cdff0",
+        &[],
+    ),
+    (
+        "memory_units",
+        "0.4.0",
+        "8452105ba047068f40ff7093dd1d9da90898e63dd61736462e9cdda6a90ad3c3",
+        &[],
+    ),
+    (
+        "num-derive",
+        "0.3.0",
+        "0c8b15b261814f992e33760b1fca9fe8b693d8a65299f20c9901688636cfb746",
+        &["proc-macro2 1.0.9", "quote 1.0.3", "syn 1.0.17"],
+    ),
+    (
+        "num-integer",
+        "0.1.42",
+        "3f6ea62e9d81a77cd3ee9a2a5b9b609447857f3d358704331e4ef39eb247fcba",
+        &["autocfg 1.0.0", "num-traits 0.2.11"],
+    ),
+    (
+        "num-traits",
+        "0.2.11",
+        "c62be47e61d1842b9170f0fdeec8eba98e60e90e5446449a0545e5152acd7096",
+        &["autocfg 1.0.0"],
+    ),
+    (
+        "opaque-debug",
+        "0.2.3",
+        "2839e79665f131bdb5782e51f2c6c9599c133c6098982a54c794358bf432529c",
+        &[],
+    ),
+    (
+        "proc-macro2",
+        "1.0.9",
+        "6c09721c6781493a2a492a96b5a5bf19b65917fe6728884e7c44dd0c60ca3435",
+        &["unicode-xid 0.2.0"],
+    ),
+    (
+        "quote",
+        "1.0.3",
+        "2bdc6c187c65bca4260c9011c9e3132efe4909da44726bad24cf7572ae338d7f",
+        &["proc-macro2 1.0.9"],
+    ),
+    (
+        "ripemd160",
+        "0.9.0",
+        "7037e00ff78e861f53edd08ea4c4351fbf9b357145fdb791bc2f9a2236a33b45",
+        &["block-buffer 0.8.0", "digest 0.9.0", "opaque-debug 0.2.3"],
+    ),
+    (
+        "rustc-hex",
+        "2.1.0",
+        "3e75f6a532d0fd9f7f13144f392b6ad56a32696bfcd9c78f797f16bbb6f072d6",
+        &[],
+    ),
+    (
+        "sha2",
+        "0.9.0",
+        "72377440080fd008550fe9b441e854e43318db116f90181eef92e9ae9aedab48",
+        &[
+            "block-buffer 0.8.0",
+            "digest 0.9.0",
+            "fake-simd 0.1.2",
+            "opaque-debug 0.2.3",
+        ],
+    ),
+    (
+        "sha3",
+        "0.9.0",
+        "b859cf80317bb4ab6b29422f3d77de357ef60a0e0b3dedf28469d2b11d098968",
+        &[
+            "block-buffer 0.8.0",
+            "byte-tools 0.3.1",
+            "digest 0.9.0",
+            "keccak 0.1.0",
+            "opaque-debug 0.2.3",
+        ],
+    ),
+    (
+        "static_assertions",
+        "1.1.0",
+        "a2eb9349b6444b326872e140eb1cf5e7c522154d69e7a0ffb0fb81c06b37543f",
+        &[],
+    ),
+    (
+        "subtle",
+        "1.0.0",
+        "2d67a5a62ba6e01cb2192ff309324cb4875d0c451d55fe2319433abe7a05a8ee",
+        &[],
+    ),
+    (
+        "subtle",
+        "2.2.3",
+        "502d53007c02d7605a05df1c1a73ee436952781653da5d0bf57ad608f66932c1",
+        &[],
+    ),
+    (
+        "syn",
+        "1.0.17",
+        "0df0eb663f387145cab623dea85b09c2c5b4b0aef44e945d928e682fce71bb03",
+        &["proc-macro2 1.0.9", "quote 1.0.3", "unicode-xid 0.2.0"],
+    ),
+    (
+        "synstructure",
+        "0.12.3",
+        "67656ea1dc1b41b1451851562ea232ec2e5a80242139f7e679ceccfb5d61f545",
+        &[
+            "proc-macro2 1.0.9",
+            "quote 1.0.3",
+            "syn 1.0.17",
+            "unicode-xid 0.2.0",
+        ],
+    ),
+    (
+        "typenum",
+        "1.12.0",
+        "373c8a200f9e67a0c95e62a4f52fbf80c23b4381c05a17845531982fa99e6b33",
+        &[],
+    ),
+    (
+        "uint",
+        "0.8.2",
+        "e75a4cdd7b87b28840dba13c483b9a88ee6bbf16ba5c951ee1ecfcf723078e0d",
+        &[
+            "byteorder 1.3.4",
+            "crunchy 0.2.2",
+            "rustc-hex 2.1.0",
+            "static_assertions 1.1.0",
+        ],
+    ),
+    (
+        "unicode-xid",
+        "0.2.0",
+        "826e7639553986605ec5979c7dd957c7895e93eabed50ab2ffa7f6128a75097c",
+        &[],
+    ),
+    (
+        "version_check",
+        "0.9.2",
+        "b5a972e5669d67ba988ce3dc826706fb0a8b01471c088cb0b6110b805cc36aed",
+        &[],
+    ),
+    (
+        "wee_alloc",
+        "0.4.5",
+        "dbb3b5a6b2bb17cb6ad44a2e68a43e8d2722c997da10e928665c72ec6c0a0b8e",
+        &[
+            "cfg-if 0.1.10",
+            "libc 0.2.68",
+            "memory_units 0.4.0",
+            "winapi 0.3.8",
+        ],
+    ),
+    (
+        "winapi",
+        "0.3.8",
+        "8093091eeb260906a183e6ae1abdba2ef5ef2257a21801128899c3fc699229c6",
+        &[
+            "winapi-i686-pc-windows-gnu 0.4.0",
+            "winapi-x86_64-pc-windows-gnu 0.4.0",
+        ],
+    ),
+    (
+        "winapi-i686-pc-windows-gnu",
+        "0.4.0",
+        "ac3b87c63620426dd9b991e5ce0329eff545bccbbb34f3be09ff6fb6ab51b7b6",
+        &[],
+    ),
+    (
+        "winapi-x86_64-pc-windows-gnu",
+        "0.4.0",
+        "712e227841d057c1ee1cd2fb22fa7e5a5461ae8e48fa2ca79ec42cfc1931183f",
+        &[],
+    ),
+];
 
 pub struct CasperlabsContract<'a> {
     pub contract: &'a Contract,
-    pub visited: u32
+    pub ns: &'a Namespace,
+    pub visited: u32,
+    pub error_codes: Vec<ErrorCode<'a>>,
+    /// Generate the entry points and the installer with the macros of
+    /// `casperlabs_contract_macro`, rather than explicitly
+    pub contract_macro: bool,
+    /// The helpers in the footer which are only rendered if the generated
+    /// code uses them
+    helpers: RefCell<BTreeSet<&'static str>>,
+    /// The locals of the function being rendered which are declared at the
+    /// top of its body, and are assigned where they are set
+    hoisted: RefCell<BTreeSet<usize>>,
+}
+
+/// A call to `require()`, `revert()` or `assert()`, which reverts with the
+/// `ApiError::User` code of its reason string, see `number_error_codes()`.
+/// Failures which do not come from such a call, like an array index out of
+/// bounds, revert with code 0.
+#[derive(Clone)]
+pub struct ErrorCode<'a> {
+    pub code: u16,
+    pub reason: Option<String>,
+    pub loc: pt::Loc,
+    instr: &'a Instr,
+}
+
+#[derive(Serialize)]
+struct ErrorCodeJson {
+    code: u16,
+    reason: Option<String>,
+    locations: Vec<ErrorLocationJson>,
+}
+
+#[derive(Serialize)]
+#[allow(non_snake_case)]
+struct ErrorLocationJson {
+    location: String,
+    sourceLocation: LocJson,
+}
+
+#[derive(Serialize)]
+struct SchemaJson {
+    contracts: Vec<ContractSchemaJson>,
+    enums: Vec<EnumSchemaJson>,
+}
+
+#[derive(Serialize)]
+#[allow(non_snake_case)]
+struct ContractSchemaJson {
+    name: String,
+    entryPoints: Vec<EntryPointSchemaJson>,
+    events: Vec<EventSchemaJson>,
+}
+
+#[derive(Serialize)]
+struct EntryPointSchemaJson {
+    name: String,
+    inputs: Vec<ParamSchemaJson>,
+    outputs: Vec<ParamSchemaJson>,
+}
+
+#[derive(Serialize)]
+struct EventSchemaJson {
+    name: String,
+    fields: Vec<ParamSchemaJson>,
+}
+
+#[derive(Serialize)]
+struct ParamSchemaJson {
+    name: String,
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+#[derive(Serialize)]
+struct EnumSchemaJson {
+    name: String,
+    variants: Vec<String>,
 }
 
 impl<'a> CasperlabsContract<'a> {
-    pub fn new(contract: &'a Contract) -> Self {
-        CasperlabsContract { contract, visited: 0u32 }
+    pub fn new(contract: &'a Contract, ns: &'a Namespace) -> Self {
+        let mut result = CasperlabsContract::without_error_codes(contract, ns);
+        let reasons = result.crate_reasons();
+        result.number_error_codes(&reasons);
+        result
+    }
+
+    /// The contract with its calls to `require()`, `revert()` and `assert()`,
+    /// whose codes are not numbered yet
+    fn without_error_codes(contract: &'a Contract, ns: &'a Namespace) -> Self {
+        let mut result = CasperlabsContract {
+            contract,
+            ns,
+            visited: 0u32,
+            error_codes: Vec::new(),
+            contract_macro: true,
+            helpers: RefCell::new(BTreeSet::new()),
+            hoisted: RefCell::new(BTreeSet::new()),
+        };
+        result.error_codes = result.find_error_codes();
+        result
+    }
+
+    /// The calls to `require()`, `revert()` and `assert()`, and the reason
+    /// string of each
+    fn find_error_codes(&self) -> Vec<ErrorCode<'a>> {
+        let mut result = Vec::new();
+        let contract = self.contract;
+        for function in self.functions() {
+            let cfg = match &function.cfg {
+                Some(cfg) => cfg,
+                None => continue,
+            };
+            for instr in cfg.bb.iter().flat_map(|bb| bb.instr.iter()) {
+                let args = match instr {
+                    Instr::Call { func, args, .. }
+                        if is_helper_fn(&contract.functions[*func].signature) =>
+                    {
+                        args
+                    }
+                    _ => continue,
+                };
+                let reason = match args.last() {
+                    Some(Expression::AllocDynamicArray(_, Type::String, _, Some(bytes))) => {
+                        Some(String::from_utf8_lossy(bytes).to_string())
+                    }
+                    _ => None,
+                };
+                let loc = match args.last() {
+                    Some(arg) => arg.loc(),
+                    None => function.loc,
+                };
+                result.push(ErrorCode {
+                    code: 0,
+                    reason,
+                    loc,
+                    instr,
+                });
+            }
+        }
+        result
+    }
+
+    /// The distinct reason strings of the contract, and of the contracts it
+    /// creates, which are part of its crate, in the order in which they first
+    /// appear in the source
+    fn crate_reasons(&self) -> Vec<String> {
+        let mut errors = self.error_codes.clone();
+        for contract_no in self.created_contracts() {
+            let created =
+                CasperlabsContract::without_error_codes(&self.ns.contracts[contract_no], self.ns);
+            errors.extend(created.error_codes);
+        }
+        errors.sort_by_key(|error| (error.loc.0, error.loc.1));
+        let mut seen = HashSet::new();
+        errors
+            .into_iter()
+            .filter_map(|error| error.reason)
+            .filter(|reason| seen.insert(reason.clone()))
+            .collect()
+    }
+
+    /// Number the error codes of the contract after the reason strings of its
+    /// crate. Code 1 is for the calls without a reason, and the reasons are
+    /// numbered from 2 in order of first appearance, so that the codes of a
+    /// crate never clash, and a reason keeps its code when reasons are added
+    /// after it. The reasons beyond `u16::MAX` are left at code 0, and
+    /// reported by `render()`.
+    fn number_error_codes(&mut self, reasons: &[String]) {
+        let codes: HashMap<&String, u16> = reasons.iter().zip(2..=u16::MAX).collect();
+        for error in &mut self.error_codes {
+            error.code = match &error.reason {
+                Some(reason) => codes.get(reason).cloned().unwrap_or(0),
+                None => 1,
+            };
+        }
+    }
+
+    /// The error codes of the contract, and of the contracts it creates, which
+    /// are part of its crate
+    fn crate_error_codes(&self) -> Vec<ErrorCode<'a>> {
+        let reasons = self.crate_reasons();
+        let mut result = self.error_codes.clone();
+        for contract_no in self.created_contracts() {
+            let mut created =
+                CasperlabsContract::without_error_codes(&self.ns.contracts[contract_no], self.ns);
+            created.number_error_codes(&reasons);
+            result.extend(created.error_codes);
+        }
+        result
+    }
+
+    /// A JSON list of the user error codes with which the contract reverts,
+    /// and the reason string and source locations of each
+    pub fn render_error_manifest(&self, cache: &FileCache) -> String {
+        let mut codes: Vec<ErrorCodeJson> = Vec::new();
+        for error in &self.crate_error_codes() {
+            let location = ErrorLocationJson {
+                location: location(cache, error.loc),
+                sourceLocation: LocJson {
+                    file: cache.get_file_name(error.loc.0).to_string(),
+                    start: error.loc.1,
+                    end: error.loc.2,
+                },
+            };
+            match codes.iter_mut().find(|json| json.code == error.code) {
+                Some(json) => json.locations.push(location),
+                None => codes.push(ErrorCodeJson {
+                    code: error.code,
+                    reason: error.reason.clone(),
+                    locations: vec![location],
+                }),
+            }
+        }
+        codes.sort_by_key(|json| json.code);
+        serde_json::to_string_pretty(&codes).unwrap()
+    }
+
+    /// A JSON description of the entry points and events of the contract, and
+    /// of the contracts it creates, with the variants of the enums they use,
+    /// since enums are passed and stored as a `u8`. The types are named as in
+    /// Solidity.
+    pub fn render_schema(&self) -> String {
+        let mut contracts = vec![self.contract_schema()];
+        for contract_no in self.created_contracts() {
+            let mut created =
+                CasperlabsContract::without_error_codes(&self.ns.contracts[contract_no], self.ns);
+            created.contract_macro = false;
+            contracts.push(created.contract_schema());
+        }
+        let enums = self
+            .used_enums()
+            .into_iter()
+            .map(|enum_no| {
+                let def = &self.ns.enums[enum_no];
+                EnumSchemaJson {
+                    name: def.print_to_string(),
+                    variants: enum_variants(def)
+                        .into_iter()
+                        .map(|(name, _)| name.clone())
+                        .collect(),
+                }
+            })
+            .collect();
+        serde_json::to_string_pretty(&SchemaJson { contracts, enums }).unwrap()
+    }
+
+    fn contract_schema(&self) -> ContractSchemaJson {
+        let param = |name: String, ty: &Type| ParamSchemaJson {
+            name,
+            ty: ty.to_string(self.ns),
+        };
+        let entry_points = self
+            .named_entry_points()
+            .into_iter()
+            .map(|(name, function)| {
+                let mut inputs: Vec<ParamSchemaJson> = (0..function.params.len())
+                    .map(|arg| {
+                        let name = if self.contract_macro {
+                            self.render_param_name(function, arg)
+                        } else {
+                            function.params[arg].name.clone()
+                        };
+                        param(name, &function.params[arg].ty)
+                    })
+                    .collect();
+                // the motes paid to the contract
+                if function.is_payable() {
+                    inputs.push(ParamSchemaJson {
+                        name: "purse".to_string(),
+                        ty: "URef".to_string(),
+                    });
+                }
+                EntryPointSchemaJson {
+                    name,
+                    inputs,
+                    outputs: function
+                        .returns
+                        .iter()
+                        .map(|ret| param(ret.name.clone(), &ret.ty))
+                        .collect(),
+                }
+            })
+            .collect();
+        let events = self
+            .contract
+            .events
+            .iter()
+            .map(|event| EventSchemaJson {
+                name: event.name.clone(),
+                fields: event
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field)| match field.name.as_str() {
+                        "" => param(format!("{}", i), &field.ty),
+                        name => param(name.to_string(), &field.ty),
+                    })
+                    .collect(),
+            })
+            .collect();
+        ContractSchemaJson {
+            name: self.contract.name.clone(),
+            entryPoints: entry_points,
+            events,
+        }
     }
 
     // Api for Solang's Contract.
 
-    pub fn functions(&self) -> Vec<&FunctionDecl> {
-        self.contract.functions.iter()
+    pub fn functions(&self) -> Vec<&'a FunctionDecl> {
+        self.contract
+            .functions
+            .iter()
             .filter(|f| !is_blacklisted_fn(&f.signature.to_string()))
+            // functions without a body are only declared
+            .filter(|f| f.cfg.is_some())
             .collect()
     }
 
-    pub fn variable_name(&self, id: usize) -> Option<String> {
-        println!("// var: {:?}", id);
-        // println!("// all vars: {:?}", self.contract.variables);
-        match self.contract.variables.get(id) {
-            Some(variable) => {
-                let name = variable.name.clone();
-                if name == MSG_SENDER {
-                    Some(GET_CALLER.to_string())
-                } else {
-                    Some(format!("\"{}\"", name))
+    /// The name of the storage variable at the given slot
+    pub fn variable_name(&self, slot: usize) -> Option<String> {
+        self.contract
+            .variables
+            .iter()
+            .find(|variable| match &variable.var {
+                ContractVariableType::Storage(n) => n.to_usize() == Some(slot),
+                _ => false,
+            })
+            .map(|variable| variable.name.clone())
+    }
+
+    // Render functions
+
+    /// Render the contract as Rust. Any construct which cannot be transpiled is
+    /// reported as an error at its location in the Solidity source.
+    pub fn render(&self) -> Result<String, Vec<Output>> {
+        let mut errors = Vec::new();
+        let mut items = self.render_enums();
+        items.extend(self.render_structs(&mut errors));
+        if self.contract_macro {
+            items.push(Item::Mod(Module {
+                attrs: vec![Attribute::new("casperlabs_contract", &[])],
+                name: self.contract.name.clone(),
+                items: self.render_functions(&mut errors),
+            }));
+        } else {
+            items.extend(self.render_explicit(&mut errors));
+            items.push(Item::Fn(self.render_call()));
+        }
+        let mut entry_points: Vec<String> = self
+            .named_entry_points()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        let mut installers = Vec::new();
+        if !self.contract_macro {
+            installers.push(self.contract);
+        }
+        // each reason string of the crate needs a code of its own
+        let reasons = self.crate_reasons();
+        if reasons.len() > MAX_REASONS {
+            let reason = &reasons[MAX_REASONS];
+            let loc = self
+                .crate_error_codes()
+                .into_iter()
+                .filter(|error| error.reason.as_ref() == Some(reason))
+                .map(|error| error.loc)
+                .min_by_key(|loc| (loc.0, loc.1))
+                .unwrap();
+            errors.push(Output::error(
+                loc,
+                format!(
+                    "crate of contract ‘{}’ has {} distinct reason strings, but only {} user error codes",
+                    self.contract.name,
+                    reasons.len(),
+                    MAX_REASONS
+                ),
+            ));
+        }
+        // contracts created with `new` are installed by their creator, so their
+        // code is part of the same wasm module, and their entry points must not
+        // clash with any other
+        let mut created_contracts = Vec::new();
+        for contract_no in self.created_contracts() {
+            let mut created =
+                CasperlabsContract::without_error_codes(&self.ns.contracts[contract_no], self.ns);
+            created.number_error_codes(&reasons);
+            created.contract_macro = false;
+            installers.push(created.contract);
+            for function in created.entry_points() {
+                let name = created.render_entry_point_name(function);
+                if entry_points.contains(&name) {
+                    errors.push(Output::error(function.loc, format!(
+                        "entry point ‘{}’ of contract ‘{}’ clashes with another entry point of the crate of contract ‘{}’",
+                        name, created.contract.name, self.contract.name
+                    )));
                 }
-            },
-            None => None
+                entry_points.push(name);
+            }
+            items.extend(created.render_explicit(&mut errors));
+            self.helpers
+                .borrow_mut()
+                .extend(created.helpers.replace(BTreeSet::new()));
+            created_contracts.push(created);
+        }
+        let footer = self.render_footer();
+        // the functions at the root of the crate are imported into the module
+        // of each contract, and exported alongside its entry points
+        let mut reserved: Vec<String> = footer
+            .iter()
+            .filter_map(|item| match item {
+                Item::Fn(function) => Some(function.name.clone()),
+                _ => None,
+            })
+            .collect();
+        // a helper may be named like the installer of a contract, like `new_key`
+        // for a contract named `key`
+        for contract in &installers {
+            let name = format!("new_{}", contract.name);
+            if reserved.contains(&name) {
+                errors.push(Output::error(contract.loc, format!(
+                    "installer ‘{}’ of contract ‘{}’ clashes with the generated function ‘{}’ of the crate of contract ‘{}’",
+                    name, contract.name, name, self.contract.name
+                )));
+            }
+        }
+        reserved.push("call".to_string());
+        reserved.extend(
+            installers
+                .iter()
+                .map(|contract| format!("new_{}", contract.name)),
+        );
+        self.check_function_names(&reserved, self, &mut errors);
+        self.check_identifiers(&mut errors);
+        for created in &created_contracts {
+            created.check_function_names(&reserved, self, &mut errors);
+            created.check_identifiers(&mut errors);
+        }
+        let footer = used_helpers(footer, &items);
+        items.extend(footer);
+        let mut file = rust::File {
+            attrs: vec![
+                Attribute::new("no_main", &[]),
+                Attribute::new("allow", &["non_snake_case"]),
+            ],
+            items: self.render_imports(&rust::names(&items)),
+        };
+        if self.contract_macro {
+            file.attrs
+                .insert(1, Attribute::new("allow", &["unused_imports"]));
+        }
+        file.items.extend(items);
+        // the module of a contract only imports the root of the crate if it
+        // refers to it, other than to the entry points
+        let root: BTreeSet<String> = file
+            .items
+            .iter()
+            .flat_map(|item| match item {
+                Item::Use(_, names) => names.clone(),
+                Item::Fn(Function { name, linkage, .. }) if *linkage != Linkage::Export => {
+                    vec![name.clone()]
+                }
+                Item::Struct(Struct { name, .. }) | Item::Enum(Enum { name, .. }) => {
+                    vec![name.clone()]
+                }
+                _ => Vec::new(),
+            })
+            .collect();
+        for item in file.items.iter_mut() {
+            if let Item::Mod(module) = item {
+                if rust::names(&module.items).is_disjoint(&root) {
+                    module.items.retain(|item| !matches!(item, Item::Use(..)));
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(print_file(&file))
+        } else {
+            Err(errors)
         }
     }
 
-    // Render functions
+    /// Report the functions whose names clash with the functions which the
+    /// crate of the contract being rendered generates at its root
+    fn check_function_names(
+        &self,
+        reserved: &[String],
+        crate_contract: &CasperlabsContract,
+        errors: &mut Vec<Output>,
+    ) {
+        for function in self.functions() {
+            let names = [
+                self.render_function_name(function),
+                self.render_internal_name(function),
+            ];
+            if let Some(name) = names.iter().find(|name| reserved.contains(name)) {
+                errors.push(Output::error(function.loc, format!(
+                    "function ‘{}’ of contract ‘{}’ clashes with the generated function ‘{}’ of the crate of contract ‘{}’",
+                    function.name, self.contract.name, name, crate_contract.contract.name
+                )));
+            }
+        }
+    }
+
+    /// Report the names which the generated code uses as identifiers, but which
+    /// cannot be one, like `self`. Local variables are renamed instead.
+    fn check_identifiers(&self, errors: &mut Vec<Output>) {
+        let mut names = vec![(&self.contract.name, self.contract.loc)];
+        names.extend(
+            self.functions()
+                .into_iter()
+                .map(|function| (&function.name, function.loc)),
+        );
+        for struct_no in self.used_structs() {
+            let def = &self.ns.structs[struct_no];
+            names.push((&def.name, def.loc));
+            names.extend(def.fields.iter().map(|field| (&field.name, field.loc)));
+        }
+        for enum_no in self.used_enums() {
+            let def = &self.ns.enums[enum_no];
+            // the enum has no location of its own, so it is reported at its first value
+            if let Some((loc, _)) = def.values.values().min_by_key(|(_, value)| *value) {
+                names.push((&def.name, *loc));
+            }
+            let mut values: Vec<_> = def.values.iter().collect();
+            values.sort_by_key(|(_, (_, value))| *value);
+            names.extend(values.into_iter().map(|(name, (loc, _))| (name, *loc)));
+        }
+        for (name, loc) in names {
+            if rust::is_path_keyword(name) {
+                self.unsupported::<()>(&loc, &format!("the name ‘{}’", name), errors);
+            }
+        }
+    }
 
-    pub fn render(&self) -> String {
-        let mut result = Vec::<String>::new();
-        result.push(self.render_header());
-        result.push(self.render_functions());
-        result.push(self.render_footer());
-        result.join("\n")
+    /// Render a Cargo project for the contract, which can be built with
+    /// `cargo build --release --target wasm32-unknown-unknown`. The result
+    /// is a list of file names relative to the project directory, and their
+    /// contents.
+    pub fn render_crate(&self) -> Result<Vec<(String, String)>, Vec<Output>> {
+        let code = self.render()?;
+        Ok(vec![
+            ("Cargo.toml".to_string(), self.render_cargo_toml()),
+            ("Cargo.lock".to_string(), self.render_cargo_lock()),
+            (
+                "rust-toolchain".to_string(),
+                format!("{}\n", RUST_TOOLCHAIN),
+            ),
+            ("src/main.rs".to_string(), code),
+        ])
+    }
+
+    fn crate_name(&self) -> String {
+        self.contract
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect()
     }
 
-    fn render_header(&self) -> String {
-        format!("
-            #![no_main]
-            #![allow(unused_imports)]
-            #![allow(unused_parens)]
-            #![allow(non_snake_case)]
+    fn render_cargo_toml(&self) -> String {
+        format!("[package]
+name = \"{name}\"
+version = \"0.1.0\"
+edition = \"2018\"
 
-            {imports}
+[dependencies]
+casperlabs-contract = \"={contract_version}\"
+casperlabs-types = \"={types_version}\"
+{macro_dependency}{hash_dependencies}
+[[bin]]
+name = \"{name}\"
+path = \"src/main.rs\"
+bench = false
+doctest = false
+test = false
 
-            #[casperlabs_contract]
-            mod {name} {{
-            ", 
-            name = self.contract.name,
-            imports = self.render_imports()
+[features]
+default = [\"casperlabs-contract/std\", \"casperlabs-types/std\", \"casperlabs-contract/test-support\"]
+
+[profile.release]
+lto = true
+",
+            name = self.crate_name(),
+            contract_version = CASPERLABS_CONTRACT_VERSION,
+            types_version = CASPERLABS_TYPES_VERSION,
+            macro_dependency = if self.contract_macro {
+                format!("casperlabs_contract_macro = \"={}\"\n", CASPERLABS_CONTRACT_MACRO_VERSION)
+            } else {
+                String::new()
+            },
+            hash_dependencies = self
+                .hash_dependencies()
+                .iter()
+                .map(|(name, version)| format!(
+                    "{} = {{ version = \"={}\", default-features = false }}\n",
+                    name, version
+                ))
+                .collect::<String>()
         )
     }
 
-    fn render_imports(&self) -> String {
-        format!("
-            extern crate alloc;
-
-            use core::convert::TryInto;
-            use alloc::{{collections::{{BTreeSet, BTreeMap}}, string::String}};
-
-            use casperlabs_contract_macro::{{casperlabs_constructor, casperlabs_contract, casperlabs_method}};
-            use casperlabs_contract::{{
-                contract_api::{{runtime, storage}},
-                unwrap_or_revert::UnwrapOrRevert,
-            }};
-            use casperlabs_types::{{
-                runtime_args, CLValue, CLTyped, CLType, Group, Parameter, RuntimeArgs, URef, U256, ApiError,
-                bytesrepr::{{ToBytes, FromBytes}}, account::AccountHash,
-                contracts::{{EntryPoint, EntryPointAccess, EntryPointType, EntryPoints}},
-            }};
-        ")
-    }
-
-    fn render_footer(&self) -> String {
-        format!("
-            }}
-
-            fn get_key<T: FromBytes + CLTyped + Default>(name: &str) -> T {{
-                match runtime::get_key(name) {{
-                    None => Default::default(),
-                    Some(value) => {{
-                        let key = value.try_into().unwrap_or_revert();
-                        storage::read(key).unwrap_or_revert().unwrap_or_revert()
-                    }}
-                }}
-            }}
-
-            fn set_key<T: ToBytes + CLTyped>(name: &str, value: T) {{
-                match runtime::get_key(name) {{
-                    Some(key) => {{
-                        let key_ref = key.try_into().unwrap_or_revert();
-                        storage::write(key_ref, value);
-                    }}
-                    None => {{
-                        let key = storage::new_uref(value).into();
-                        runtime::put_key(name, key);
-                    }}
-                }}
-            }}
-
-            fn new_key(a: &str, b: AccountHash) -> String {{
-                format!(\"{{}}_{{}}\", a, b)
-            }}
-
-            fn assert(condition: bool) {{
-                if !condition {{
-                    runtime::revert(ApiError::User(1u16));
-                }}
-            }}
-
-            fn revert() {{
-                assert(false);
-            }}
-
-            fn require(condition: bool) {{
-                assert(condition);
-            }}
-        ")
-    }
-
-    fn render_functions(&self) -> String {
-        let mut result = Vec::<String>::new(); 
-        for function in self.functions() {
-            result.push(self.render_function(function));
+    /// The crates of the hash functions which the contract uses, with their
+    /// versions
+    fn hash_dependencies(&self) -> Vec<(&'static str, &'static str)> {
+        let mut result = Vec::new();
+        let hashes = self.used_hashes();
+        if !hashes.is_empty() {
+            result.push(("digest", DIGEST_VERSION));
+        }
+        if hashes.contains(&HashTy::Blake2_256) {
+            result.push(("blake2", BLAKE2_VERSION));
         }
-        result.join("\n")
+        if hashes.contains(&HashTy::Keccak256) {
+            result.push(("sha3", SHA3_VERSION));
+        }
+        if hashes.contains(&HashTy::Sha256) {
+            result.push(("sha2", SHA2_VERSION));
+        }
+        if hashes.contains(&HashTy::Ripemd160) {
+            result.push(("ripemd160", RIPEMD160_VERSION));
+        }
+        result
     }
 
-    fn render_function(&self, function: &FunctionDecl) -> String {
-        format!("
-            {attr}
-            fn {name}({args}) {{ {body}
-            }}",
-            attr = self.render_function_macro_name(&function),
-            name = self.render_function_name(&function),
-            args = self.render_function_args(&function),
-            body = self.render_function_body(&function)
+    /// The lock file of the crate, with the packages of `LOCKED_PACKAGES`
+    /// which it depends on. `casperlabs_contract_macro` is not locked, as it
+    /// is not published on crates.io.
+    fn render_cargo_lock(&self) -> String {
+        let mut dependencies = vec![
+            ("casperlabs-contract", CASPERLABS_CONTRACT_VERSION),
+            ("casperlabs-types", CASPERLABS_TYPES_VERSION),
+        ];
+        dependencies.extend(self.hash_dependencies());
+
+        // the locked packages which the crate depends on, directly or not
+        let mut locked = BTreeSet::new();
+        let mut todo = dependencies.clone();
+        while let Some(package) = todo.pop() {
+            if locked.insert(package) {
+                todo.extend(locked_package(package).3.iter().map(|d| package_id(d)));
+            }
+        }
+
+        // a dependency only has its version if several versions are locked
+        let reference = |(name, version): (&str, &str)| {
+            if locked.iter().filter(|(n, _)| *n == name).count() > 1 {
+                format!(" \"{} {}\",\n", name, version)
+            } else {
+                format!(" \"{}\",\n", name)
+            }
+        };
+
+        let mut packages: Vec<((&str, &str), String)> = locked
+            .iter()
+            .map(|package| {
+                let (name, version, checksum, dependencies) = locked_package(*package);
+                let mut entry = format!(
+                    "[[package]]\nname = \"{}\"\nversion = \"{}\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\nchecksum = \"{}\"\n",
+                    name, version, checksum
+                );
+                if !dependencies.is_empty() {
+                    entry.push_str("dependencies = [\n");
+                    for dependency in dependencies.iter() {
+                        entry.push_str(&reference(package_id(dependency)));
+                    }
+                    entry.push_str("]\n");
+                }
+                (*package, entry)
+            })
+            .collect();
+
+        let name = self.crate_name();
+        let mut entry = format!(
+            "[[package]]\nname = \"{}\"\nversion = \"0.1.0\"\ndependencies = [\n",
+            name
+        );
+        dependencies.sort();
+        for dependency in dependencies {
+            entry.push_str(&reference(dependency));
+        }
+        entry.push_str("]\n");
+        packages.push(((&name, "0.1.0"), entry));
+        packages.sort_by(|a, b| a.0.cmp(&b.0));
+
+        format!(
+            "# This file is automatically @generated by Cargo.\n# It is not intended for manual editing.\n{}",
+            packages
+                .into_iter()
+                .map(|(_, entry)| entry)
+                .collect::<Vec<String>>()
+                .join("\n")
         )
     }
 
-    fn render_function_macro_name(&self, function: &FunctionDecl) -> String {
-        match (function.is_constructor(), function.is_public()) {
-            (true, true) => "#[casperlabs_constructor]",
-            (false, true) => "#[casperlabs_method]",
-            _ => ""
-        }.to_string()
+    /// The imports of the names which the items of the crate refer to; a
+    /// trait is imported if its methods are called. With the contract macro,
+    /// everything is imported.
+    fn render_imports(&self, names: &BTreeSet<String>) -> Vec<Item> {
+        let mut result = vec![
+            Item::ExternCrate("alloc".to_string()),
+            Item::use_("core::convert", &["TryInto"]),
+            Item::use_("alloc::collections", &["BTreeSet", "BTreeMap"]),
+            Item::use_("alloc::string", &["String"]),
+        ];
+        if self.contract_macro {
+            result.push(Item::use_(
+                "casperlabs_contract_macro",
+                &[
+                    "casperlabs_constructor",
+                    "casperlabs_contract",
+                    "casperlabs_method",
+                ],
+            ));
+        }
+        result.extend(vec![
+            Item::use_(
+                "casperlabs_contract::contract_api",
+                &["account", "runtime", "storage", "system"],
+            ),
+            Item::use_("casperlabs_contract::unwrap_or_revert", &["UnwrapOrRevert"]),
+            Item::use_(
+                "casperlabs_types",
+                &[
+                    "bytesrepr",
+                    "runtime_args",
+                    "CLValue",
+                    "CLTyped",
+                    "CLType",
+                    "Group",
+                    "Parameter",
+                    "Key",
+                    "RuntimeArgs",
+                    "URef",
+                    "U128",
+                    "U256",
+                    "U512",
+                    "ApiError",
+                    "ContractHash",
+                ],
+            ),
+            Item::use_("casperlabs_types::bytesrepr", &["ToBytes", "FromBytes"]),
+            Item::use_("casperlabs_types::account", &["AccountHash"]),
+            Item::use_(
+                "casperlabs_types::contracts",
+                &[
+                    "EntryPoint",
+                    "EntryPointAccess",
+                    "EntryPointType",
+                    "EntryPoints",
+                ],
+            ),
+        ]);
+        let hashes = self.used_hashes();
+        let mut digest = Vec::new();
+        if hashes.iter().any(|hash| *hash != HashTy::Blake2_256) {
+            digest.push("Digest");
+        }
+        // blake2b has a variable output size
+        if hashes.contains(&HashTy::Blake2_256) {
+            digest.extend(&["Update", "VariableOutput"]);
+        }
+        if !digest.is_empty() {
+            result.push(Item::use_("digest", &digest));
+        }
+        // the code which the macros expand to refers to the imports
+        if self.contract_macro {
+            return result;
+        }
+        let used = |name: &String| match name.as_str() {
+            "TryInto" => names.contains("try_into"),
+            "UnwrapOrRevert" => names.contains("unwrap_or_revert"),
+            "ToBytes" => ["ToBytes", "to_bytes", "into_bytes", "serialized_length"]
+                .iter()
+                .any(|name| names.contains(*name)),
+            "FromBytes" => names.contains("FromBytes") || names.contains("from_bytes"),
+            // the macro expands to the name
+            "RuntimeArgs" => names.contains("runtime_args"),
+            "Digest" | "Update" | "VariableOutput" => true,
+            _ => names.contains(name),
+        };
+        result
+            .into_iter()
+            .filter_map(|item| match item {
+                Item::Use(path, imported) => {
+                    let imported: Vec<String> = imported.into_iter().filter(used).collect();
+                    if imported.is_empty() {
+                        None
+                    } else {
+                        Some(Item::Use(path, imported))
+                    }
+                }
+                item => Some(item),
+            })
+            .collect()
     }
 
-    fn render_function_name(&self, function: &FunctionDecl) -> String {
+    /// Rust enums for the Solidity enums used by the contract. They are
+    /// serialized as their `u8` discriminant, and deserializing any other byte
+    /// fails, so an entry point reverts on an argument out of range.
+    fn render_enums(&self) -> Vec<Item> {
+        let mut result = Vec::new();
+        for enum_no in self.used_enums() {
+            let def = &self.ns.enums[enum_no];
+            let name = self.render_enum_name(enum_no);
+            let ty = rust::Type::path(&name);
+            let variants = enum_variants(def);
+            let variant = |name: &str, value: &str| Expr::path(&format!("{}::{}", name, value));
+            let method = |name: &str,
+                          receiver: bool,
+                          params: Vec<Param>,
+                          returns: rust::Type,
+                          body: Block| Function {
+                attrs: Vec::new(),
+                linkage: Linkage::Private,
+                name: name.to_string(),
+                generics: Vec::new(),
+                receiver,
+                params,
+                returns: Some(returns),
+                body,
+            };
+            let discriminant = || {
+                Expr::Cast(
+                    Box::new(Expr::unary(UnOp::Deref, Expr::SelfValue)),
+                    rust::Type::path("u8"),
+                )
+            };
+            let bytes_ty = rust::Type::Ref(Box::new(rust::Type::Slice(Box::new(
+                rust::Type::path("u8"),
+            ))));
+            let result_ty = |ty: rust::Type| {
+                rust::Type::generic("Result", vec![ty, rust::Type::path("bytesrepr::Error")])
+            };
+            // the variants of the discriminants, followed by an arm for any other value
+            let arms = |other: Expr| {
+                let mut arms: Vec<Arm> = variants
+                    .iter()
+                    .map(|(value, n)| Arm {
+                        pat: Pat::Lit(Lit::Int(BigInt::from(*n), None)),
+                        body: variant(&name, value),
+                    })
+                    .collect();
+                arms.push(Arm {
+                    pat: Pat::Wild,
+                    body: other,
+                });
+                arms
+            };
+
+            result.push(Item::Enum(Enum {
+                attrs: vec![
+                    Attribute::new("derive", &["Clone", "Copy", "Debug", "PartialEq"]),
+                    Attribute::new("repr", &["u8"]),
+                ],
+                name: name.clone(),
+                variants: variants
+                    .iter()
+                    .map(|(value, _)| value.to_string())
+                    .collect(),
+            }));
+            // like any other value, an enum defaults to its first value
+            result.push(Item::Impl(Impl {
+                trait_: Some(Path::new("Default")),
+                ty: ty.clone(),
+                fns: vec![method(
+                    "default",
+                    false,
+                    vec![],
+                    ty.clone(),
+                    Block::value(vec![], variant(&name, variants[0].0)),
+                )],
+            }));
+            // explicit conversions from integers revert if the value is out of range
+            result.push(Item::Impl(Impl {
+                trait_: Some(Path::new("From").with_args(vec![rust::Type::path("u8")])),
+                ty: ty.clone(),
+                fns: vec![method(
+                    "from",
+                    false,
+                    vec![Param::new("value", rust::Type::path("u8"))],
+                    ty.clone(),
+                    Block::value(
+                        vec![],
+                        Expr::Match(
+                            Box::new(Expr::path("value")),
+                            arms(Expr::call(
+                                Path::new("runtime::revert"),
+                                vec![Expr::call(
+                                    Path::new("ApiError::User"),
+                                    vec![Expr::int(&BigInt::zero())],
+                                )],
+                            )),
+                        ),
+                    ),
+                )],
+            }));
+            result.push(Item::Impl(Impl {
+                trait_: Some(Path::new("CLTyped")),
+                ty: ty.clone(),
+                fns: vec![method(
+                    "cl_type",
+                    false,
+                    vec![],
+                    rust::Type::path("CLType"),
+                    Block::value(vec![], Expr::path("CLType::U8")),
+                )],
+            }));
+            result.push(Item::Impl(Impl {
+                trait_: Some(Path::new("ToBytes")),
+                ty: ty.clone(),
+                fns: vec![
+                    method(
+                        "to_bytes",
+                        true,
+                        vec![],
+                        result_ty(rust::Type::generic("Vec", vec![rust::Type::path("u8")])),
+                        Block::value(vec![], discriminant().method("to_bytes", vec![])),
+                    ),
+                    method(
+                        "serialized_length",
+                        true,
+                        vec![],
+                        rust::Type::path("usize"),
+                        Block::value(vec![], discriminant().method("serialized_length", vec![])),
+                    ),
+                ],
+            }));
+            result.push(Item::Impl(Impl {
+                trait_: Some(Path::new("FromBytes")),
+                ty: ty.clone(),
+                fns: vec![method(
+                    "from_bytes",
+                    false,
+                    vec![Param::new("bytes", bytes_ty.clone())],
+                    result_ty(rust::Type::Tuple(vec![ty, bytes_ty.clone()])),
+                    Block::value(
+                        vec![
+                            rust::Stmt::Let(
+                                Pat::Tuple(vec![Pat::ident("value"), Pat::ident("bytes")]),
+                                Some(rust::Type::Tuple(vec![
+                                    rust::Type::path("u8"),
+                                    bytes_ty.clone(),
+                                ])),
+                                Some(Expr::Try(Box::new(Expr::call(
+                                    Path::new("FromBytes::from_bytes"),
+                                    vec![Expr::path("bytes")],
+                                )))),
+                            ),
+                            rust::Stmt::let_(
+                                "value",
+                                None,
+                                Expr::Match(
+                                    Box::new(Expr::path("value")),
+                                    arms(Expr::Return(Some(Box::new(Expr::call(
+                                        Path::new("Err"),
+                                        vec![Expr::path("bytesrepr::Error::Formatting")],
+                                    ))))),
+                                ),
+                            ),
+                        ],
+                        Expr::call(
+                            Path::new("Ok"),
+                            vec![Expr::Tuple(vec![Expr::path("value"), Expr::path("bytes")])],
+                        ),
+                    ),
+                )],
+            }));
+        }
+        result
+    }
+
+    /// Rust structs for the Solidity structs used by the contract, with the
+    /// traits needed to store them
+    fn render_structs(&self, errors: &mut Vec<Output>) -> Vec<Item> {
+        let mut result = Vec::new();
+        for struct_no in self.used_structs() {
+            let def = &self.ns.structs[struct_no];
+            let name = self.render_struct_name(struct_no);
+            let ty = rust::Type::path(&name);
+            let mut fields = Vec::new();
+            for field in &def.fields {
+                fields.push((
+                    field.name.clone(),
+                    self.render_type(&field.ty, &field.loc, errors),
+                ));
+            }
+            let method = |name: &str,
+                          receiver: bool,
+                          params: Vec<Param>,
+                          returns: rust::Type,
+                          body: Block| Function {
+                attrs: Vec::new(),
+                linkage: Linkage::Private,
+                name: name.to_string(),
+                generics: Vec::new(),
+                receiver,
+                params,
+                returns: Some(returns),
+                body,
+            };
+            let field = |name: &str| Expr::Field(Box::new(Expr::SelfValue), name.to_string());
+            let bytes_ty = rust::Type::Ref(Box::new(rust::Type::Slice(Box::new(
+                rust::Type::path("u8"),
+            ))));
+            let result_ty = |ty: rust::Type| {
+                rust::Type::generic("Result", vec![ty, rust::Type::path("bytesrepr::Error")])
+            };
+
+            // fields are serialized in order of declaration
+            let mut to_bytes = vec![rust::Stmt::Let(
+                Pat::Ident(true, "result".to_string()),
+                None,
+                Some(Expr::call(Path::new("Vec::new"), vec![])),
+            )];
+            let mut serialized_length: Option<Expr> = None;
+            let mut from_bytes = Vec::new();
+            let mut values = Vec::new();
+            for (i, (name, _)) in fields.iter().enumerate() {
+                to_bytes.push(rust::Stmt::Expr(Expr::path("result").method(
+                    "extend",
+                    vec![Expr::Try(Box::new(field(name).method("to_bytes", vec![])))],
+                )));
+                let length = field(name).method("serialized_length", vec![]);
+                serialized_length = Some(match serialized_length {
+                    Some(sum) => Expr::binary(BinOp::Add, sum, length),
+                    None => length,
+                });
+                // the fields are bound to numbered names, which cannot clash with `bytes`
+                let value = format!("field_{}", i);
+                from_bytes.push(rust::Stmt::Let(
+                    Pat::Tuple(vec![Pat::ident(&value), Pat::ident("bytes")]),
+                    None,
+                    Some(Expr::Try(Box::new(Expr::call(
+                        Path::new("FromBytes::from_bytes"),
+                        vec![Expr::path("bytes")],
+                    )))),
+                ));
+                values.push((name.clone(), Expr::path(&value)));
+            }
+
+            // a field without a default, like an address, is set to its zero
+            // value by an impl of `Default`
+            let derived_default = def.fields.iter().all(|field| self.has_default(&field.ty));
+            let derives: &[&str] = if derived_default {
+                &["Clone", "Debug", "Default", "PartialEq"]
+            } else {
+                &["Clone", "Debug", "PartialEq"]
+            };
+            result.push(Item::Struct(Struct {
+                attrs: vec![Attribute::new("derive", derives)],
+                name: name.clone(),
+                fields,
+            }));
+            if !derived_default {
+                let zeros = def
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        (
+                            field.name.clone(),
+                            self.render_zero(&field.ty, &field.loc, errors),
+                        )
+                    })
+                    .collect();
+                result.push(Item::Impl(Impl {
+                    trait_: Some(Path::new("Default")),
+                    ty: ty.clone(),
+                    fns: vec![method(
+                        "default",
+                        false,
+                        vec![],
+                        ty.clone(),
+                        Block::value(vec![], Expr::Struct(Path::new(&name), zeros)),
+                    )],
+                }));
+            }
+            result.push(Item::Impl(Impl {
+                trait_: Some(Path::new("CLTyped")),
+                ty: ty.clone(),
+                fns: vec![method(
+                    "cl_type",
+                    false,
+                    vec![],
+                    rust::Type::path("CLType"),
+                    Block::value(vec![], Expr::path("CLType::Any")),
+                )],
+            }));
+            result.push(Item::Impl(Impl {
+                trait_: Some(Path::new("ToBytes")),
+                ty: ty.clone(),
+                fns: vec![
+                    method(
+                        "to_bytes",
+                        true,
+                        vec![],
+                        result_ty(rust::Type::generic("Vec", vec![rust::Type::path("u8")])),
+                        Block::value(
+                            to_bytes,
+                            Expr::call(Path::new("Ok"), vec![Expr::path("result")]),
+                        ),
+                    ),
+                    method(
+                        "serialized_length",
+                        true,
+                        vec![],
+                        rust::Type::path("usize"),
+                        Block::value(
+                            vec![],
+                            serialized_length.unwrap_or_else(|| Expr::int(&BigInt::zero())),
+                        ),
+                    ),
+                ],
+            }));
+            result.push(Item::Impl(Impl {
+                trait_: Some(Path::new("FromBytes")),
+                ty: ty.clone(),
+                fns: vec![method(
+                    "from_bytes",
+                    false,
+                    vec![Param::new("bytes", bytes_ty.clone())],
+                    result_ty(rust::Type::Tuple(vec![ty, bytes_ty])),
+                    Block::value(
+                        from_bytes,
+                        Expr::call(
+                            Path::new("Ok"),
+                            vec![Expr::Tuple(vec![
+                                Expr::Struct(Path::new(&name), values),
+                                Expr::path("bytes"),
+                            ])],
+                        ),
+                    ),
+                )],
+            }));
+        }
+        result
+    }
+
+    /// The structs used by the contract and the contracts it creates
+    fn used_structs(&self) -> BTreeSet<usize> {
+        self.used_types()
+            .into_iter()
+            .filter_map(|ty| match ty {
+                Type::Struct(n) => Some(*n),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The enums used by the contract and the contracts it creates
+    fn used_enums(&self) -> BTreeSet<usize> {
+        self.used_types()
+            .into_iter()
+            .filter_map(|ty| match ty {
+                Type::Enum(n) => Some(*n),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The types of storage, function arguments, local variables and event
+    /// fields, and the types which those contain, of this contract and the
+    /// contracts it creates
+    fn used_types(&self) -> Vec<&'a Type> {
+        let mut types = Vec::new();
+        let created = self
+            .created_contracts()
+            .into_iter()
+            .map(|n| &self.ns.contracts[n]);
+        for contract in std::iter::once(self.contract).chain(created) {
+            for variable in &contract.variables {
+                types.push(&variable.ty);
+            }
+            // the builtins are not rendered
+            for function in contract
+                .functions
+                .iter()
+                .filter(|f| !is_blacklisted_fn(&f.signature))
+            {
+                types.extend(function.params.iter().map(|p| &p.ty));
+                types.extend(function.returns.iter().map(|p| &p.ty));
+                if let Some(cfg) = &function.cfg {
+                    types.extend(cfg.vars.iter().map(|v| &v.ty));
+                }
+            }
+            for event in &contract.events {
+                types.extend(event.fields.iter().map(|f| &f.ty));
+            }
+        }
+        let mut structs = BTreeSet::new();
+        let mut result = Vec::new();
+        while let Some(ty) = types.pop() {
+            match ty {
+                Type::Struct(n) if structs.insert(*n) => {
+                    types.extend(self.ns.structs[*n].fields.iter().map(|f| &f.ty));
+                }
+                Type::Array(ty, _) | Type::Ref(ty) | Type::StorageRef(ty) => types.push(ty),
+                Type::Mapping(key, value) => {
+                    types.push(key);
+                    types.push(value);
+                }
+                _ => {}
+            }
+            result.push(ty);
+        }
+        result
+    }
+
+    /// Structs are named after the Solidity struct, prefixed with the contract
+    /// name if more than one struct has that name
+    fn render_struct_name(&self, struct_no: usize) -> String {
+        let def = &self.ns.structs[struct_no];
+        match &def.contract {
+            Some(contract)
+                if self
+                    .ns
+                    .structs
+                    .iter()
+                    .filter(|s| s.name == def.name)
+                    .count()
+                    > 1 =>
+            {
+                format!("{}_{}", contract, def.name)
+            }
+            _ => def.name.clone(),
+        }
+    }
+
+    /// Enums are named like structs
+    fn render_enum_name(&self, enum_no: usize) -> String {
+        let def = &self.ns.enums[enum_no];
+        match &def.contract {
+            Some(contract) if self.ns.enums.iter().filter(|e| e.name == def.name).count() > 1 => {
+                format!("{}_{}", contract, def.name)
+            }
+            _ => def.name.clone(),
+        }
+    }
+
+    /// The hash functions called by the contract, and by the contracts it creates
+    fn used_hashes(&self) -> Vec<HashTy> {
+        let mut contracts = vec![self.contract];
+        contracts.extend(
+            self.created_contracts()
+                .into_iter()
+                .map(|no| &self.ns.contracts[no]),
+        );
+        let mut result = Vec::new();
+        for contract in contracts {
+            for cfg in contract.functions.iter().filter_map(|f| f.cfg.as_ref()) {
+                for instr in cfg.bb.iter().flat_map(|bb| bb.instr.iter()) {
+                    if let Instr::Call { func, .. } = instr {
+                        match builtin_hash(&contract.functions[*func]) {
+                            Some(hash) if !result.contains(hash) => result.push(hash.clone()),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Helper functions used by the generated code
+    fn render_footer(&self) -> Vec<Item> {
+        let get_key = Expr::call(Path::new("runtime::get_key"), vec![Expr::path("name")]);
+        let unwrap_or_revert = |expr: Expr| expr.method("unwrap_or_revert", vec![]);
+        let helper = |name: &str,
+                      generics: Vec<Generic>,
+                      params: Vec<Param>,
+                      returns: Option<rust::Type>,
+                      body: Block| {
+            Item::Fn(Function {
+                attrs: Vec::new(),
+                linkage: Linkage::Private,
+                name: name.to_string(),
+                generics,
+                receiver: false,
+                params,
+                returns,
+                body,
+            })
+        };
+        let generic = |bounds: &[&str]| {
+            vec![Generic {
+                name: "T".to_string(),
+                bounds: bounds.iter().map(|bound| Path::new(bound)).collect(),
+            }]
+        };
+        let name_param = || Param::new("name", rust::Type::Ref(Box::new(rust::Type::path("str"))));
+        let condition_param = || Param::new("condition", rust::Type::path("bool"));
+        let code_param = || Param::new("code", rust::Type::path("u16"));
+        let revert = |code: Expr| {
+            rust::Stmt::Expr(Expr::call(
+                Path::new("runtime::revert"),
+                vec![Expr::call(Path::new("ApiError::User"), vec![code])],
+            ))
+        };
+
+        let mut result = vec![
+            helper(
+                "get_key",
+                generic(&["FromBytes", "CLTyped", "Default"]),
+                vec![name_param()],
+                Some(rust::Type::path("T")),
+                Block::value(
+                    vec![],
+                    Expr::call(
+                        Path::new("get_key_or"),
+                        vec![
+                            Expr::path("name"),
+                            Expr::call(Path::new("Default::default"), vec![]),
+                        ],
+                    ),
+                ),
+            ),
+            // a key which is not set reads as the zero value of its type
+            helper(
+                "get_key_or",
+                generic(&["FromBytes", "CLTyped"]),
+                vec![name_param(), Param::new("zero", rust::Type::path("T"))],
+                Some(rust::Type::path("T")),
+                Block::value(
+                    vec![],
+                    Expr::Match(
+                        Box::new(get_key.clone()),
+                        vec![
+                            Arm {
+                                pat: Pat::Path(Path::new("None")),
+                                body: Expr::path("zero"),
+                            },
+                            Arm {
+                                pat: Pat::tuple_struct("Some", vec![Pat::ident("value")]),
+                                body: Expr::Block(Block::value(
+                                    vec![rust::Stmt::let_(
+                                        "key",
+                                        None,
+                                        unwrap_or_revert(
+                                            Expr::path("value").method("try_into", vec![]),
+                                        ),
+                                    )],
+                                    unwrap_or_revert(unwrap_or_revert(Expr::call(
+                                        Path::new("storage::read"),
+                                        vec![Expr::path("key")],
+                                    ))),
+                                )),
+                            },
+                        ],
+                    ),
+                ),
+            ),
+            helper(
+                "set_key",
+                generic(&["ToBytes", "CLTyped"]),
+                vec![name_param(), Param::new("value", rust::Type::path("T"))],
+                None,
+                Block::new(vec![rust::Stmt::Expr(Expr::Match(
+                    Box::new(get_key),
+                    vec![
+                        Arm {
+                            pat: Pat::tuple_struct("Some", vec![Pat::ident("key")]),
+                            body: Expr::Block(Block::new(vec![
+                                rust::Stmt::let_(
+                                    "key_ref",
+                                    None,
+                                    unwrap_or_revert(Expr::path("key").method("try_into", vec![])),
+                                ),
+                                rust::Stmt::Expr(Expr::call(
+                                    Path::new("storage::write"),
+                                    vec![Expr::path("key_ref"), Expr::path("value")],
+                                )),
+                            ])),
+                        },
+                        Arm {
+                            pat: Pat::Path(Path::new("None")),
+                            body: Expr::Block(Block::new(vec![
+                                rust::Stmt::let_(
+                                    "key",
+                                    None,
+                                    Expr::call(
+                                        Path::new("storage::new_uref"),
+                                        vec![Expr::path("value")],
+                                    )
+                                    .method("into", vec![]),
+                                ),
+                                rust::Stmt::Expr(Expr::call(
+                                    Path::new("runtime::put_key"),
+                                    vec![Expr::path("name"), Expr::path("key")],
+                                )),
+                            ])),
+                        },
+                    ],
+                ))]),
+            ),
+            helper(
+                "emit_event",
+                vec![],
+                vec![
+                    name_param(),
+                    Param::new(
+                        "fields",
+                        rust::Type::generic(
+                            "Vec",
+                            vec![rust::Type::Tuple(vec![
+                                rust::Type::Ref(Box::new(rust::Type::path("str"))),
+                                rust::Type::path("CLValue"),
+                            ])],
+                        ),
+                    ),
+                ],
+                None,
+                Block::new(vec![
+                    rust::Stmt::let_(
+                        "index",
+                        Some(rust::Type::path("u64")),
+                        Expr::call(Path::new("get_key"), vec![Expr::str("__events_length")]),
+                    ),
+                    rust::Stmt::Let(
+                        Pat::Ident(true, "values".to_string()),
+                        Some(rust::Type::generic(
+                            "BTreeMap",
+                            vec![
+                                rust::Type::path("String"),
+                                rust::Type::generic("Vec", vec![rust::Type::path("u8")]),
+                            ],
+                        )),
+                        Some(Expr::call(Path::new("BTreeMap::new"), vec![])),
+                    ),
+                    rust::Stmt::Expr(Expr::ForLoop(
+                        Pat::Tuple(vec![Pat::ident("field"), Pat::ident("value")]),
+                        Box::new(Expr::path("fields")),
+                        Block::new(vec![rust::Stmt::Expr(Expr::path("values").method(
+                            "insert",
+                            vec![
+                                Expr::path("field").method("into", vec![]),
+                                unwrap_or_revert(Expr::path("value").method("to_bytes", vec![])),
+                            ],
+                        ))]),
+                    )),
+                    rust::Stmt::Expr(Expr::call(
+                        Path::new("set_key"),
+                        vec![
+                            Expr::macro_call(
+                                "format",
+                                Delimiter::Paren,
+                                vec![Expr::str("__event_{}"), Expr::path("index")],
+                            )
+                            .reference(),
+                            Expr::Tuple(vec![
+                                Expr::call(Path::new("String::from"), vec![Expr::path("name")]),
+                                Expr::path("values"),
+                            ]),
+                        ],
+                    )),
+                    rust::Stmt::Expr(Expr::call(
+                        Path::new("set_key"),
+                        vec![
+                            Expr::str("__events_length"),
+                            Expr::binary(
+                                BinOp::Add,
+                                Expr::path("index"),
+                                Expr::int(&BigInt::one()),
+                            ),
+                        ],
+                    )),
+                ]),
+            ),
+            // the key of a mapping entry is the key of the mapping followed by the
+            // serialized mapping key in hex, so different entries cannot clash
+            helper(
+                "new_key",
+                generic(&["ToBytes"]),
+                vec![
+                    name_param(),
+                    Param::new("key", rust::Type::Ref(Box::new(rust::Type::path("T")))),
+                ],
+                Some(rust::Type::path("String")),
+                Block::value(
+                    vec![
+                        rust::Stmt::Let(
+                            Pat::Ident(true, "result".to_string()),
+                            None,
+                            Some(Expr::call(
+                                Path::new("String::from"),
+                                vec![Expr::path("name")],
+                            )),
+                        ),
+                        rust::Stmt::Expr(
+                            Expr::path("result").method("push", vec![Expr::Lit(Lit::Char('['))]),
+                        ),
+                        rust::Stmt::Expr(Expr::ForLoop(
+                            Pat::ident("byte"),
+                            Box::new(unwrap_or_revert(
+                                Expr::path("key").method("to_bytes", vec![]),
+                            )),
+                            Block::new(vec![rust::Stmt::Expr(Expr::path("result").method(
+                                "push_str",
+                                vec![
+                                Expr::macro_call("format", Delimiter::Paren,
+                                    vec![Expr::str("{:02x}"), Expr::path("byte")]).reference()
+                            ],
+                            ))]),
+                        )),
+                        rust::Stmt::Expr(
+                            Expr::path("result").method("push", vec![Expr::Lit(Lit::Char(']'))]),
+                        ),
+                    ],
+                    Expr::path("result"),
+                ),
+            ),
+            helper(
+                "assert",
+                vec![],
+                vec![condition_param(), code_param()],
+                None,
+                Block::new(vec![rust::Stmt::Expr(Expr::if_(
+                    Expr::unary(UnOp::Not, Expr::path("condition")),
+                    Block::new(vec![revert(Expr::path("code"))]),
+                    None,
+                ))]),
+            ),
+            helper(
+                "revert",
+                vec![],
+                vec![code_param()],
+                Some(rust::Type::Never),
+                Block::new(vec![revert(Expr::path("code"))]),
+            ),
+            helper(
+                "require",
+                vec![],
+                vec![condition_param(), code_param()],
+                None,
+                Block::new(vec![rust::Stmt::Expr(Expr::call(
+                    Path::new("assert"),
+                    vec![Expr::path("condition"), Expr::path("code")],
+                ))]),
+            ),
+            // the type of the arguments and return values of entry points
+            helper(
+                "cl_type",
+                generic(&["CLTyped"]),
+                vec![],
+                Some(rust::Type::path("CLType")),
+                Block::value(vec![], Expr::call(Path::new("T::cl_type"), vec![])),
+            ),
+            // the motes of the contract are kept in its own purse, which is
+            // created when it is first used
+            helper(
+                "contract_purse",
+                vec![],
+                vec![],
+                Some(rust::Type::path("URef")),
+                Block::value(
+                    vec![],
+                    Expr::Match(
+                        Box::new(Expr::call(
+                            Path::new("runtime::get_key"),
+                            vec![Expr::str("__purse")],
+                        )),
+                        vec![
+                            Arm {
+                                pat: Pat::tuple_struct("Some", vec![Pat::ident("key")]),
+                                body: unwrap_or_revert(
+                                    Expr::path("key").method("try_into", vec![]),
+                                ),
+                            },
+                            Arm {
+                                pat: Pat::Path(Path::new("None")),
+                                body: Expr::Block(Block::value(
+                                    vec![
+                                        rust::Stmt::let_(
+                                            "purse",
+                                            Some(rust::Type::path("URef")),
+                                            Expr::call(Path::new("system::create_purse"), vec![]),
+                                        ),
+                                        rust::Stmt::Expr(Expr::call(
+                                            Path::new("runtime::put_key"),
+                                            vec![
+                                                Expr::str("__purse"),
+                                                Expr::path("purse").method("into", vec![]),
+                                            ],
+                                        )),
+                                    ],
+                                    Expr::path("purse"),
+                                )),
+                            },
+                        ],
+                    ),
+                ),
+            ),
+            // a payable entry point takes a purse, whose motes are moved to
+            // the purse of the contract
+            helper(
+                "deposit_purse",
+                vec![],
+                vec![Param::new("purse", rust::Type::path("URef"))],
+                Some(rust::Type::path("U512")),
+                Block::value(
+                    vec![
+                        rust::Stmt::let_(
+                            "value",
+                            Some(rust::Type::path("U512")),
+                            unwrap_or_revert(Expr::call(
+                                Path::new("system::get_balance"),
+                                vec![Expr::path("purse")],
+                            )),
+                        ),
+                        rust::Stmt::Expr(unwrap_or_revert(Expr::call(
+                            Path::new("system::transfer_from_purse_to_purse"),
+                            vec![
+                                Expr::path("purse"),
+                                Expr::call(Path::new("contract_purse"), vec![]),
+                                Expr::path("value"),
+                            ],
+                        ))),
+                    ],
+                    Expr::path("value"),
+                ),
+            ),
+            // a new purse with motes of the contract, which is passed to a
+            // payable entry point of another contract
+            helper(
+                "new_purse",
+                vec![],
+                vec![Param::new("value", rust::Type::path("U512"))],
+                Some(rust::Type::path("URef")),
+                Block::value(
+                    vec![
+                        rust::Stmt::let_(
+                            "purse",
+                            Some(rust::Type::path("URef")),
+                            Expr::call(Path::new("system::create_purse"), vec![]),
+                        ),
+                        rust::Stmt::Expr(unwrap_or_revert(Expr::call(
+                            Path::new("system::transfer_from_purse_to_purse"),
+                            vec![
+                                Expr::call(Path::new("contract_purse"), vec![]),
+                                Expr::path("purse"),
+                                Expr::path("value"),
+                            ],
+                        ))),
+                    ],
+                    Expr::path("purse"),
+                ),
+            ),
+        ];
+        // the helpers of the hash functions are named after the builtins
+        for hash in self.used_hashes() {
+            let digest = |path: &str| {
+                Block::value(
+                    vec![],
+                    Expr::call(Path::new(path), vec![Expr::path("bs")]).method("into", vec![]),
+                )
+            };
+            let (body, length) =
+                match hash {
+                    HashTy::Keccak256 => (digest("sha3::Keccak256::digest"), 32),
+                    HashTy::Sha256 => (digest("sha2::Sha256::digest"), 32),
+                    HashTy::Ripemd160 => (digest("ripemd160::Ripemd160::digest"), 20),
+                    // blake2b with a 32 byte output, like the hashes of the Casper runtime
+                    HashTy::Blake2_256 => (
+                        Block::value(
+                            vec![
+                                rust::Stmt::Let(
+                                    Pat::Ident(true, "hasher".to_string()),
+                                    None,
+                                    Some(unwrap_or_revert(
+                                        Expr::call(
+                                            Path::new("blake2::VarBlake2b::new"),
+                                            vec![Expr::int(&BigInt::from(32))],
+                                        )
+                                        .method("ok", vec![]),
+                                    )),
+                                ),
+                                rust::Stmt::Expr(
+                                    Expr::path("hasher").method("update", vec![Expr::path("bs")]),
+                                ),
+                                rust::Stmt::Let(
+                                    Pat::Ident(true, "result".to_string()),
+                                    Some(rust::Type::Array(Box::new(rust::Type::path("u8")), 32)),
+                                    Some(Expr::call(Path::new("Default::default"), vec![])),
+                                ),
+                                rust::Stmt::Expr(Expr::path("hasher").method(
+                                    "finalize_variable",
+                                    vec![Expr::Closure(
+                                        vec![Pat::ident("hash")],
+                                        Box::new(
+                                            Expr::path("result").method(
+                                                "copy_from_slice",
+                                                vec![Expr::path("hash")],
+                                            ),
+                                        ),
+                                    )],
+                                )),
+                            ],
+                            Expr::path("result"),
+                        ),
+                        32,
+                    ),
+                    // not a builtin on this target
+                    HashTy::Blake2_128 => continue,
+                };
+            result.push(helper(
+                &hash.to_string(),
+                vec![],
+                vec![Param::new(
+                    "bs",
+                    rust::Type::Ref(Box::new(rust::Type::Slice(Box::new(rust::Type::path(
+                        "u8",
+                    ))))),
+                )],
+                Some(rust::Type::Array(Box::new(rust::Type::path("u8")), length)),
+                body,
+            ));
+        }
+        let fixed_bytes = self.used_types().into_iter().filter_map(|ty| match ty {
+            Type::Bytes(n) => Some(*n),
+            _ => None,
+        });
+        let helpers = self.helpers.borrow();
+        if fixed_bytes.count() > 0 {
+            result.extend(self.render_bytes_helpers());
+        } else if helpers.contains("u256_bytes") {
+            result.push(self.render_u256_bytes());
+        }
+        if helpers.contains("I256") {
+            result.extend(self.render_i256());
+        }
+        // the wide integers convert by way of their little-endian bytes
+        let convert = |name: &str, from: &str, to: &str, length: u16, truncate: bool| {
+            let mut stmts = vec![
+                rust::Stmt::Let(
+                    Pat::Ident(true, "bytes".to_string()),
+                    None,
+                    Some(Expr::Macro(
+                        Path::new("vec"),
+                        Delimiter::Bracket,
+                        MacroArgs::Repeat(
+                            Box::new(Expr::int(&BigInt::zero())),
+                            Box::new(Expr::int(&BigInt::from(length))),
+                        ),
+                    )),
+                ),
+                rust::Stmt::Expr(Expr::path("value").method(
+                    "to_little_endian",
+                    vec![Expr::path("bytes").method("as_mut_slice", vec![])],
+                )),
+            ];
+            if truncate {
+                stmts.push(rust::Stmt::Expr(
+                    Expr::path("bytes").method("truncate", vec![Expr::int(&BigInt::from(32))]),
+                ));
+            }
+            helper(
+                name,
+                vec![],
+                vec![Param::new("value", rust::Type::path(from))],
+                Some(rust::Type::path(to)),
+                Block::value(
+                    stmts,
+                    Expr::call(
+                        Path::new(&format!("{}::from_little_endian", to)),
+                        vec![Expr::path("bytes").reference()],
+                    ),
+                ),
+            )
+        };
+        if helpers.contains("u256_to_u512") {
+            result.push(convert("u256_to_u512", "U256", "U512", 32, false));
+        }
+        if helpers.contains("u512_to_u256") {
+            result.push(convert("u512_to_u256", "U512", "U256", 64, true));
+        }
+        result
+    }
+
+    /// A signed 256-bit integer, which is the two's complement of its value in
+    /// a `U256`. Like the primitive integers, it has wrapping arithmetic, and
+    /// its shifts and bitwise operators are methods named after the operators.
+    /// It is serialized as a `U256`.
+    fn render_i256(&self) -> Vec<Item> {
+        let ty = || rust::Type::path("I256");
+        let function =
+            |name: &str, receiver: bool, params: Vec<Param>, returns: rust::Type, body: Block| {
+                Function {
+                    attrs: Vec::new(),
+                    linkage: Linkage::Private,
+                    name: name.to_string(),
+                    generics: Vec::new(),
+                    receiver,
+                    params,
+                    returns: Some(returns),
+                    body,
+                }
+            };
+        let new = |value: Expr| Expr::Struct(Path::new("I256"), vec![("value".to_string(), value)]);
+        let value = |expr: Expr| Expr::Field(Box::new(expr), "value".to_string());
+        let self_value = || value(Expr::SelfValue);
+        let other = || Param::new("other", ty());
+        let is_negative = |expr: Expr| expr.method("is_negative", vec![]);
+        let negate_if = |cond: Expr, expr: Expr| {
+            Expr::if_(
+                cond,
+                Block::value(vec![], expr.clone().method("wrapping_neg", vec![])),
+                Some(Expr::Block(Block::value(vec![], expr))),
+            )
+        };
+        let bytes_ty = rust::Type::Ref(Box::new(rust::Type::Slice(Box::new(rust::Type::path(
+            "u8",
+        )))));
+        let result_ty = |ty: rust::Type| {
+            rust::Type::generic("Result", vec![ty, rust::Type::path("bytesrepr::Error")])
+        };
+        let ordering = |name: &str| Expr::path(&format!("core::cmp::Ordering::{}", name));
+
+        let mut fns = vec![
+            function(
+                "is_negative",
+                true,
+                vec![],
+                rust::Type::path("bool"),
+                Block::value(
+                    vec![],
+                    self_value().method("bit", vec![Expr::int(&BigInt::from(255))]),
+                ),
+            ),
+            function(
+                "unsigned_abs",
+                true,
+                vec![],
+                rust::Type::path("U256"),
+                Block::value(
+                    vec![],
+                    Expr::if_(
+                        is_negative(Expr::SelfValue),
+                        Block::value(
+                            vec![],
+                            value(Expr::SelfValue.method("wrapping_neg", vec![])),
+                        ),
+                        Some(Expr::Block(Block::value(vec![], self_value()))),
+                    ),
+                ),
+            ),
+        ];
+        for op in &["add", "sub", "mul"] {
+            let overflowing = self_value().method(
+                &format!("overflowing_{}", op),
+                vec![value(Expr::path("other"))],
+            );
+            fns.push(function(
+                &format!("wrapping_{}", op),
+                true,
+                vec![other()],
+                ty(),
+                Block::value(
+                    vec![],
+                    new(Expr::Field(Box::new(overflowing), "0".to_string())),
+                ),
+            ));
+        }
+        fns.push(function(
+            "wrapping_neg",
+            true,
+            vec![],
+            ty(),
+            Block::value(
+                vec![],
+                new(Expr::Field(
+                    Box::new(Expr::unary(UnOp::Not, self_value()).method(
+                        "overflowing_add",
+                        vec![Expr::call(Path::new("U256::one"), vec![])],
+                    )),
+                    "0".to_string(),
+                )),
+            ),
+        ));
+        // the quotient is rounded towards zero, and the remainder has the sign of the dividend
+        for (name, op, sign) in &[
+            (
+                "wrapping_div",
+                BinOp::Div,
+                Expr::binary(
+                    BinOp::Ne,
+                    is_negative(Expr::SelfValue),
+                    is_negative(Expr::path("other")),
+                ),
+            ),
+            ("wrapping_rem", BinOp::Rem, is_negative(Expr::SelfValue)),
+        ] {
+            fns.push(function(
+                name,
+                true,
+                vec![other()],
+                ty(),
+                Block::value(
+                    vec![rust::Stmt::let_(
+                        "result",
+                        None,
+                        new(Expr::binary(
+                            *op,
+                            Expr::SelfValue.method("unsigned_abs", vec![]),
+                            Expr::path("other").method("unsigned_abs", vec![]),
+                        )),
+                    )],
+                    negate_if(sign.clone(), Expr::path("result")),
+                ),
+            ));
+        }
+        fns.push(function(
+            "not",
+            true,
+            vec![],
+            ty(),
+            Block::value(vec![], new(Expr::unary(UnOp::Not, self_value()))),
+        ));
+        for (name, op) in &[
+            ("bitand", BinOp::BitAnd),
+            ("bitor", BinOp::BitOr),
+            ("bitxor", BinOp::BitXor),
+        ] {
+            fns.push(function(
+                name,
+                true,
+                vec![other()],
+                ty(),
+                Block::value(
+                    vec![],
+                    new(Expr::binary(*op, self_value(), value(Expr::path("other")))),
+                ),
+            ));
+        }
+        let bits = || Param::new("bits", rust::Type::path("usize"));
+        fns.push(function(
+            "shl",
+            true,
+            vec![bits()],
+            ty(),
+            Block::value(
+                vec![],
+                new(Expr::binary(BinOp::Shl, self_value(), Expr::path("bits"))),
+            ),
+        ));
+        // a negative number is shifted in ones
+        fns.push(function(
+            "shr",
+            true,
+            vec![bits()],
+            ty(),
+            Block::value(
+                vec![],
+                Expr::if_(
+                    is_negative(Expr::SelfValue),
+                    Block::value(
+                        vec![],
+                        new(Expr::unary(
+                            UnOp::Not,
+                            Expr::binary(
+                                BinOp::Shr,
+                                Expr::unary(UnOp::Not, self_value()),
+                                Expr::path("bits"),
+                            ),
+                        )),
+                    ),
+                    Some(Expr::Block(Block::value(
+                        vec![],
+                        new(Expr::binary(BinOp::Shr, self_value(), Expr::path("bits"))),
+                    ))),
+                ),
+            ),
+        ));
+
+        vec![
+            Item::Struct(Struct {
+                attrs: vec![Attribute::new(
+                    "derive",
+                    &["Clone", "Copy", "Debug", "Default", "PartialEq", "Eq"],
+                )],
+                name: "I256".to_string(),
+                fields: vec![("value".to_string(), rust::Type::path("U256"))],
+            }),
+            Item::Impl(Impl {
+                trait_: None,
+                ty: ty(),
+                fns,
+            }),
+            Item::Impl(Impl {
+                trait_: Some(Path::new("From").with_args(vec![rust::Type::path("i64")])),
+                ty: ty(),
+                fns: vec![function(
+                    "from",
+                    false,
+                    vec![Param::new("value", rust::Type::path("i64"))],
+                    ty(),
+                    Block::value(
+                        vec![rust::Stmt::let_(
+                            "result",
+                            None,
+                            new(Expr::call(
+                                Path::new("U256::from"),
+                                vec![Expr::Cast(
+                                    Box::new(Expr::path("value").method("wrapping_abs", vec![])),
+                                    rust::Type::path("u64"),
+                                )],
+                            )),
+                        )],
+                        negate_if(
+                            Expr::binary(
+                                BinOp::Lt,
+                                Expr::path("value"),
+                                Expr::int(&BigInt::zero()),
+                            ),
+                            Expr::path("result"),
+                        ),
+                    ),
+                )],
+            }),
+            Item::Impl(Impl {
+                trait_: Some(Path::new("From").with_args(vec![rust::Type::path("U256")])),
+                ty: ty(),
+                fns: vec![function(
+                    "from",
+                    false,
+                    vec![Param::new("value", rust::Type::path("U256"))],
+                    ty(),
+                    Block::value(vec![], new(Expr::path("value"))),
+                )],
+            }),
+            Item::Impl(Impl {
+                trait_: Some(Path::new("From").with_args(vec![ty()])),
+                ty: rust::Type::path("U256"),
+                fns: vec![function(
+                    "from",
+                    false,
+                    vec![Param::new("value", ty())],
+                    rust::Type::path("U256"),
+                    Block::value(vec![], value(Expr::path("value"))),
+                )],
+            }),
+            Item::Impl(Impl {
+                trait_: Some(Path::new("PartialOrd")),
+                ty: ty(),
+                fns: vec![function(
+                    "partial_cmp",
+                    true,
+                    vec![Param::new("other", rust::Type::Ref(Box::new(ty())))],
+                    rust::Type::generic("Option", vec![rust::Type::path("core::cmp::Ordering")]),
+                    Block::value(
+                        vec![],
+                        Expr::call(
+                            Path::new("Some"),
+                            vec![Expr::SelfValue.method("cmp", vec![Expr::path("other")])],
+                        ),
+                    ),
+                )],
+            }),
+            // the negative numbers are the ones with the top bit set
+            Item::Impl(Impl {
+                trait_: Some(Path::new("Ord")),
+                ty: ty(),
+                fns: vec![function(
+                    "cmp",
+                    true,
+                    vec![Param::new("other", rust::Type::Ref(Box::new(ty())))],
+                    rust::Type::path("core::cmp::Ordering"),
+                    Block::value(
+                        vec![],
+                        Expr::Match(
+                            Box::new(Expr::Tuple(vec![
+                                is_negative(Expr::SelfValue),
+                                is_negative(Expr::path("other")),
+                            ])),
+                            vec![
+                                Arm {
+                                    pat: Pat::Tuple(vec![
+                                        Pat::Lit(Lit::Bool(true)),
+                                        Pat::Lit(Lit::Bool(false)),
+                                    ]),
+                                    body: ordering("Less"),
+                                },
+                                Arm {
+                                    pat: Pat::Tuple(vec![
+                                        Pat::Lit(Lit::Bool(false)),
+                                        Pat::Lit(Lit::Bool(true)),
+                                    ]),
+                                    body: ordering("Greater"),
+                                },
+                                Arm {
+                                    pat: Pat::Wild,
+                                    body: self_value().method(
+                                        "cmp",
+                                        vec![value(Expr::path("other")).reference()],
+                                    ),
+                                },
+                            ],
+                        ),
+                    ),
+                )],
+            }),
+            Item::Impl(Impl {
+                trait_: Some(Path::new("CLTyped")),
+                ty: ty(),
+                fns: vec![function(
+                    "cl_type",
+                    false,
+                    vec![],
+                    rust::Type::path("CLType"),
+                    Block::value(vec![], Expr::path("CLType::U256")),
+                )],
+            }),
+            Item::Impl(Impl {
+                trait_: Some(Path::new("ToBytes")),
+                ty: ty(),
+                fns: vec![
+                    function(
+                        "to_bytes",
+                        true,
+                        vec![],
+                        result_ty(rust::Type::generic("Vec", vec![rust::Type::path("u8")])),
+                        Block::value(vec![], self_value().method("to_bytes", vec![])),
+                    ),
+                    function(
+                        "serialized_length",
+                        true,
+                        vec![],
+                        rust::Type::path("usize"),
+                        Block::value(vec![], self_value().method("serialized_length", vec![])),
+                    ),
+                ],
+            }),
+            Item::Impl(Impl {
+                trait_: Some(Path::new("FromBytes")),
+                ty: ty(),
+                fns: vec![function(
+                    "from_bytes",
+                    false,
+                    vec![Param::new("bytes", bytes_ty.clone())],
+                    result_ty(rust::Type::Tuple(vec![ty(), bytes_ty.clone()])),
+                    Block::value(
+                        vec![rust::Stmt::Let(
+                            Pat::Tuple(vec![Pat::ident("value"), Pat::ident("bytes")]),
+                            Some(rust::Type::Tuple(vec![
+                                rust::Type::path("U256"),
+                                bytes_ty.clone(),
+                            ])),
+                            Some(Expr::Try(Box::new(Expr::call(
+                                Path::new("FromBytes::from_bytes"),
+                                vec![Expr::path("bytes")],
+                            )))),
+                        )],
+                        Expr::call(
+                            Path::new("Ok"),
+                            vec![Expr::Tuple(vec![
+                                new(Expr::path("value")),
+                                Expr::path("bytes"),
+                            ])],
+                        ),
+                    ),
+                )],
+            }),
+        ]
+    }
+
+    /// Helper functions for fixed length byte arrays, which are generic over
+    /// the length of the arrays
+    fn render_bytes_helpers(&self) -> Vec<Item> {
+        let helper = |name: &str,
+                      generics: Vec<Generic>,
+                      params: Vec<Param>,
+                      returns: rust::Type,
+                      body: Block| {
+            Item::Fn(Function {
+                attrs: Vec::new(),
+                linkage: Linkage::Private,
+                name: name.to_string(),
+                generics,
+                receiver: false,
+                params,
+                returns: Some(returns),
+                body,
+            })
+        };
+        let generic = |name: &str, bounds: &[&str]| Generic {
+            name: name.to_string(),
+            bounds: bounds.iter().map(|bound| Path::new(bound)).collect(),
+        };
+        let deref = |name: &str| Expr::unary(UnOp::Deref, Expr::path(name));
+        let as_ref = |name: &str| Expr::path(name).method("as_ref", vec![]);
+        let iter_mut = |name: &str| {
+            Expr::path(name)
+                .method("as_mut", vec![])
+                .method("iter_mut", vec![])
+        };
+        let default = |name: &str| {
+            rust::Stmt::Let(
+                Pat::Ident(true, "result".to_string()),
+                None,
+                Some(Expr::call(Path::new(&format!("{}::default", name)), vec![])),
+            )
+        };
+        let u32_from = |name: &str| Expr::call(Path::new("u32::from"), vec![Expr::path(name)]);
+        // the byte at an index, or zero past the ends of the array
+        let byte_at = |index: Expr| {
+            index
+                .method("cloned", vec![])
+                .method("unwrap_or", vec![Expr::int(&BigInt::zero())])
+        };
+        let bits =
+            |op: BinOp, n: u8| Expr::binary(op, Expr::path("bits"), Expr::int(&BigInt::from(n)));
+        // the two bytes which the bits of a shifted byte come from
+        let pair = || {
+            Expr::binary(
+                BinOp::BitOr,
+                Expr::binary(BinOp::Shl, u32_from("high"), Expr::int(&BigInt::from(8))),
+                u32_from("low"),
+            )
+        };
+        let bytes_params = |first: &str, second: Param| {
+            vec![
+                Param {
+                    pat: Pat::Ident(true, first.to_string()),
+                    ty: rust::Type::path("T"),
+                },
+                second,
+            ]
+        };
+        let mut result = Vec::new();
+        for (name, op) in &[
+            ("bytes_and", BinOp::BitAnd),
+            ("bytes_or", BinOp::BitOr),
+            ("bytes_xor", BinOp::BitXor),
+        ] {
+            result.push(helper(
+                name,
+                vec![generic("T", &["AsRef<[u8]>", "AsMut<[u8]>"])],
+                bytes_params("left", Param::new("right", rust::Type::path("T"))),
+                rust::Type::path("T"),
+                Block::value(
+                    vec![rust::Stmt::Expr(Expr::ForLoop(
+                        Pat::Tuple(vec![Pat::ident("l"), Pat::ident("r")]),
+                        Box::new(iter_mut("left").method("zip", vec![as_ref("right")])),
+                        Block::new(vec![rust::Stmt::Expr(Expr::assign(
+                            deref("l"),
+                            Expr::binary(*op, deref("l"), deref("r")),
+                        ))]),
+                    ))],
+                    Expr::path("left"),
+                ),
+            ));
+        }
+        result.push(helper(
+            "bytes_not",
+            vec![generic("T", &["AsMut<[u8]>"])],
+            vec![Param {
+                pat: Pat::Ident(true, "value".to_string()),
+                ty: rust::Type::path("T"),
+            }],
+            rust::Type::path("T"),
+            Block::value(
+                vec![rust::Stmt::Expr(Expr::ForLoop(
+                    Pat::ident("byte"),
+                    Box::new(iter_mut("value")),
+                    Block::new(vec![rust::Stmt::Expr(Expr::assign(
+                        deref("byte"),
+                        Expr::unary(UnOp::Not, deref("byte")),
+                    ))]),
+                ))],
+                Expr::path("value"),
+            ),
+        ));
+        // the arrays are shifted as big-endian numbers
+        let shift = |name: &str, high: Expr, low: Expr, byte: Expr| {
+            helper(
+                name,
+                vec![generic("T", &["AsRef<[u8]>", "AsMut<[u8]>", "Default"])],
+                vec![
+                    Param::new("value", rust::Type::path("T")),
+                    Param::new("bits", rust::Type::path("usize")),
+                ],
+                rust::Type::path("T"),
+                Block::value(
+                    vec![
+                        default("T"),
+                        rust::Stmt::Expr(Expr::ForLoop(
+                            Pat::Tuple(vec![Pat::ident("i"), Pat::ident("byte")]),
+                            Box::new(iter_mut("result").method("enumerate", vec![])),
+                            Block::new(vec![
+                                rust::Stmt::let_("high", None, byte_at(high)),
+                                rust::Stmt::let_("low", None, byte_at(low)),
+                                rust::Stmt::Expr(Expr::assign(
+                                    deref("byte"),
+                                    Expr::Cast(Box::new(byte), rust::Type::path("u8")),
+                                )),
+                            ]),
+                        )),
+                    ],
+                    Expr::path("result"),
+                ),
+            )
+        };
+        let get = |index: Expr| as_ref("value").method("get", vec![index]);
+        result.push(shift(
+            "bytes_shl",
+            get(Expr::binary(
+                BinOp::Add,
+                Expr::path("i"),
+                bits(BinOp::Div, 8),
+            )),
+            get(Expr::binary(
+                BinOp::Add,
+                Expr::binary(BinOp::Add, Expr::path("i"), bits(BinOp::Div, 8)),
+                Expr::int(&BigInt::one()),
+            )),
+            Expr::binary(
+                BinOp::Shr,
+                Expr::binary(BinOp::Shl, pair(), bits(BinOp::Rem, 8)),
+                Expr::int(&BigInt::from(8)),
+            ),
+        ));
+        let get_before = |offset: Expr| {
+            Expr::path("i").method("checked_sub", vec![offset]).method(
+                "and_then",
+                vec![Expr::Closure(
+                    vec![Pat::ident("i")],
+                    Box::new(get(Expr::path("i"))),
+                )],
+            )
+        };
+        result.push(shift(
+            "bytes_shr",
+            get_before(Expr::binary(
+                BinOp::Add,
+                bits(BinOp::Div, 8),
+                Expr::int(&BigInt::one()),
+            )),
+            get_before(bits(BinOp::Div, 8)),
+            Expr::binary(BinOp::Shr, pair(), bits(BinOp::Rem, 8)),
+        ));
+        result.push(helper(
+            "bytes_resize",
+            vec![
+                generic("T", &["AsRef<[u8]>"]),
+                generic("U", &["AsMut<[u8]>", "Default"]),
+            ],
+            vec![Param::new("value", rust::Type::path("T"))],
+            rust::Type::path("U"),
+            Block::value(
+                vec![
+                    default("U"),
+                    rust::Stmt::Expr(Expr::ForLoop(
+                        Pat::Tuple(vec![Pat::ident("r"), Pat::ident("v")]),
+                        Box::new(iter_mut("result").method("zip", vec![as_ref("value")])),
+                        Block::new(vec![rust::Stmt::Expr(Expr::assign(deref("r"), deref("v")))]),
+                    )),
+                ],
+                Expr::path("result"),
+            ),
+        ));
+        result.push(self.render_u256_bytes());
+        result
+    }
+
+    /// The big-endian bytes of a `U256`, which are also those of an address
+    fn render_u256_bytes(&self) -> Item {
+        let array = rust::Type::Array(Box::new(rust::Type::path("u8")), 32);
+        Item::Fn(Function {
+            attrs: Vec::new(),
+            linkage: Linkage::Private,
+            name: "u256_bytes".to_string(),
+            generics: Vec::new(),
+            receiver: false,
+            params: vec![Param::new("value", rust::Type::path("U256"))],
+            returns: Some(array.clone()),
+            body: Block::value(
+                vec![
+                    rust::Stmt::Let(
+                        Pat::Ident(true, "result".to_string()),
+                        Some(array),
+                        Some(Expr::call(Path::new("Default::default"), vec![])),
+                    ),
+                    rust::Stmt::Expr(Expr::path("value").method(
+                        "to_big_endian",
+                        vec![Expr::path("result").method("as_mut", vec![])],
+                    )),
+                ],
+                Expr::path("result"),
+            ),
+        })
+    }
+
+    /// The contracts created with `new` by this contract, and by those in turn
+    fn created_contracts(&self) -> Vec<usize> {
+        let mut result = self.contract.creates();
+        let mut i = 0;
+        while i < result.len() {
+            for contract_no in self.ns.contracts[result[i]].creates() {
+                if !result.contains(&contract_no) {
+                    result.push(contract_no);
+                }
+            }
+            i += 1;
+        }
+        result
+    }
+
+    /// The functions which are entry points of the contract once installed
+    fn entry_points(&self) -> Vec<&'a FunctionDecl> {
+        self.functions()
+            .into_iter()
+            .filter(|f| f.is_public() || f.is_constructor())
+            .collect()
+    }
+
+    /// The entry points of the contract once installed, with their names
+    fn named_entry_points(&self) -> Vec<(String, &'a FunctionDecl)> {
+        if self.contract_macro {
+            self.functions()
+                .into_iter()
+                .filter(|f| f.is_public())
+                .map(|f| (self.render_function_name(f), f))
+                .collect()
+        } else {
+            self.entry_points()
+                .into_iter()
+                .map(|f| (self.render_entry_point_name(f), f))
+                .collect()
+        }
+    }
+
+    /// Constructors are prefixed with the contract name, as the contracts
+    /// created with `new` share the wasm module of their creator
+    fn render_entry_point_name(&self, function: &FunctionDecl) -> String {
         if function.is_constructor() {
-            "constructor".to_string()
+            format!(
+                "{}_{}",
+                self.contract.name,
+                self.render_function_name(function)
+            )
         } else {
-            function.name.clone()
+            self.render_function_name(function)
+        }
+    }
+
+    /// The contract without the contract macro: a module with its functions,
+    /// the entry points exported from the wasm module, which read their
+    /// arguments by name, and a function which installs the contract and
+    /// returns its contract hash.
+    fn render_explicit(&self, errors: &mut Vec<Output>) -> Vec<Item> {
+        let module = &self.contract.name;
+        let mut items = vec![Item::use_("super", &["*"])];
+        for function in self.functions() {
+            items.push(Item::Fn(Function {
+                attrs: Vec::new(),
+                linkage: Linkage::Super,
+                ..self.render_function(function, errors)
+            }));
+        }
+        let mut result = vec![Item::Mod(Module {
+            attrs: Vec::new(),
+            name: module.clone(),
+            items,
+        })];
+        for function in self.entry_points() {
+            let mut args: Vec<Expr> = function
+                .params
+                .iter()
+                .map(|param| {
+                    Expr::call(
+                        Path::new("runtime::get_named_arg"),
+                        vec![Expr::str(&param.name)],
+                    )
+                })
+                .collect();
+            if function.is_payable() {
+                args.push(Expr::call(
+                    Path::new("deposit_purse"),
+                    vec![Expr::call(
+                        Path::new("runtime::get_named_arg"),
+                        vec![Expr::str("purse")],
+                    )],
+                ));
+            }
+            let path = Path::new(&format!(
+                "{}::{}",
+                module,
+                self.render_function_name(function)
+            ));
+            let mut call = Expr::call(path, args);
+            if !function.returns.is_empty() {
+                call = render_ret(call);
+            }
+            result.push(Item::Fn(Function {
+                attrs: Vec::new(),
+                linkage: Linkage::Export,
+                name: self.render_entry_point_name(function),
+                generics: Vec::new(),
+                receiver: false,
+                params: Vec::new(),
+                returns: None,
+                body: Block::new(vec![rust::Stmt::Expr(call)]),
+            }));
+        }
+        result.push(Item::Fn(self.render_installer(errors)));
+        result
+    }
+
+    /// The session code which installs the contract, with the arguments of its
+    /// constructor, and stores its contract hash under the name of the contract.
+    /// A payable constructor is sent the motes of argument `amount` from the
+    /// main purse of the account.
+    fn render_call(&self) -> Function {
+        let mut body = Vec::new();
+        let mut args = Vec::new();
+        if let Some(constructor) = self.entry_points().into_iter().find(|f| f.is_constructor()) {
+            for param in &constructor.params {
+                args.push(Expr::call(
+                    Path::new("runtime::get_named_arg"),
+                    vec![Expr::str(&param.name)],
+                ));
+            }
+            if constructor.is_payable() {
+                body.push(rust::Stmt::let_(
+                    "purse",
+                    Some(rust::Type::path("URef")),
+                    Expr::call(Path::new("system::create_purse"), vec![]),
+                ));
+                body.push(rust::Stmt::Expr(
+                    Expr::call(
+                        Path::new("system::transfer_from_purse_to_purse"),
+                        vec![
+                            Expr::call(Path::new("account::get_main_purse"), vec![]),
+                            Expr::path("purse"),
+                            Expr::call(
+                                Path::new("runtime::get_named_arg"),
+                                vec![Expr::str("amount")],
+                            ),
+                        ],
+                    )
+                    .method("unwrap_or_revert", vec![]),
+                ));
+                args.push(Expr::path("purse"));
+            }
+        }
+        body.push(rust::Stmt::let_(
+            "contract_hash",
+            None,
+            Expr::call(Path::new(&format!("new_{}", self.contract.name)), args),
+        ));
+        body.push(rust::Stmt::Expr(Expr::call(
+            Path::new("runtime::put_key"),
+            vec![
+                Expr::str(&self.contract.name),
+                Expr::call(Path::new("Key::Hash"), vec![Expr::path("contract_hash")]),
+            ],
+        )));
+        Function {
+            attrs: Vec::new(),
+            linkage: Linkage::Export,
+            name: "call".to_string(),
+            generics: Vec::new(),
+            receiver: false,
+            params: Vec::new(),
+            returns: None,
+            body: Block::new(body),
+        }
+    }
+
+    /// `new_C(..)` installs contract `C` as a new contract package, and calls
+    /// its constructor, which only the installer may call. A payable
+    /// constructor is passed the purse given to the installer.
+    fn render_installer(&self, errors: &mut Vec<Output>) -> Function {
+        let constructor = self.entry_points().into_iter().find(|f| f.is_constructor());
+        let mut body = vec![rust::Stmt::Let(
+            Pat::Tuple(vec![Pat::ident("package_hash"), Pat::Wild]),
+            None,
+            Some(Expr::call(
+                Path::new("storage::create_contract_package_at_hash"),
+                vec![],
+            )),
+        )];
+        if constructor.is_some() {
+            let group = Expr::call(
+                Path::new("storage::create_contract_user_group"),
+                vec![
+                    Expr::path("package_hash"),
+                    Expr::str("constructor"),
+                    Expr::int(&BigInt::one()),
+                    Expr::call(Path::new("BTreeSet::new"), vec![]),
+                ],
+            );
+            body.push(rust::Stmt::let_(
+                "_constructor_access",
+                Some(rust::Type::path("URef")),
+                group
+                    .method("unwrap_or_revert", vec![])
+                    .method("pop", vec![])
+                    .method("unwrap_or_revert", vec![]),
+            ));
+        }
+        body.push(rust::Stmt::Let(
+            Pat::Ident(true, "entry_points".to_string()),
+            None,
+            Some(Expr::call(Path::new("EntryPoints::new"), vec![])),
+        ));
+        for function in self.entry_points() {
+            let mut params: Vec<Expr> = function
+                .params
+                .iter()
+                .map(|param| {
+                    Expr::call(
+                        Path::new("Parameter::new"),
+                        vec![
+                            Expr::str(&param.name),
+                            self.render_cl_type(std::slice::from_ref(param), &function.loc, errors),
+                        ],
+                    )
+                })
+                .collect();
+            if function.is_payable() {
+                params.push(Expr::call(
+                    Path::new("Parameter::new"),
+                    vec![Expr::str("purse"), Expr::path("CLType::URef")],
+                ));
+            }
+            let access = if function.is_constructor() {
+                Expr::call(
+                    Path::new("EntryPointAccess::Groups"),
+                    vec![Expr::macro_call(
+                        "vec",
+                        Delimiter::Bracket,
+                        vec![Expr::call(
+                            Path::new("Group::new"),
+                            vec![Expr::str("constructor")],
+                        )],
+                    )],
+                )
+            } else {
+                Expr::path("EntryPointAccess::Public")
+            };
+            let entry_point = Expr::call(
+                Path::new("EntryPoint::new"),
+                vec![
+                    Expr::str(&self.render_entry_point_name(function)),
+                    Expr::macro_call("vec", Delimiter::Bracket, params),
+                    self.render_cl_type(&function.returns, &function.loc, errors),
+                    access,
+                    Expr::path("EntryPointType::Contract"),
+                ],
+            );
+            body.push(rust::Stmt::Expr(
+                Expr::path("entry_points").method("add_entry_point", vec![entry_point]),
+            ));
+        }
+        // the contract hash is only known once the contract is added, so the
+        // contract finds it in a URef which is written afterwards
+        body.push(rust::Stmt::let_(
+            "this",
+            Some(rust::Type::path("URef")),
+            Expr::call(
+                Path::new("storage::new_uref"),
+                vec![Expr::call(Path::new("ContractHash::default"), vec![])],
+            ),
+        ));
+        body.push(rust::Stmt::Let(
+            Pat::Ident(true, "named_keys".to_string()),
+            Some(rust::Type::generic(
+                "BTreeMap",
+                vec![rust::Type::path("String"), rust::Type::path("Key")],
+            )),
+            Some(Expr::call(Path::new("BTreeMap::new"), vec![])),
+        ));
+        body.push(rust::Stmt::Expr(Expr::path("named_keys").method(
+            "insert",
+            vec![
+                Expr::call(Path::new("String::from"), vec![Expr::str("__this")]),
+                Expr::path("this").method("into", vec![]),
+            ],
+        )));
+        body.push(rust::Stmt::Let(
+            Pat::Tuple(vec![Pat::ident("contract_hash"), Pat::Wild]),
+            None,
+            Some(Expr::call(
+                Path::new("storage::add_contract_version"),
+                vec![
+                    Expr::path("package_hash"),
+                    Expr::path("entry_points"),
+                    Expr::path("named_keys"),
+                ],
+            )),
+        ));
+        body.push(rust::Stmt::Expr(Expr::call(
+            Path::new("storage::write"),
+            vec![Expr::path("this"), Expr::path("contract_hash")],
+        )));
+        let mut params = Vec::new();
+        if let Some(constructor) = constructor {
+            let mut args: Vec<(Expr, Expr)> = constructor
+                .params
+                .iter()
+                .enumerate()
+                .map(|(arg, param)| {
+                    (
+                        Expr::str(&param.name),
+                        Expr::path(&self.render_param_name(constructor, arg)),
+                    )
+                })
+                .collect();
+            if constructor.is_payable() {
+                args.push((Expr::str("purse"), Expr::path("purse")));
+            }
+            body.push(rust::Stmt::Expr(Expr::call(
+                Path::new("runtime::call_contract").with_args(vec![rust::Type::unit()]),
+                vec![
+                    Expr::path("contract_hash"),
+                    Expr::str(&self.render_entry_point_name(constructor)),
+                    Expr::Macro(
+                        Path::new("runtime_args"),
+                        Delimiter::Brace,
+                        MacroArgs::Map(args),
+                    ),
+                ],
+            )));
+            params = self.render_function_args(constructor, errors);
+            if constructor.is_payable() {
+                params.push(Param::new("purse", rust::Type::path("URef")));
+            }
+        }
+        Function {
+            attrs: Vec::new(),
+            linkage: Linkage::Private,
+            name: format!("new_{}", self.contract.name),
+            generics: Vec::new(),
+            receiver: false,
+            params,
+            returns: Some(rust::Type::path("ContractHash")),
+            body: Block::value(body, Expr::path("contract_hash")),
+        }
+    }
+
+    fn render_functions(&self, errors: &mut Vec<Output>) -> Vec<Item> {
+        let mut result = Vec::new();
+        for function in self.functions() {
+            let mut rendered = self.render_function(function, errors);
+            if self.is_wrapped(function) {
+                let mut args: Vec<Expr> = (0..function.params.len())
+                    .map(|arg| Expr::path(&self.render_param_name(function, arg)))
+                    .collect();
+                // the parameters are not set by the entry point
+                let mut params: Vec<Param> = rendered
+                    .params
+                    .iter()
+                    .take(function.params.len())
+                    .map(|param| match &param.pat {
+                        Pat::Ident(_, name) => Param::new(name, param.ty.clone()),
+                        _ => param.clone(),
+                    })
+                    .collect();
+                if function.is_payable() {
+                    args.push(Expr::call(
+                        Path::new("deposit_purse"),
+                        vec![Expr::path("purse")],
+                    ));
+                    params.push(Param::new("purse", rust::Type::path("URef")));
+                }
+                let mut call = Expr::call(Path::new(&rendered.name), args);
+                if !function.returns.is_empty() {
+                    call = render_ret(call);
+                }
+                result.push(Item::Fn(Function {
+                    name: self.render_function_name(function),
+                    params,
+                    returns: None,
+                    body: Block::new(vec![rust::Stmt::Expr(call)]),
+                    ..rendered.clone()
+                }));
+                rendered.attrs = Vec::new();
+            }
+            result.push(Item::Fn(rendered));
+        }
+        result
+    }
+
+    fn render_function(&self, function: &FunctionDecl, errors: &mut Vec<Output>) -> Function {
+        // the Casper runtime only serializes tuples of up to three values
+        if function.is_public() && function.returns.len() > 3 {
+            self.unsupported::<()>(
+                &function.loc,
+                "returning more than three values from an entry point",
+                errors,
+            );
+        }
+        let returns = if function.returns.is_empty() {
+            None
+        } else {
+            Some(self.render_return_type(&function.returns, &function.loc, errors))
+        };
+        let mut params = self.render_function_args(function, errors);
+        if let Some(cfg) = &function.cfg {
+            for (arg, param) in params.iter_mut().enumerate() {
+                match (argument_var(arg, cfg), &mut param.pat) {
+                    (Some(var), Pat::Ident(mutable, _)) if is_mutable(var, cfg) => *mutable = true,
+                    _ => {}
+                }
+            }
+            if self.takes_value(function) {
+                params.push(Param::new(
+                    &self.unused_name("value", cfg),
+                    rust::Type::path("U512"),
+                ));
+            }
+        }
+        Function {
+            attrs: self.render_function_attrs(function),
+            linkage: Linkage::Private,
+            name: self.render_internal_name(function),
+            generics: Vec::new(),
+            receiver: false,
+            params,
+            returns,
+            body: Block::new(self.render_function_body(function, errors)),
+        }
+    }
+
+    /// The contract macro does not return the values of a method, so a method
+    /// which returns values is an entry point which returns the values of a
+    /// separate function, which is the one called from other functions. A
+    /// payable method is wrapped too, as its entry point takes a purse rather
+    /// than the value.
+    fn is_wrapped(&self, function: &FunctionDecl) -> bool {
+        self.contract_macro
+            && function.is_public()
+            && (!function.returns.is_empty() || function.is_payable())
+    }
+
+    /// Payable functions take the amount of motes transferred to the contract
+    /// as their last argument, which is the value of `msg.value`. So do the
+    /// internal functions which read `msg.value`, and those which call them.
+    fn takes_value(&self, function: &FunctionDecl) -> bool {
+        let functions = &self.contract.functions;
+        let mut result: Vec<bool> = functions
+            .iter()
+            .map(|f| {
+                f.is_payable()
+                    || match &f.cfg {
+                        Some(cfg) => cfg.reads_value,
+                        None => false,
+                    }
+            })
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (no, f) in functions.iter().enumerate() {
+                if result[no] || f.is_public() {
+                    continue;
+                }
+                let calls = match &f.cfg {
+                    Some(cfg) => cfg.bb.iter().any(|bb| {
+                        bb.instr.iter().any(|instr| match instr {
+                            Instr::Call { func, .. } => result[*func],
+                            _ => false,
+                        })
+                    }),
+                    None => false,
+                };
+                if calls {
+                    result[no] = true;
+                    changed = true;
+                }
+            }
+        }
+        functions
+            .iter()
+            .position(|f| std::ptr::eq(f, function))
+            .map_or(function.is_payable(), |no| result[no])
+    }
+
+    /// The name of the Rust function which returns the values of the function
+    fn render_internal_name(&self, function: &FunctionDecl) -> String {
+        if self.is_wrapped(function) {
+            format!("{}_inner", self.render_function_name(function))
+        } else {
+            self.render_function_name(function)
+        }
+    }
+
+    fn render_function_attrs(&self, function: &FunctionDecl) -> Vec<Attribute> {
+        match (function.is_constructor(), function.is_public()) {
+            (true, true) => vec![Attribute::new("casperlabs_constructor", &[])],
+            (false, true) => vec![Attribute::new("casperlabs_method", &[])],
+            _ => Vec::new(),
+        }
+    }
+
+    fn render_function_name(&self, function: &FunctionDecl) -> String {
+        let name = if function.ty == pt::FunctionTy::Constructor {
+            "constructor".to_string()
+        } else {
+            function.name.clone()
+        };
+        // Functions of base contracts which are not reachable by name are
+        // suffixed with the number of the base contract.
+        match function.base_contract {
+            Some(base_no) if function.overridden || function.ty == pt::FunctionTy::Constructor => {
+                format!("{}_{}", name, base_no)
+            }
+            _ => name,
+        }
+    }
+
+    fn render_function_args(
+        &self,
+        function: &FunctionDecl,
+        errors: &mut Vec<Output>,
+    ) -> Vec<Param> {
+        let mut result = Vec::new();
+        for (arg, param) in function.params.iter().enumerate() {
+            result.push(Param::new(
+                &self.render_param_name(function, arg),
+                self.render_type(&param.ty, &function.loc, errors),
+            ));
+        }
+        result
+    }
+
+    /// A parameter is named like the variable it is copied to
+    fn render_param_name(&self, function: &FunctionDecl, arg: usize) -> String {
+        match function
+            .cfg
+            .as_ref()
+            .and_then(|cfg| Some((argument_var(arg, cfg)?, cfg)))
+        {
+            Some((var, cfg)) => self.render_local_name(var, cfg),
+            None => function.params[arg].name.clone(),
+        }
+    }
+
+    /// Variables which are set more than once are declared as mutable at the
+    /// start of the function, and assigned where they are set. Arguments
+    /// which are set are mutable parameters instead.
+    fn render_function_body(
+        &self,
+        function: &FunctionDecl,
+        errors: &mut Vec<Output>,
+    ) -> Vec<rust::Stmt> {
+        let cfg = function.cfg.as_ref().unwrap();
+        let stmts = match structure(cfg, &function.loc, errors) {
+            Ok(stmts) => stmts,
+            Err(()) => return Vec::new(),
+        };
+        let args: Vec<usize> = (0..function.params.len())
+            .filter_map(|arg| argument_var(arg, cfg))
+            .collect();
+        let uninitialized = uninitialized_vars(&stmts, cfg);
+        let mut hoisted: BTreeSet<usize> = (0..cfg.vars.len())
+            .filter(|var_no| !args.contains(var_no))
+            .filter(|var_no| {
+                is_mutable(*var_no, cfg)
+                    || (uninitialized.contains(var_no)
+                        && self.slot_definition(*var_no, cfg).is_none())
+            })
+            .collect();
+        // there is no destructuring assignment, so the values of a tuple are
+        // either all declared where they are set or all assigned
+        for instr in cfg.bb.iter().flat_map(|bb| bb.instr.iter()) {
+            if let Instr::Call { res, .. } | Instr::AbiDecode { res, .. } = instr {
+                if res.len() > 1 && res.iter().any(|res| hoisted.contains(res)) {
+                    hoisted.extend(res);
+                }
+            }
+        }
+        let mut result = Vec::new();
+        for var_no in &hoisted {
+            let var = &cfg.vars[*var_no];
+            let default = uninitialized.contains(var_no);
+            result.push(rust::Stmt::Let(
+                Pat::Ident(
+                    default || is_mutable(*var_no, cfg),
+                    self.render_local_name(*var_no, cfg),
+                ),
+                Some(self.render_type(&var.ty, &var.id.loc, errors)),
+                if default {
+                    Some(self.render_zero(&var.ty, &var.id.loc, errors))
+                } else {
+                    None
+                },
+            ));
+        }
+        *self.hoisted.borrow_mut() = hoisted;
+        result.extend(self.render_stmts(&stmts, function, cfg, errors));
+        result
+    }
+
+    /// Set a variable, which is declared here unless it is declared at the top
+    /// of the function
+    fn render_definition(
+        &self,
+        var: usize,
+        value: Expr,
+        loc: &pt::Loc,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> rust::Stmt {
+        if is_mutable(var, cfg) || self.hoisted.borrow().contains(&var) {
+            rust::Stmt::Expr(Expr::assign(self.render_local_var(var, cfg), value))
+        } else {
+            rust::Stmt::let_(
+                &self.render_local_name(var, cfg),
+                Some(self.render_type(&cfg.vars[var].ty, loc, errors)),
+                value,
+            )
+        }
+    }
+
+    /// Set the variables to the values of a tuple
+    fn render_tuple_definition(
+        &self,
+        res: &[usize],
+        value: Expr,
+        ty: rust::Type,
+        cfg: &ControlFlowGraph,
+    ) -> rust::Stmt {
+        if !self.hoisted.borrow().contains(&res[0]) {
+            return rust::Stmt::Let(
+                Pat::Tuple(
+                    res.iter()
+                        .map(|res| Pat::ident(&self.render_local_name(*res, cfg)))
+                        .collect(),
+                ),
+                Some(ty),
+                Some(value),
+            );
+        }
+        let values = self.unused_name("values", cfg);
+        let mut stmts = vec![rust::Stmt::let_(&values, Some(ty), value)];
+        for (i, res) in res.iter().enumerate() {
+            stmts.push(rust::Stmt::Expr(Expr::assign(
+                self.render_local_var(*res, cfg),
+                Expr::Field(Box::new(Expr::path(&values)), i.to_string()),
+            )));
+        }
+        rust::Stmt::Expr(Expr::Block(Block::new(stmts)))
+    }
+
+    fn render_stmts(
+        &self,
+        stmts: &[Stmt],
+        function: &FunctionDecl,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Vec<rust::Stmt> {
+        let mut result = Vec::new();
+        for stmt in stmts {
+            result.extend(self.render_stmt(stmt, function, cfg, errors));
+        }
+        result
+    }
+
+    fn render_stmt(
+        &self,
+        stmt: &Stmt,
+        function: &FunctionDecl,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Vec<rust::Stmt> {
+        let expr = match stmt {
+            Stmt::BasicBlock(bb) => {
+                let mut result = Vec::new();
+                let mut reverted = false;
+                for instruction in &cfg.bb[*bb].instr {
+                    // `revert()` does not return, so the unreachable which follows it is dropped
+                    match instruction {
+                        Instr::Unreachable if reverted => continue,
+                        Instr::Call { func, .. } => {
+                            reverted = self.contract.functions[*func].name == "revert";
+                        }
+                        _ => reverted = false,
+                    }
+                    if let Some(i) = self.render_instruction(instruction, function, cfg, errors) {
+                        result.push(i)
+                    }
+                }
+                return result;
+            }
+            Stmt::If(bb, true_, false_) => {
+                let cond = match cfg.bb[*bb].instr.last() {
+                    Some(Instr::BranchCond { cond, .. }) => {
+                        self.render_expression(cond, cfg, errors)
+                    }
+                    _ => unreachable!(),
+                };
+                let mut else_stmts = self.render_stmts(false_, function, cfg, errors);
+                let else_expr = match else_stmts.len() {
+                    0 => None,
+                    // print as `else if`
+                    1 if is_if(&else_stmts[0]) => match else_stmts.pop() {
+                        Some(rust::Stmt::Expr(expr)) => Some(expr),
+                        _ => unreachable!(),
+                    },
+                    _ => Some(Expr::Block(Block::new(else_stmts))),
+                };
+                Expr::if_(
+                    cond,
+                    Block::new(self.render_stmts(true_, function, cfg, errors)),
+                    else_expr,
+                )
+            }
+            Stmt::Loop(bb, body) => Expr::Loop(
+                Some(format!("loop_{}", bb)),
+                Block::new(self.render_stmts(body, function, cfg, errors)),
+            ),
+            // A loop which never repeats, so that it can be exited with break
+            Stmt::Block(bb, body) => Expr::Loop(
+                Some(format!("block_{}", bb)),
+                Block::new(self.render_stmts(body, function, cfg, errors)),
+            ),
+            Stmt::Break(bb) => Expr::Break(Some(format!("block_{}", bb))),
+            Stmt::Continue(bb) => Expr::Continue(Some(format!("loop_{}", bb))),
+        };
+        vec![rust::Stmt::Expr(expr)]
+    }
+
+    fn render_instruction(
+        &self,
+        instruction: &Instr,
+        function: &FunctionDecl,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Option<rust::Stmt> {
+        let expr = match instruction {
+            // only expressions with side effects are kept
+            Instr::Eval { expr } => match expr {
+                Expression::StorageBytesPush(..) => self.render_expression(expr, cfg, errors),
+                Expression::StorageBytesPop(..) => {
+                    return Some(rust::Stmt::Let(
+                        Pat::Wild,
+                        None,
+                        Some(self.render_expression(expr, cfg, errors)),
+                    ))
+                }
+                _ => return None,
+            },
+            Instr::Return { value } => {
+                let mut values: Vec<Expr> = value
+                    .iter()
+                    .zip(&function.returns)
+                    .map(|(v, ret)| self.render_converted(v, &ret.ty, cfg, errors))
+                    .collect();
+                match values.len() {
+                    0 => Expr::Return(None),
+                    1 => Expr::Return(Some(Box::new(values.remove(0)))),
+                    _ => Expr::Return(Some(Box::new(Expr::Tuple(values)))),
+                }
+            }
+            Instr::SetStorage { ty, local, storage } => {
+                if is_dynamic_array(ty) {
+                    return self.unsupported(
+                        &storage.loc(),
+                        "assigning a whole dynamic storage array",
+                        errors,
+                    );
+                }
+                match self.storage_place(storage, cfg, errors) {
+                    Some((place, _)) => self.render_storage_store(
+                        place,
+                        self.render_local_var(*local, cfg),
+                        &storage.loc(),
+                        cfg,
+                        errors,
+                    ),
+                    None => Expr::call(
+                        Path::new("set_key"),
+                        vec![
+                            self.render_var_name_or_default(storage, cfg, errors),
+                            self.render_local_var(*local, cfg),
+                        ],
+                    ),
+                }
+            }
+            Instr::Set { res, expr } => {
+                // slots of array elements are rendered as keys where they are used
+                if self.slot_definition(*res, cfg).is_some() {
+                    return None;
+                }
+                let left = self.render_local_var(*res, cfg);
+                let mut right = self.render_converted(expr, &cfg.vars[*res].ty, cfg, errors);
+                if left == right {
+                    return None;
+                };
+                // memory values are copied rather than moved
+                match expr {
+                    Expression::Variable(..) | Expression::FunctionArg(..)
+                        if !is_copy(&cfg.vars[*res].ty) =>
+                    {
+                        right = right.method("clone", vec![]);
+                    }
+                    _ => {}
+                }
+                return Some(self.render_definition(
+                    *res,
+                    right,
+                    &cfg.vars[*res].id.loc,
+                    cfg,
+                    errors,
+                ));
+            }
+            Instr::Call { res, func, args } => {
+                let callee = self.contract.functions.get(*func).unwrap();
+                if let (Some(hash), [res], [arg]) =
+                    (builtin_hash(callee), res.as_slice(), args.as_slice())
+                {
+                    let hash = render_hash(hash, self.render_expression(arg, cfg, errors));
+                    return Some(self.render_definition(*res, hash, &arg.loc(), cfg, errors));
+                }
+                // Builtins are not rendered; only some have a helper in the footer
+                if is_blacklisted_fn(&callee.signature) && !is_helper_fn(&callee.signature) {
+                    let loc = match args.first() {
+                        Some(arg) => arg.loc(),
+                        None => function.loc,
+                    };
+                    return self.unsupported(&loc, &format!("‘{}’", callee.signature), errors);
+                }
+                let mut result = Vec::new();
+                if is_helper_fn(&callee.signature) {
+                    // the condition is kept, and the reason is replaced by the error code
+                    if callee.name != "revert" {
+                        result.push(self.render_expression(&args[0], cfg, errors));
+                    }
+                    let code = self
+                        .error_codes
+                        .iter()
+                        .find(|error| std::ptr::eq(error.instr, instruction))
+                        .map(|error| error.code)
+                        .unwrap_or(0);
+                    result.push(Expr::int(&BigInt::from(code)));
+                    return Some(rust::Stmt::Expr(Expr::call(
+                        Path::new(&callee.name),
+                        result,
+                    )));
+                }
+                for (arg, param) in args.iter().zip(&callee.params) {
+                    result.push(self.render_converted(arg, &param.ty, cfg, errors));
+                }
+                // `msg.value` is the same for internal calls
+                if self.takes_value(callee) {
+                    result.push(if self.takes_value(function) {
+                        Expr::path(&self.unused_name("value", cfg))
+                    } else {
+                        Expr::call(Path::new("U512::zero"), vec![])
+                    });
+                }
+                let call = Expr::call(Path::new(&self.render_internal_name(callee)), result);
+                return Some(match res.as_slice() {
+                    [] => rust::Stmt::Expr(call),
+                    [res] => self.render_definition(*res, call, &callee.loc, cfg, errors),
+                    _ => self.render_tuple_definition(
+                        res,
+                        call,
+                        self.render_return_type(&callee.returns, &callee.loc, errors),
+                        cfg,
+                    ),
+                });
+            }
+            // Branches are rendered as structured control flow
+            Instr::BranchCond { .. } | Instr::Branch { .. } => return None,
+            Instr::ClearStorage { ty, storage } => match self.storage_place(storage, cfg, errors) {
+                // a missing key reads as the default value
+                Some((ref place, _)) if place.members.is_empty() => {
+                    Expr::call(Path::new("runtime::remove_key"), vec![place.key.clone()])
+                }
+                Some((place, _)) => self.render_storage_store(
+                    place,
+                    self.render_zero(ty, &storage.loc(), errors),
+                    &storage.loc(),
+                    cfg,
+                    errors,
+                ),
+                None => return self.unsupported(&storage.loc(), "‘delete’ of storage", errors),
+            },
+            Instr::SetStorageBytes { storage, .. } => {
+                return self.unsupported(
+                    &storage.loc(),
+                    "assigning to an index of storage bytes",
+                    errors,
+                )
+            }
+            Instr::Constant { res, .. } => {
+                return self.unsupported(&cfg.vars[*res].id.loc, "constant array", errors)
+            }
+            Instr::Store { dest, pos } => Expr::assign(
+                self.render_expression(dest, cfg, errors),
+                self.render_local_var(*pos, cfg),
+            ),
+            Instr::AssertFailure { .. } => {
+                return self.render_instruction(&Instr::Unreachable, function, cfg, errors)
+            }
+            Instr::Print { expr } => return self.unsupported(&expr.loc(), "‘print’", errors),
+            Instr::Constructor {
+                success: None,
+                res,
+                contract_no,
+                constructor_no,
+                args,
+                value,
+                salt: None,
+                ..
+            } => {
+                let mut result = Vec::new();
+                let constructor = self.ns.contracts[*contract_no]
+                    .functions
+                    .iter()
+                    .filter(|f| f.is_constructor())
+                    .nth(*constructor_no);
+                if let Some(constructor) = constructor {
+                    for (arg, param) in args.iter().zip(&constructor.params) {
+                        result.push(self.render_converted(arg, &param.ty, cfg, errors));
+                    }
+                }
+                let payable = self.ns.contracts[*contract_no]
+                    .functions
+                    .iter()
+                    .any(|f| f.is_constructor() && f.is_payable());
+                match value {
+                    // the installer passes the purse to the constructor
+                    Some(value) if payable => result.push(Expr::call(
+                        Path::new("new_purse"),
+                        vec![self.render_expression(value, cfg, errors)],
+                    )),
+                    Some(value) => {
+                        return self.unsupported(
+                            &value.loc(),
+                            "sending value to a constructor which is not payable",
+                            errors,
+                        )
+                    }
+                    None if payable => result.push(Expr::call(
+                        Path::new("new_purse"),
+                        vec![Expr::call(Path::new("U512::zero"), vec![])],
+                    )),
+                    None => {}
+                }
+                let name = format!("new_{}", self.ns.contracts[*contract_no].name);
+                return Some(self.render_definition(
+                    *res,
+                    Expr::call(Path::new(&name), result),
+                    &function.loc,
+                    cfg,
+                    errors,
+                ));
+            }
+            Instr::Constructor {
+                success: Some(_), ..
+            } => return self.unsupported(&function.loc, "‘try’", errors),
+            Instr::Constructor { .. } => return self.unsupported(&function.loc, "‘salt’", errors),
+            Instr::ExternalCall {
+                success: None,
+                address,
+                contract_no: Some(contract_no),
+                function_no,
+                args,
+                value,
+                ..
+            } => {
+                let callee = &self.ns.contracts[*contract_no].functions[*function_no];
+                // the entry points of the callee read their arguments by name
+                let mut named_args = Vec::new();
+                for (param, arg) in callee.params.iter().zip(args) {
+                    named_args.push((
+                        Expr::str(&param.name),
+                        self.render_converted(arg, &param.ty, cfg, errors),
+                    ));
+                }
+                // a payable entry point takes a purse with the value
+                if callee.is_payable() {
+                    named_args.push((
+                        Expr::str("purse"),
+                        Expr::call(
+                            Path::new("new_purse"),
+                            vec![self.render_expression(value, cfg, errors)],
+                        ),
+                    ));
+                } else {
+                    match value {
+                        Expression::NumberLiteral(_, _, n) if n.is_zero() => {}
+                        _ => {
+                            return self.unsupported(
+                                &value.loc(),
+                                "sending value to a function which is not payable",
+                                errors,
+                            )
+                        }
+                    }
+                }
+                let args = vec![
+                    self.render_contract_hash(address, cfg, errors),
+                    Expr::str(&self.render_function_name(callee)),
+                    Expr::Macro(
+                        Path::new("runtime_args"),
+                        Delimiter::Brace,
+                        MacroArgs::Map(named_args),
+                    ),
+                ];
+                if callee.returns.is_empty() {
+                    let path =
+                        Path::new("runtime::call_contract").with_args(vec![rust::Type::unit()]);
+                    Expr::call(path, args)
+                } else {
+                    // the values are bound to the result variables by the following AbiDecode
+                    return Some(rust::Stmt::let_(
+                        &self.unused_name("return_data", cfg),
+                        Some(self.render_return_type(&callee.returns, &callee.loc, errors)),
+                        Expr::call(Path::new("runtime::call_contract"), args),
+                    ));
+                }
+            }
+            Instr::ExternalCall {
+                success: Some(_),
+                address,
+                contract_no: Some(_),
+                ..
+            } => return self.unsupported(&address.loc(), "‘try’", errors),
+            // `transfer()` and `send()` move motes from the purse of the contract
+            // to the main purse of an account
+            Instr::ExternalCall {
+                success,
+                address,
+                contract_no: None,
+                value,
+                ..
+            } => {
+                let transfer = Expr::call(
+                    Path::new("system::transfer_from_purse_to_account"),
+                    vec![
+                        Expr::call(Path::new("contract_purse"), vec![]),
+                        self.render_expression(address, cfg, errors),
+                        self.render_expression(value, cfg, errors),
+                    ],
+                );
+                match success {
+                    Some(success) => {
+                        return Some(self.render_definition(
+                            *success,
+                            transfer.method("is_ok", vec![]),
+                            &address.loc(),
+                            cfg,
+                            errors,
+                        ))
+                    }
+                    None => transfer.method("unwrap_or_revert", vec![]),
+                }
+            }
+            Instr::AbiDecode {
+                res,
+                selector: None,
+                exception: None,
+                tys,
+                data: data @ Expression::ReturnData(_),
+            } => {
+                let return_data = Expr::path(&self.unused_name("return_data", cfg));
+                return Some(match res.as_slice() {
+                    [res] => self.render_definition(*res, return_data, &data.loc(), cfg, errors),
+                    _ => self.render_tuple_definition(
+                        res,
+                        return_data,
+                        self.render_return_type(tys, &data.loc(), errors),
+                        cfg,
+                    ),
+                });
+            }
+            Instr::AbiDecode { data, .. } => {
+                return self.unsupported(&data.loc(), "abi decoding", errors)
+            }
+            // failures which are not a call to `require()`, `revert()` or `assert()`
+            Instr::Unreachable => Expr::call(Path::new("revert"), vec![Expr::int(&BigInt::zero())]),
+            Instr::SelfDestruct { recipient } => {
+                return self.unsupported(&recipient.loc(), "‘selfdestruct’", errors)
+            }
+            Instr::Hash { res, hash, expr } => {
+                let hash = render_hash(hash, self.render_expression(expr, cfg, errors));
+                return Some(self.render_definition(*res, hash, &expr.loc(), cfg, errors));
+            }
+            Instr::EmitEvent { event_no, args } => {
+                let event = &self.contract.events[*event_no];
+                let mut fields = Vec::new();
+                for (i, arg) in args.iter().enumerate() {
+                    let name = match event.fields[i].name.as_str() {
+                        "" => format!("{}", i),
+                        name => name.to_string(),
+                    };
+                    let value = Expr::call(
+                        Path::new("CLValue::from_t"),
+                        vec![self.render_converted(arg, &event.fields[i].ty, cfg, errors)],
+                    );
+                    fields.push(Expr::Tuple(vec![
+                        Expr::str(&name),
+                        value.method("unwrap_or_revert", vec![]),
+                    ]));
+                }
+                Expr::call(
+                    Path::new("emit_event"),
+                    vec![
+                        Expr::str(&event.name),
+                        Expr::macro_call("vec", Delimiter::Bracket, fields),
+                    ],
+                )
+            }
+        };
+        Some(rust::Stmt::Expr(expr))
+    }
+
+    /// Render the helper with this name in the footer
+    fn use_helper(&self, name: &'static str) {
+        self.helpers.borrow_mut().insert(name);
+    }
+
+    /// Report that a construct cannot be transpiled
+    fn unsupported<T>(&self, loc: &pt::Loc, what: &str, errors: &mut Vec<Output>) -> Option<T> {
+        errors.push(Output::error(
+            *loc,
+            format!("{} is not supported on target {}", what, Target::Casperlabs),
+        ));
+        None
+    }
+
+    fn render_expression(
+        &self,
+        expression: &Expression,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Expr {
+        // the operands of an integer operator are converted to the same type
+        let binary =
+            |op: BinOp, l: &Expression, r: &Expression, errors: &mut Vec<Output>| match self
+                .int_operand_type(l, r, cfg)
+            {
+                Some(ty) => Expr::binary(
+                    op,
+                    self.render_converted(l, &ty, cfg, errors),
+                    self.render_converted(r, &ty, cfg, errors),
+                ),
+                None => Expr::binary(
+                    op,
+                    self.render_expression(l, cfg, errors),
+                    self.render_expression(r, cfg, errors),
+                ),
+            };
+        // an enum is compared with a literal as an enum, and with an integer as a `u8`;
+        // a fixed length byte array is compared with a literal as an array
+        let compare = |op: BinOp, l: &Expression, r: &Expression, errors: &mut Vec<Output>| {
+            let ty = match (self.expression_type(l, cfg), self.expression_type(r, cfg)) {
+                (Some(ty @ Type::Enum(_)), None) | (None, Some(ty @ Type::Enum(_))) => ty,
+                (Some(Type::Enum(n)), Some(Type::Enum(m))) if n == m => Type::Enum(n),
+                (Some(Type::Enum(_)), _) | (_, Some(Type::Enum(_))) => Type::Uint(8),
+                (Some(ty @ Type::Bytes(_)), _) | (_, Some(ty @ Type::Bytes(_))) => ty,
+                _ => return binary(op, l, r, errors),
+            };
+            Expr::binary(
+                op,
+                self.render_converted(l, &ty, cfg, errors),
+                self.render_converted(r, &ty, cfg, errors),
+            )
+        };
+        // fixed length byte arrays are combined byte by byte, and `I256` has
+        // methods named after the operators
+        let bitwise = |op: BinOp,
+                       helper: &str,
+                       method: &str,
+                       l: &Expression,
+                       r: &Expression,
+                       errors: &mut Vec<Output>| {
+            match self
+                .expression_type(l, cfg)
+                .or_else(|| self.expression_type(r, cfg))
+            {
+                Some(ty @ Type::Bytes(_)) => Expr::call(
+                    Path::new(helper),
+                    vec![
+                        self.render_converted(l, &ty, cfg, errors),
+                        self.render_converted(r, &ty, cfg, errors),
+                    ],
+                ),
+                Some(ref ty) if int_type(ty) == Some(("I256", 256)) => self
+                    .render_receiver(l, ty, cfg, errors)
+                    .method(method, vec![self.render_converted(r, ty, cfg, errors)]),
+                _ => binary(op, l, r, errors),
+            }
+        };
+        match expression {
+            // Literals
+            Expression::FunctionArg(_, pos) => self.render_local_var(*pos, cfg),
+            Expression::BoolLiteral(_, value) => Expr::bool(*value),
+            Expression::BytesLiteral(_, s) => {
+                Expr::Array(s.iter().map(|b| Expr::int(&BigInt::from(*b))).collect())
+            }
+            // the wide integers have no literals
+            Expression::NumberLiteral(_, bits, n) if *bits > 64 => {
+                self.render_number(n, &Type::Uint(*bits))
+            }
+            Expression::NumberLiteral(_, _bits, n) => Expr::int(n),
+            Expression::StructLiteral(_, ty, exprs) => match ty {
+                Type::Struct(n) => {
+                    let mut fields = Vec::new();
+                    for (field, expr) in self.ns.structs[*n].fields.iter().zip(exprs) {
+                        fields.push((
+                            field.name.clone(),
+                            self.render_converted(expr, &field.ty, cfg, errors),
+                        ));
+                    }
+                    Expr::Struct(Path::new(&self.render_struct_name(*n)), fields)
+                }
+                _ => unreachable!(),
+            },
+            // Expression::ConstArrayLiteral(_, dims, exprs) =>
+            Expression::ArrayLiteral(_, _, dims, exprs) => {
+                self.render_static_array(dims, exprs, cfg, errors)
+            }
+
+            // Arithmetic
+            Expression::Add(_, l, r) => self
+                .render_wrapping("add", l, r, cfg, errors)
+                .unwrap_or_else(|| binary(BinOp::Add, l, r, errors)),
+            Expression::Subtract(_, l, r) => self
+                .render_wrapping("sub", l, r, cfg, errors)
+                .unwrap_or_else(|| binary(BinOp::Sub, l, r, errors)),
+            Expression::BitwiseOr(_, l, r) => {
+                bitwise(BinOp::BitOr, "bytes_or", "bitor", l, r, errors)
+            }
+            Expression::BitwiseAnd(_, l, r) => {
+                bitwise(BinOp::BitAnd, "bytes_and", "bitand", l, r, errors)
+            }
+            Expression::BitwiseXor(_, l, r) => {
+                bitwise(BinOp::BitXor, "bytes_xor", "bitxor", l, r, errors)
+            }
+            // a fixed length byte array is lengthened by shifting its extension
+            Expression::ShiftLeft(_, l, r) if self.fixed_bytes(l, cfg).is_some() => {
+                match (&**l, &**r) {
+                    (Expression::ZeroExt(_, ty, value), Expression::NumberLiteral(..))
+                        if self.fixed_bytes(value, cfg).is_some() =>
+                    {
+                        self.render_resize(value, ty, cfg, errors)
+                    }
+                    _ => Expr::call(
+                        Path::new("bytes_shl"),
+                        vec![
+                            self.render_expression(l, cfg, errors),
+                            self.render_shift_amount(
+                                r,
+                                self.fixed_bytes(l, cfg).unwrap() as u16 * 8,
+                                "usize",
+                                cfg,
+                                errors,
+                            )
+                            .0,
+                        ],
+                    ),
+                }
+            }
+            Expression::ShiftRight(_, l, r, _) if self.fixed_bytes(l, cfg).is_some() => Expr::call(
+                Path::new("bytes_shr"),
+                vec![
+                    self.render_expression(l, cfg, errors),
+                    self.render_shift_amount(
+                        r,
+                        self.fixed_bytes(l, cfg).unwrap() as u16 * 8,
+                        "usize",
+                        cfg,
+                        errors,
+                    )
+                    .0,
+                ],
+            ),
+            Expression::ShiftLeft(loc, l, r) => self
+                .render_shift(true, l, r, cfg, errors)
+                .unwrap_or_else(|| {
+                    self.unsupported::<Expr>(loc, "shift", errors);
+                    Expr::unit()
+                }),
+            Expression::ShiftRight(loc, l, r, _) => self
+                .render_shift(false, l, r, cfg, errors)
+                .unwrap_or_else(|| {
+                    self.unsupported::<Expr>(loc, "shift", errors);
+                    Expr::unit()
+                }),
+            Expression::Multiply(_, l, r) => self
+                .render_wrapping("mul", l, r, cfg, errors)
+                .unwrap_or_else(|| binary(BinOp::Mul, l, r, errors)),
+            // only a signed division overflows
+            Expression::UDivide(_, l, r) => binary(BinOp::Div, l, r, errors),
+            Expression::SDivide(_, l, r) => self
+                .render_wrapping("div", l, r, cfg, errors)
+                .unwrap_or_else(|| binary(BinOp::Div, l, r, errors)),
+            Expression::UModulo(_, l, r) => binary(BinOp::Rem, l, r, errors),
+            Expression::SModulo(_, l, r) => self
+                .render_wrapping("rem", l, r, cfg, errors)
+                .unwrap_or_else(|| binary(BinOp::Rem, l, r, errors)),
+            // a power of literals is computed here, as the wide integers have no literals
+            Expression::Power(loc, l, _) if literal_value(expression).is_some() => {
+                let n = literal_value(expression).unwrap();
+                let bits = match l.as_ref() {
+                    Expression::NumberLiteral(_, bits, _) => {
+                        (*bits).max(n.to_bytes_be().1.len() as u16 * 8)
+                    }
+                    _ => 256,
+                };
+                self.render_expression(&Expression::NumberLiteral(*loc, bits, n), cfg, errors)
+            }
+            // the exponent of a primitive integer is a `u32`
+            Expression::Power(_, l, r) => match self.int_operand_type(l, r, cfg) {
+                Some(ty) if int_type(&ty).unwrap().1 <= 64 => {
+                    let result = self
+                        .render_receiver(l, &ty, cfg, errors)
+                        .method("wrapping_pow", vec![self.render_u32(r, &ty, cfg, errors)]);
+                    self.render_wrapped(result, &ty)
+                }
+                _ => self
+                    .render_wrapping("pow", l, r, cfg, errors)
+                    .unwrap_or_else(|| {
+                        let base = self.render_expression(l, cfg, errors);
+                        base.method("pow", vec![self.render_expression(r, cfg, errors)])
+                    }),
+            },
+
+            // Data
+            Expression::Variable(_, res) => self.render_local_var(*res, cfg),
+            Expression::StorageLoad(loc, ty, _) if is_dynamic_array(ty) => {
+                self.unsupported::<Expr>(loc, "copying a whole dynamic storage array", errors);
+                Expr::unit()
+            }
+            Expression::StorageLoad(loc, ty, expr) => match self.storage_place(expr, cfg, errors) {
+                Some((place, _)) => {
+                    let mut result = self.render_storage_load(&place, loc, errors);
+                    for member in place.members {
+                        result = member.access(result);
+                    }
+                    result
+                }
+                None => self.render_get_key(
+                    self.render_var_name_or_default(expr, cfg, errors),
+                    ty,
+                    loc,
+                    errors,
+                ),
+            },
+            Expression::Load(_, expr) => {
+                let result = self.render_expression(expr, cfg, errors);
+                match self.expression_type(expr, cfg) {
+                    Some(ref ty) if is_copy(ty) => result,
+                    _ => result.method("clone", vec![]),
+                }
+            }
+            Expression::ZeroExt(_, ty, expr) | Expression::SignExt(_, ty, expr) => {
+                self.render_converted(expr, ty, cfg, errors)
+            }
+            // indexing and shortening a fixed length byte array are truncations of a shift
+            Expression::Trunc(loc, ty @ Type::Bytes(_), expr) => {
+                match (bytes_index(expression), &**expr) {
+                    (Some((array, index)), _) => Expr::Array(vec![Expr::Index(
+                        Box::new(self.render_expression(array, cfg, errors)),
+                        Box::new(self.render_usize(index, cfg, errors)),
+                    )]),
+                    (None, Expression::ShiftRight(_, value, _, _))
+                        if self.fixed_bytes(value, cfg).is_some() =>
+                    {
+                        self.render_resize(value, ty, cfg, errors)
+                    }
+                    _ => {
+                        self.unsupported::<Expr>(loc, "truncation", errors);
+                        Expr::unit()
+                    }
+                }
+            }
+            Expression::Trunc(_, ty, expr) if int_type(ty).is_some() => {
+                self.render_converted(expr, ty, cfg, errors)
+            }
+
+            // Comparators
+            Expression::SMore(_, l, r) | Expression::UMore(_, l, r) => {
+                compare(BinOp::Gt, l, r, errors)
+            }
+            Expression::SLess(_, l, r) | Expression::ULess(_, l, r) => {
+                compare(BinOp::Lt, l, r, errors)
+            }
+            Expression::SMoreEqual(_, l, r) | Expression::UMoreEqual(_, l, r) => {
+                compare(BinOp::Ge, l, r, errors)
+            }
+            Expression::SLessEqual(_, l, r) | Expression::ULessEqual(_, l, r) => {
+                compare(BinOp::Le, l, r, errors)
+            }
+            Expression::Equal(_, l, r) => compare(BinOp::Eq, l, r, errors),
+            Expression::NotEqual(_, l, r) => compare(BinOp::Ne, l, r, errors),
+
+            // Arrays and Structs
+            Expression::ArraySubscript(_, a, i) => Expr::Index(
+                Box::new(self.render_expression(a, cfg, errors)),
+                Box::new(Expr::Cast(
+                    Box::new(self.render_expression(i, cfg, errors)),
+                    rust::Type::path("usize"),
+                )),
+            ),
+            Expression::DynamicArraySubscript(_, a, _, i) => Expr::Index(
+                Box::new(self.render_expression(a, cfg, errors)),
+                Box::new(Expr::Cast(
+                    Box::new(self.render_expression(i, cfg, errors)),
+                    rust::Type::path("usize"),
+                )),
+            ),
+            Expression::DynamicArrayLength(_, a) => Expr::Cast(
+                Box::new(self.render_expression(a, cfg, errors).method("len", vec![])),
+                rust::Type::path("u32"),
+            ),
+            Expression::AllocDynamicArray(_, Type::String, _, Some(init)) => Expr::call(
+                Path::new("String::from"),
+                vec![Expr::str(&String::from_utf8_lossy(init))],
+            ),
+            Expression::AllocDynamicArray(_, _, _, Some(init)) => Expr::macro_call(
+                "vec",
+                Delimiter::Bracket,
+                init.iter().map(|b| Expr::int(&BigInt::from(*b))).collect(),
+            ),
+            Expression::AllocDynamicArray(loc, ty, size, None) => Expr::Macro(
+                Path::new("vec"),
+                Delimiter::Bracket,
+                MacroArgs::Repeat(
+                    Box::new(self.render_zero(&ty.array_deref(), loc, errors)),
+                    Box::new(Expr::Cast(
+                        Box::new(self.render_expression(size, cfg, errors)),
+                        rust::Type::path("usize"),
+                    )),
+                ),
+            ),
+            // storage bytes are stored as a whole under their key
+            Expression::StorageBytesPush(loc, a, value) => match self.storage_place(a, cfg, errors)
+            {
+                Some((place, _)) => {
+                    let bytes = self.unused_name("bytes", cfg);
+                    Expr::Block(Block::new(vec![
+                        rust::Stmt::Let(
+                            Pat::Ident(true, bytes.clone()),
+                            None,
+                            Some(self.render_storage_load(&place, loc, errors)),
+                        ),
+                        rust::Stmt::Expr(Expr::path(&bytes).method(
+                            "push",
+                            vec![self.render_converted(value, &Type::Uint(8), cfg, errors)],
+                        )),
+                        rust::Stmt::Expr(Expr::call(
+                            Path::new("set_key"),
+                            vec![place.key, Expr::path(&bytes)],
+                        )),
+                    ]))
+                }
+                None => {
+                    self.unsupported::<Expr>(loc, "dynamic storage bytes", errors);
+                    Expr::unit()
+                }
+            },
+            Expression::StorageBytesPop(loc, a) => match self.storage_place(a, cfg, errors) {
+                Some((place, _)) => {
+                    let bytes = self.unused_name("bytes", cfg);
+                    let last = self.unused_name("last", cfg);
+                    Expr::Block(Block::value(
+                        vec![
+                            rust::Stmt::Let(
+                                Pat::Ident(true, bytes.clone()),
+                                None,
+                                Some(self.render_storage_load(&place, loc, errors)),
+                            ),
+                            rust::Stmt::let_(
+                                &last,
+                                None,
+                                Expr::path(&bytes)
+                                    .method("pop", vec![])
+                                    .method("unwrap_or_revert", vec![]),
+                            ),
+                            rust::Stmt::Expr(Expr::call(
+                                Path::new("set_key"),
+                                vec![place.key, Expr::path(&bytes)],
+                            )),
+                        ],
+                        Expr::Array(vec![Expr::path(&last)]),
+                    ))
+                }
+                None => {
+                    self.unsupported::<Expr>(loc, "dynamic storage bytes", errors);
+                    Expr::unit()
+                }
+            },
+            Expression::StorageBytesLength(loc, a) => match self.storage_place(a, cfg, errors) {
+                Some((place, _)) => Expr::Cast(
+                    Box::new(
+                        self.render_storage_load(&place, loc, errors)
+                            .method("len", vec![]),
+                    ),
+                    rust::Type::path("u32"),
+                ),
+                None => {
+                    self.unsupported::<Expr>(loc, "dynamic storage bytes", errors);
+                    Expr::unit()
+                }
+            },
+            Expression::StructMember(loc, expr, field) => {
+                match self
+                    .expression_type(expr, cfg)
+                    .as_ref()
+                    .map(|ty| ty.deref())
+                {
+                    Some(Type::Struct(n)) => Expr::Field(
+                        Box::new(self.render_expression(expr, cfg, errors)),
+                        self.ns.structs[*n].fields[*field].name.clone(),
+                    ),
+                    _ => {
+                        self.unsupported::<Expr>(loc, "struct", errors);
+                        Expr::unit()
+                    }
+                }
+            }
+
+            // Bool operators
+            Expression::Or(_, l, r) => binary(BinOp::Or, l, r, errors),
+            Expression::And(_, l, r) => binary(BinOp::And, l, r, errors),
+            Expression::Ternary(_, c, l, r) => Expr::if_(
+                self.render_expression(c, cfg, errors),
+                Block::value(vec![], self.render_expression(l, cfg, errors)),
+                Some(Expr::Block(Block::value(
+                    vec![],
+                    self.render_expression(r, cfg, errors),
+                ))),
+            ),
+            Expression::Not(_, expr) => {
+                Expr::unary(UnOp::Not, self.render_expression(expr, cfg, errors))
+            }
+            Expression::Complement(_, expr) => match self.expression_type(expr, cfg) {
+                Some(Type::Bytes(_)) => Expr::call(
+                    Path::new("bytes_not"),
+                    vec![self.render_expression(expr, cfg, errors)],
+                ),
+                Some(ref ty) if int_type(ty).is_some() => {
+                    let value = self.render_expression(expr, cfg, errors);
+                    let result = match int_type(ty) {
+                        Some(("I256", _)) => value.method("not", vec![]),
+                        _ => Expr::unary(UnOp::Not, value),
+                    };
+                    self.render_wrapped(result, ty)
+                }
+                _ => Expr::unary(UnOp::Not, self.render_expression(expr, cfg, errors)),
+            },
+            Expression::UnaryMinus(_, expr) => match self.expression_type(expr, cfg) {
+                Some(ref ty) if int_type(ty).is_some() => {
+                    let value = self.render_expression(expr, cfg, errors);
+                    let result = match int_type(ty) {
+                        Some((name, _)) if name.starts_with('U') => Expr::Field(
+                            Box::new(value.method("overflowing_neg", vec![])),
+                            "0".to_string(),
+                        ),
+                        _ => value.method("wrapping_neg", vec![]),
+                    };
+                    self.render_wrapped(result, ty)
+                }
+                _ => Expr::unary(UnOp::Neg, self.render_expression(expr, cfg, errors)),
+            },
+
+            // Others
+            // Expression::Poison => "☠".to_string(),
+            // Expression::Unreachable => "❌".to_string(),
+            // Expression::StringCompare(_, l, r) => format!(
+            // Expression::StringConcat(_, l, r) => format!(
+            // Expression::LocalFunctionCall(_, f, args) => format!(
+            // Expression::Constructor {
+            //     contract_no,
+            //     constructor_no,
+            //     args,
+            //     ..
+            // } =>
+            // Expression::CodeLiteral(_, contract_no, runtime) => format!(
+            Expression::Sender(_) => Expr::call(Path::new("runtime::get_caller"), vec![]),
+            Expression::Value(_) => Expr::path(&self.unused_name("value", cfg)),
+            // the block time is in milliseconds, and only converts into a `u64`
+            Expression::Timestamp(_) => {
+                let mut into = Path::new("Into::into");
+                into.segments[0].args = vec![rust::Type::path("u64")];
+                Expr::binary(
+                    BinOp::Div,
+                    Expr::call(
+                        into,
+                        vec![Expr::call(Path::new("runtime::get_blocktime"), vec![])],
+                    ),
+                    Expr::int(&BigInt::from(1000)),
+                )
+            }
+            // the installer stores the contract hash under a named key of the contract
+            Expression::GetAddress(loc) if self.contract_macro => {
+                errors.push(Output::error(
+                    *loc,
+                    format!(
+                        "‘this’ requires --no-contract-macro on target {}",
+                        Target::Casperlabs
+                    ),
+                ));
+                Expr::unit()
+            }
+            Expression::GetAddress(_) => Expr::call(
+                Path::new("get_key").with_args(vec![rust::Type::path("ContractHash")]),
+                vec![Expr::str("__this")],
+            ),
+            Expression::Balance(_, address) => match address.as_ref() {
+                Expression::GetAddress(_) => Expr::call(
+                    Path::new("system::get_balance"),
+                    vec![Expr::call(Path::new("contract_purse"), vec![])],
+                )
+                .method("unwrap_or_revert", vec![]),
+                _ => {
+                    self.unsupported::<Expr>(
+                        &address.loc(),
+                        "‘balance’ of an address other than ‘address(this)’",
+                        errors,
+                    );
+                    Expr::unit()
+                }
+            },
+            // Expression::ExternalFunctionCall {
+            //     function_no,
+            //     contract_no,
+            //     address,
+            //     args,
+            //     ..
+            // } => format!(
+            Expression::Keccak256(loc, _) => match self.storage_place(expression, cfg, errors) {
+                Some((place, _)) => place.key,
+                None => {
+                    self.unsupported::<Expr>(loc, "storage slot", errors);
+                    Expr::unit()
+                }
+            },
+            _ => {
+                self.unsupported::<Expr>(&expression.loc(), expression_name(expression), errors);
+                Expr::unit()
+            }
+        }
+    }
+
+    /// Render an expression as a value of the given type. Enum values and
+    /// fixed length byte arrays are numbers in the control flow graph, and
+    /// casts between those, integers and addresses of the same size are not
+    /// explicit, so these are converted where the types differ.
+    fn render_converted(
+        &self,
+        expr: &Expression,
+        ty: &Type,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Expr {
+        let ty = ty.deref();
+        match (ty, self.expression_type(expr, cfg)) {
+            (Type::Enum(n), Some(Type::Enum(m))) if *n == m => {
+                self.render_expression(expr, cfg, errors)
+            }
+            (Type::Enum(n), _) => {
+                let name = self.render_enum_name(*n);
+                let value = match expr {
+                    Expression::NumberLiteral(_, _, value) => self.ns.enums[*n]
+                        .values
+                        .iter()
+                        .find(|(_, (_, v))| value.to_usize() == Some(*v))
+                        .map(|(variant, _)| variant),
+                    _ => None,
+                };
+                match value {
+                    Some(variant) => Expr::path(&format!("{}::{}", name, variant)),
+                    None => Expr::call(
+                        Path::new(&format!("{}::from", name)),
+                        vec![self.render_converted(expr, &Type::Uint(8), cfg, errors)],
+                    ),
+                }
+            }
+            // the discriminant is a `u8`
+            (_, Some(Type::Enum(_))) => {
+                let value = self.render_expression(expr, cfg, errors);
+                match int_type(ty) {
+                    // every discriminant fits in the primitive integers other than `i8`
+                    Some((name, _))
+                        if *ty != Type::Int(8) && name.starts_with(char::is_lowercase) =>
+                    {
+                        Expr::Cast(Box::new(value), rust::Type::path(name))
+                    }
+                    Some(_) => self.render_int_cast(
+                        Expr::Cast(Box::new(value), rust::Type::path("u8")),
+                        &Type::Uint(8),
+                        ty,
+                    ),
+                    None => Expr::Cast(Box::new(value), rust::Type::path("u8")),
+                }
+            }
+            // byte arrays are big-endian, like the numbers they convert to and from
+            (Type::Bytes(n), from) => {
+                if let Expression::NumberLiteral(_, _, value) = expr {
+                    let (_, bytes) = value.to_bytes_be();
+                    let mut result =
+                        vec![Expr::int(&BigInt::zero()); (*n as usize).saturating_sub(bytes.len())];
+                    result.extend(bytes.iter().map(|b| Expr::int(&BigInt::from(*b))));
+                    return Expr::Array(result);
+                }
+                let value = self.render_expression(expr, cfg, errors);
+                match from.as_ref().map(|from| (from, int_type(from))) {
+                    Some((_, Some(("U256", _)))) if *n == 32 => {
+                        Expr::call(Path::new("u256_bytes"), vec![value])
+                    }
+                    Some((_, Some((name, width)))) if width == *n as u16 * 8 && name != "U128" => {
+                        value.method("to_be_bytes", vec![])
+                    }
+                    // the bytes of any other integer are the last bytes of a `U256`
+                    Some((from, Some(_))) => {
+                        let bytes = Expr::call(
+                            Path::new("u256_bytes"),
+                            vec![self.render_int_cast(value, from, &Type::Uint(256))],
+                        );
+                        let shifted = Expr::call(
+                            Path::new("bytes_shl"),
+                            vec![bytes, Expr::int(&BigInt::from((32 - *n as usize) * 8))],
+                        );
+                        Expr::call(
+                            Path::new("bytes_resize").with_args(vec![
+                                rust::Type::path("_"),
+                                self.render_type(ty, &expr.loc(), errors),
+                            ]),
+                            vec![shifted],
+                        )
+                    }
+                    Some((Type::Address(_), _)) => value.method("value", vec![]),
+                    _ => value,
+                }
+            }
+            (Type::Uint(_), Some(Type::Bytes(n))) | (Type::Int(_), Some(Type::Bytes(n))) => {
+                let value = self.render_expression(expr, cfg, errors);
+                match int_type(ty) {
+                    Some((name, width)) if width == n as u16 * 8 && width <= 64 => {
+                        Expr::call(Path::new(&format!("{}::from_be_bytes", name)), vec![value])
+                    }
+                    _ => self.render_int_cast(
+                        Expr::call(Path::new("U256::from_big_endian"), vec![value.reference()]),
+                        &Type::Uint(256),
+                        ty,
+                    ),
+                }
+            }
+            // an address is the big-endian bytes of a `U256`
+            (Type::Uint(_), Some(Type::Address(_))) | (Type::Int(_), Some(Type::Address(_))) => {
+                let word = Expr::call(
+                    Path::new("U256::from_big_endian"),
+                    vec![self
+                        .render_expression(expr, cfg, errors)
+                        .method("value", vec![])
+                        .reference()],
+                );
+                self.render_int_cast(word, &Type::Uint(256), ty)
+            }
+            // an account hash and a contract hash are both 32 bytes
+            (Type::Address(_), Some(Type::Bytes(_)))
+            | (Type::Address(_), Some(Type::Contract(_))) => Expr::call(
+                Path::new("AccountHash::new"),
+                vec![self.render_expression(expr, cfg, errors)],
+            ),
+            (Type::Address(_), Some(Type::Uint(_)))
+            | (Type::Address(_), Some(Type::Int(_)))
+            | (Type::Address(_), None)
+                if self.expression_type(expr, cfg).is_some() || literal_value(expr).is_some() =>
+            {
+                self.use_helper("u256_bytes");
+                Expr::call(
+                    Path::new("AccountHash::new"),
+                    vec![Expr::call(
+                        Path::new("u256_bytes"),
+                        vec![self.render_converted(expr, &Type::Uint(256), cfg, errors)],
+                    )],
+                )
+            }
+            (Type::Address(_), Some(from)) if !matches!(from, Type::Address(_)) => {
+                let what = format!("conversion from {} to address", from.to_string(self.ns));
+                self.unsupported::<()>(&expr.loc(), &what, errors);
+                Expr::unit()
+            }
+            (Type::Contract(_), Some(Type::Address(_))) => self
+                .render_expression(expr, cfg, errors)
+                .method("value", vec![]),
+            // the wide integers have no literals
+            (Type::Uint(_), None) | (Type::Int(_), None) => match literal_value(expr) {
+                Some(n) if int_type(ty).unwrap().1 > 64 => self.render_number(&n, ty),
+                _ => self.render_expression(expr, cfg, errors),
+            },
+            (Type::Uint(_), Some(from)) | (Type::Int(_), Some(from))
+                if int_type(&from).is_some() && from != *ty =>
+            {
+                let value = self.render_expression(expr, cfg, errors);
+                self.render_int_cast(value, &from, ty)
+            }
+            _ => self.render_expression(expr, cfg, errors),
+        }
+    }
+
+    /// The length of an expression which is a fixed length byte array
+    fn fixed_bytes(&self, expr: &Expression, cfg: &ControlFlowGraph) -> Option<u8> {
+        match self.expression_type(expr, cfg) {
+            Some(Type::Bytes(n)) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// A fixed length byte array of another length, which is padded or
+    /// truncated on the right
+    fn render_resize(
+        &self,
+        expr: &Expression,
+        ty: &Type,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Expr {
+        let path = Path::new("bytes_resize").with_args(vec![
+            rust::Type::path("_"),
+            self.render_type(ty, &expr.loc(), errors),
+        ]);
+        Expr::call(path, vec![self.render_expression(expr, cfg, errors)])
+    }
+
+    /// An integer as a `usize`, for indexes
+    fn render_usize(
+        &self,
+        expr: &Expression,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Expr {
+        if let Expression::NumberLiteral(_, _, n) = expr {
+            return Expr::int(n);
+        }
+        let value = self.render_expression(expr, cfg, errors);
+        match self.expression_type(expr, cfg).as_ref().and_then(int_type) {
+            Some(("I256", _)) => {
+                Expr::call(Path::new("U256::from"), vec![value]).method("as_usize", vec![])
+            }
+            Some((_, width)) if width > 64 => value.method("as_usize", vec![]),
+            _ => Expr::Cast(Box::new(value), rust::Type::path("usize")),
+        }
+    }
+
+    /// A primitive integer as a `u32`, for exponents
+    fn render_u32(
+        &self,
+        expr: &Expression,
+        ty: &Type,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Expr {
+        let inner = uncast(expr);
+        if let Expression::NumberLiteral(_, _, n) = inner {
+            return Expr::int(n);
+        }
+        // a primitive integer is cast once, rather than to the width of the value first
+        let inner_ty = self.expression_type(inner, cfg);
+        let (expr, ty) = match inner_ty.as_ref().and_then(int_type) {
+            Some((_, width)) if width <= 64 => (inner, inner_ty.as_ref().unwrap()),
+            _ => (expr, ty),
+        };
+        let value = self.render_converted(expr, ty, cfg, errors);
+        match int_type(ty) {
+            Some(("u32", _)) => value,
+            _ => Expr::Cast(Box::new(value), rust::Type::path("u32")),
+        }
+    }
+
+    /// The integer type of the operands of an operator
+    fn int_operand_type(
+        &self,
+        l: &Expression,
+        r: &Expression,
+        cfg: &ControlFlowGraph,
+    ) -> Option<Type> {
+        self.expression_type(l, cfg)
+            .or_else(|| self.expression_type(r, cfg))
+            .filter(|ty| int_type(ty).is_some())
+    }
+
+    /// An operand on which a method is called; a literal must have a type
+    fn render_receiver(
+        &self,
+        expr: &Expression,
+        ty: &Type,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Expr {
+        match literal_value(expr) {
+            Some(n) => self.render_number(&n, ty),
+            None => self.render_converted(expr, ty, cfg, errors),
+        }
+    }
+
+    /// Integer arithmetic, which wraps around like it does in Solidity. The
+    /// wide integers have no wrapping methods, so the result of their
+    /// overflowing methods is used without the overflow flag.
+    fn render_wrapping(
+        &self,
+        op: &str,
+        l: &Expression,
+        r: &Expression,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Option<Expr> {
+        let ty = self.int_operand_type(l, r, cfg)?;
+        let left = self.render_receiver(l, &ty, cfg, errors);
+        let right = self.render_converted(r, &ty, cfg, errors);
+        let result = match int_type(&ty) {
+            Some((name, _)) if name.starts_with('U') => Expr::Field(
+                Box::new(left.method(&format!("overflowing_{}", op), vec![right])),
+                "0".to_string(),
+            ),
+            _ => left.method(&format!("wrapping_{}", op), vec![right]),
+        };
+        Some(self.render_wrapped(result, &ty))
+    }
+
+    /// A shift of an integer. Bits shifted past the width of the integer are
+    /// lost, so shifting by the width or more leaves zero, or only the sign for
+    /// an arithmetic shift right.
+    fn render_shift(
+        &self,
+        left: bool,
+        l: &Expression,
+        r: &Expression,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Option<Expr> {
+        let ty = self
+            .expression_type(l, cfg)
+            .filter(|ty| int_type(ty).is_some())?;
+        let (name, width) = int_type(&ty).unwrap();
+        let value = self.render_receiver(l, &ty, cfg, errors);
+        let result = if width > 64 {
+            // the wide integers shift by a `usize`
+            let (bits, _) = self.render_shift_amount(r, width, "usize", cfg, errors);
+            match (name, left) {
+                ("I256", true) => value.method("shl", vec![bits]),
+                ("I256", false) => value.method("shr", vec![bits]),
+                (_, true) => Expr::binary(BinOp::Shl, value, bits),
+                (_, false) => Expr::binary(BinOp::Shr, value, bits),
+            }
+        } else if !left && ty.signed() {
+            let max = width - 1;
+            let bits = match self.render_shift_amount(r, max, "u32", cfg, errors) {
+                (bits, true) => bits,
+                (bits, false) => bits.method("min", vec![Expr::int(&BigInt::from(max))]),
+            };
+            Expr::binary(BinOp::Shr, value, bits)
+        } else {
+            let method = if left { "checked_shl" } else { "checked_shr" };
+            value
+                .method(
+                    method,
+                    vec![self.render_shift_amount(r, width, "u32", cfg, errors).0],
+                )
+                .method("unwrap_or", vec![Expr::int(&BigInt::zero())])
+        };
+        Some(if left {
+            self.render_wrapped(result, &ty)
+        } else {
+            result
+        })
+    }
+
+    /// The amount of a shift as a `u32` or a `usize`. The amount is cast to the
+    /// type of the shifted value, so the amount before that cast is used. An
+    /// amount which may not fit is clamped to the limit, above which every
+    /// amount shifts alike; the flag is set if the amount is no more than the limit.
+    fn render_shift_amount(
+        &self,
+        r: &Expression,
+        limit: u16,
+        to: &str,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> (Expr, bool) {
+        let amount = uncast(r);
+        if let Expression::NumberLiteral(_, _, n) = amount {
+            return (Expr::int(std::cmp::min(n, &BigInt::from(limit))), true);
+        }
+        let ty = match self
+            .expression_type(amount, cfg)
+            .filter(|ty| int_type(ty).is_some())
+        {
+            Some(ty) => ty,
+            None => {
+                return (
+                    Expr::Cast(
+                        Box::new(self.render_expression(r, cfg, errors)),
+                        rust::Type::path(to),
+                    ),
+                    false,
+                )
+            }
+        };
+        let (name, _) = int_type(&ty).unwrap();
+        let value = self.render_expression(amount, cfg, errors);
+        if !ty.signed() && ty.bits(self.ns) <= 32 {
+            let value = match name {
+                _ if name == to => value,
+                _ => Expr::Cast(Box::new(value), rust::Type::path(to)),
+            };
+            return (value, false);
+        }
+        let value = value.method("min", vec![self.render_number(&BigInt::from(limit), &ty)]);
+        let value = match to {
+            "usize" if name.starts_with(char::is_lowercase) => {
+                Expr::Cast(Box::new(value), rust::Type::path(to))
+            }
+            "usize" => Expr::Cast(
+                Box::new(self.render_int_conversion(value, name, "u64")),
+                rust::Type::path(to),
+            ),
+            _ => self.render_int_conversion(value, name, to),
+        };
+        (value, true)
+    }
+
+    /// Convert an integer to another integer type. The value is truncated, or
+    /// extended with its sign if its type is signed, like the casts of Solidity.
+    fn render_int_cast(&self, value: Expr, from: &Type, to: &Type) -> Expr {
+        let (from_name, _) = int_type(from).unwrap();
+        let (to_name, _) = int_type(to).unwrap();
+        let result = self.render_int_conversion(value, from_name, to_name);
+        // the value is within the range of the new type, unless that is
+        // narrower, or the value is reinterpreted with another sign
+        let (from_bits, to_bits) = (from.bits(self.ns), to.bits(self.ns));
+        if (!from.signed() && from_bits < to_bits)
+            || (from.signed() == to.signed() && from_bits <= to_bits)
+        {
+            result
+        } else {
+            self.render_wrapped(result, to)
+        }
+    }
+
+    /// Convert a value between the Rust types which hold integers
+    fn render_int_conversion(&self, value: Expr, from: &str, to: &str) -> Expr {
+        let primitive = |name: &str| name.starts_with(char::is_lowercase);
+        let signed = |name: &str| name.starts_with(['i', 'I']);
+        let from_path = |name: &str, value: Expr| {
+            Expr::call(Path::new(&format!("{}::from", name)), vec![value])
+        };
+        match (from, to) {
+            _ if from == to => value,
+            _ if primitive(from) && primitive(to) => {
+                Expr::Cast(Box::new(value), rust::Type::path(to))
+            }
+            (_, "I256") => {
+                self.use_helper("I256");
+                let value = match from {
+                    "i64" => value,
+                    "i32" => Expr::Cast(Box::new(value), rust::Type::path("i64")),
+                    _ => self.render_int_conversion(value, from, "U256"),
+                };
+                from_path("I256", value)
+            }
+            // signed integers are extended by way of `I256`
+            _ if primitive(from) && signed(from) => self.render_int_conversion(
+                self.render_int_conversion(value, from, "I256"),
+                "I256",
+                to,
+            ),
+            _ if primitive(from) => from_path(to, value),
+            ("I256", _) => self.render_int_conversion(from_path("U256", value), "U256", to),
+            (_, "u64") => value.method("low_u64", vec![]),
+            _ if primitive(to) => Expr::Cast(
+                Box::new(value.method("low_u64", vec![])),
+                rust::Type::path(to),
+            ),
+            ("U128", _) => from_path(to, value.method("as_u128", vec![])),
+            (_, "U128") => from_path("U128", value.method("low_u128", vec![])),
+            ("U256", _) => {
+                self.use_helper("u256_to_u512");
+                Expr::call(Path::new("u256_to_u512"), vec![value])
+            }
+            _ => {
+                self.use_helper("u512_to_u256");
+                Expr::call(Path::new("u512_to_u256"), vec![value])
+            }
         }
     }
 
-    fn render_function_args(&self, function: &FunctionDecl) -> String {
-        let mut result = Vec::<String>::new();
-        for param in &function.params {
-            result.push(format!(
-                "{}: {}", 
-                param.name, 
-                self.render_type(&param.ty)));
+    /// Integers which are narrower than the Rust type which holds them wrap
+    /// around at their own width
+    fn render_wrapped(&self, value: Expr, ty: &Type) -> Expr {
+        let (name, width) = match int_type(ty) {
+            Some(int_type) => int_type,
+            None => return value,
+        };
+        let bits = ty.bits(self.ns);
+        if bits == width {
+            value
+        } else if ty.signed() {
+            // the sign bit is shifted to the top, and back again
+            let shift = Expr::int(&BigInt::from(width - bits));
+            match name {
+                "I256" => value
+                    .method("shl", vec![shift.clone()])
+                    .method("shr", vec![shift]),
+                _ => Expr::binary(
+                    BinOp::Shr,
+                    Expr::binary(BinOp::Shl, value, shift.clone()),
+                    shift,
+                ),
+            }
+        } else {
+            let mask = (BigInt::one() << bits as usize) - BigInt::one();
+            Expr::binary(
+                BinOp::BitAnd,
+                value,
+                self.render_number(&mask, &Type::Uint(width)),
+            )
         }
-        result.join(", ")
-    }
-
-    fn render_function_body(&self, function: &FunctionDecl) -> String {
-        self.render_function_cfg(&function.cfg.as_ref().unwrap())
     }
 
-    fn render_function_cfg(&self, cfg: &ControlFlowGraph) -> String {
-        // println!("// Got blocks count: {}", cfg.bb.len());
-        // for var in &cfg.vars {
-        //     println!("// Var: {}, {}", var.id.name, self.render_type(&var.ty));
-        // }
-        // println!("// Vars Done");
-        self.render_block(0, cfg, Vec::new())
-    }
-
-    fn render_block(
-        &self, 
-        block_id: usize, 
-        cfg: &ControlFlowGraph, 
-        mut visited_bbs: Vec<usize>
-    ) -> String {
-        visited_bbs.push(block_id);
-        let block = cfg.bb.get(block_id).unwrap();
-        let mut result = Vec::<String>::new();
-        for instruction in &block.instr {
-            match self.render_instruction(&instruction, &cfg, visited_bbs.clone()) {
-                Some(i) => result.push(i),
-                None => {}
+    /// A number literal of the given type, so that it serializes like any
+    /// other value of that type
+    fn render_number(&self, n: &BigInt, ty: &Type) -> Expr {
+        match int_type(ty) {
+            // a signed 256-bit literal is converted from a `U256` or from an `i64`
+            Some(("I256", _)) => {
+                self.use_helper("I256");
+                match n.to_i64() {
+                    Some(_) => Expr::call(Path::new("I256::from"), vec![Expr::typed_int(n, "i64")]),
+                    None => {
+                        let value = Expr::call(
+                            Path::new("I256::from"),
+                            vec![self.render_number(&n.abs(), &Type::Uint(256))],
+                        );
+                        if n.is_negative() {
+                            value.method("wrapping_neg", vec![])
+                        } else {
+                            value
+                        }
+                    }
+                }
             }
+            // a wider literal is built from its little endian 64-bit words
+            Some((name, bits)) if bits > 64 => match n.to_u64() {
+                Some(_) => Expr::call(
+                    Path::new(&format!("{}::from", name)),
+                    vec![Expr::typed_int(n, "u64")],
+                ),
+                None => {
+                    let modulus = BigInt::one() << bits as usize;
+                    let (_, mut bytes) = (((n % &modulus) + &modulus) % &modulus).to_bytes_le();
+                    bytes.resize(bits as usize / 8, 0);
+                    let words = bytes
+                        .chunks(8)
+                        .map(|word| {
+                            Expr::int(&BigInt::from(u64::from_le_bytes(word.try_into().unwrap())))
+                        })
+                        .collect();
+                    Expr::call(Path::new(name), vec![Expr::Array(words)])
+                }
+            },
+            Some((name, _)) => Expr::typed_int(n, name),
+            None => Expr::int(n),
         }
-        result.join("")
     }
 
-    fn render_instruction(
-        &self, 
-        instruction: &Instr, 
-        cfg: &ControlFlowGraph, 
-        visited_bbs: Vec<usize>
-    ) -> Option<String> {
-        match instruction {
-            Instr::Eval { expr } => { 
-                // println!("expression: {}", self.render_expression(&expr, vars));
-                // Some(self.render_expression(&expr, cfg))
-                None
-            },
-            Instr::Return { value } => {
-                if value.is_empty() { None } else {
-                    let expression = self.render_expression(
-                        &value.first().unwrap(), cfg);
-                    Some(format!("ret({});", expression))
+    /// Find the key and struct fields of a storage slot. Each storage variable,
+    /// mapping entry and dynamic array element is stored under its own key;
+    /// struct members are stored as part of the struct.
+    fn storage_place(
+        &self,
+        slot: &Expression,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Option<(StoragePlace, Type)> {
+        if let Some((array, offset)) = element_slot(slot) {
+            let (place, ty) = self.storage_place(array, cfg, errors)?;
+            if !place.members.is_empty() || !is_dynamic_array(&ty) {
+                return None;
+            }
+            let elem_ty = ty.deref().storage_deref().deref().clone();
+            let index = self.render_expression(
+                element_index(offset, &elem_ty.storage_slots(self.ns))?,
+                cfg,
+                errors,
+            );
+            let key = match place.key {
+                Expr::Lit(Lit::Str(name)) => Expr::macro_call(
+                    "format",
+                    Delimiter::Paren,
+                    vec![Expr::str(&format!("{}[{{}}]", name)), index],
+                ),
+                key => Expr::macro_call(
+                    "format",
+                    Delimiter::Paren,
+                    vec![Expr::str("{}[{}]"), key, index],
+                ),
+            };
+            return Some(keyed_place(key.reference(), elem_ty));
+        }
+        match slot {
+            Expression::NumberLiteral(_, _, n) => {
+                let variable =
+                    self.contract
+                        .variables
+                        .iter()
+                        .find(|variable| match &variable.var {
+                            ContractVariableType::Storage(slot) => slot == n,
+                            _ => false,
+                        })?;
+                Some(keyed_place(Expr::str(&variable.name), variable.ty.clone()))
+            }
+            Expression::Variable(_, pos) => {
+                self.storage_place(self.slot_definition(*pos, cfg)?, cfg, errors)
+            }
+            Expression::Add(_, base, offset) => {
+                let (mut place, ty) = self.storage_place(base, cfg, errors)?;
+                match (ty.deref(), &**offset) {
+                    (Type::Struct(n), Expression::NumberLiteral(_, _, offset)) => {
+                        let mut field_slot = BigInt::zero();
+                        for field in &self.ns.structs[*n].fields {
+                            if field_slot == *offset {
+                                place.members.push(Member::Field(field.name.clone()));
+                                return Some((place, field.ty.clone()));
+                            }
+                            field_slot += field.ty.storage_slots(self.ns);
+                        }
+                        None
+                    }
+                    // elements of fixed length arrays are stored as part of the array
+                    (Type::Array(..), _) if !is_dynamic_array(&ty) => {
+                        let elem_ty = ty.deref().storage_deref().deref().clone();
+                        let index = element_index(offset, &elem_ty.storage_slots(self.ns))?;
+                        place
+                            .members
+                            .push(Member::Index(self.render_expression(index, cfg, errors)));
+                        Some((place, elem_ty))
+                    }
+                    _ => None,
                 }
-            },
-            Instr::SetStorage { ty: _, local, storage } => {
-                Some(format!(
-                    "set_key({}, {});",
-                    self.render_var_name_or_default(&storage, cfg),
-                    self.render_local_var(*local, cfg)
-                ))
-            },
-            Instr::Set { res, expr } => {
-                let left = self.render_local_var(*res, cfg);
-                let right = self.render_expression(&expr, cfg);
-                if left == right { 
-                    return None 
-                };
-                Some(format!(
-                    "let {}: {} = {};",
-                    left,
-                    self.render_type(&cfg.vars[*res].ty),
-                    right
-                ))
-            },
-            Instr::Call { res: _, func, args } => {
-                let fn_name = self.contract.functions.get(*func).unwrap().name.clone();
-                let mut result = Vec::<String>::new();
-                for arg in args {
-                    result.push(self.render_expression(arg, cfg));
-                }
-                Some(format!(
-                    "{}({});",
-                    fn_name,
-                    result.join(", ")
-                ))
-            },
-            Instr::BranchCond { cond, true_, false_} => {
-                let true_bb = cfg.bb.get(*true_).unwrap();
-                let false_bb = cfg.bb.get(*false_).unwrap();
-                let cond = self.render_expression(&cond, cfg);
-                println!("// false {:?}", false_bb);
-                println!("// true {:?}", true_bb);
-
-                match false_bb.name.as_str() {
-                    "endwhile" => {
-                        Some(format!(
-                            "while {} {{ {} }} {}",
-                            cond,
-                            self.render_block(*true_, cfg, visited_bbs.clone()),
-                            self.render_block(*false_, cfg, visited_bbs.clone())
-                        ))
-                    },
-                    "endfor" => {
-                        Some(format!(
-                            "while {} {{ {} }} {}",
-                            cond,
-                            self.render_block(*true_, cfg, visited_bbs.clone()),
-                            self.render_block(*false_, cfg, visited_bbs.clone())
-                        ))
-                    },
-                    "endif" | "else" => {
-                        let else_stm = match self.render_block(*false_, cfg, visited_bbs.clone()) {
-                            code if code.len() == 0 => code,
-                            code => format!("else {{ {} }}", code)
+            }
+            Expression::Keccak256(_, exprs) if exprs.len() == 2 => {
+                let (place, ty) = self.storage_place(&exprs[0].0, cfg, errors)?;
+                match ty.deref() {
+                    Type::Mapping(key_ty, value) if place.members.is_empty() => {
+                        let key = match (&exprs[1].0, &**key_ty) {
+                            (expr, ty @ Type::Enum(_)) | (expr, ty @ Type::Bytes(_)) => {
+                                self.render_converted(expr, ty, cfg, errors)
+                            }
+                            (Expression::NumberLiteral(_, _, n), ty) => self.render_number(n, ty),
+                            (expr, _) => self.render_expression(expr, cfg, errors),
                         };
-                        Some(format!(
-                            "if {} {{ {} }} {}",
-                            cond,
-                            self.render_block(*true_, cfg, visited_bbs.clone()),
-                            else_stm
-                        ))
+                        let key =
+                            Expr::call(Path::new("new_key"), vec![place.key, key.reference()]);
+                        Some(keyed_place(key.reference(), *value.clone()))
                     }
-                    _ => None
+                    _ => None,
                 }
+            }
+            _ => None,
+        }
+    }
 
+    /// The expression which sets a temporary to the storage slot of an array
+    /// element, for the temporaries of `push()` and `pop()`, or which sets a
+    /// storage reference which is only set once
+    fn slot_definition<'b>(&self, pos: usize, cfg: &'b ControlFlowGraph) -> Option<&'b Expression> {
+        let mut definitions = cfg
+            .bb
+            .iter()
+            .flat_map(|bb| bb.instr.iter())
+            .filter_map(|instr| match instr {
+                Instr::Set { res, expr } if *res == pos => Some(expr),
+                _ => None,
+            });
+        match (definitions.next(), definitions.next()) {
+            (Some(expr), None) if element_slot(expr).is_some() => Some(expr),
+            // the temporaries of values in storage are storage types too
+            (Some(expr), None) => match &cfg.vars[pos].ty {
+                Type::StorageRef(ty) if ty.is_reference_type() => Some(expr),
+                _ => None,
             },
-            Instr::Branch { bb} => {
-                println!("// branch {:?} {:?}", bb, cfg.bb.get(*bb));
-                if visited_bbs.contains(bb) {
-                    None
-                } else {
-                    Some(self.render_block(*bb, cfg, visited_bbs))
-                }
-                // None
-            },
-            Instr::ClearStorage { ty, storage} => {
-                panic!("Unhandled Instr::ClearStorage");
-            },
-            Instr::SetStorageBytes { local, storage, offset} => {
-                panic!("Unhandled Instr::SetStorageBytes");
-            },
-            Instr::Constant { res, constant} => {
-                panic!("Unhandled Instr::Constant");
-            },
-            Instr::Store { dest, pos} => {
-                // panic!("Unhandled Instr::Store");
-                Some(format!("{} = {}",
-                    self.render_var_name_or_default(dest, cfg),
-                    self.render_local_var(*pos, cfg)
-                ))
-            },
-            Instr::AssertFailure { expr} =>
-                self.render_instruction(&Instr::Unreachable, &cfg, visited_bbs),
-            Instr::Print{ expr} => {
-                panic!("Unhandled Instr::Print");
-            },
-            Instr::Constructor {
-                success,
-                res,
-                contract_no,
-                constructor_no,
-                args,
-                value,
-                gas,
-                salt
-            } => {
-                panic!("Unhandled Instr::Constructor");
-            },
-            Instr::ExternalCall {
-                success, 
-                address,
-                contract_no,
-                function_no,
-                args,
-                value,
-                gas
-            } => {
-                panic!("Unhandled Instr::ExternalCall");
-            },
-            Instr::AbiDecode {
-                res,
-                selector,
-                exception,
-                tys,
-                data
-            } => {
-                panic!("Unhandled Instr::AbiDecode");
-            },
-            Instr::Unreachable => {
-                Some(format!("assert(false);"))
-            },
-            Instr::SelfDestruct { recipient} => {
-                panic!("Unhandled Instr::SelfDestruct");
-            },
-            Instr::Hash { res, hash, expr} => {
-                panic!("Unhandled Instr::Hash");
-            }
+            _ => None,
         }
     }
 
-    fn render_expression(&self, expression: &Expression, cfg: &ControlFlowGraph) -> String {
-        match expression {
-            // Literals
-            Expression::FunctionArg(_, pos) => self.render_local_var(*pos, cfg),
-            Expression::BoolLiteral(_, false) => "false".to_string(),
-            Expression::BoolLiteral(_, true) => "true".to_string(),
-            Expression::BytesLiteral(_, s) => format!("vec!{:?}", s),
-            Expression::NumberLiteral(_, _bits, n) => format!("{}", n.to_str_radix(10)),
-            // Expression::StructLiteral(_, _, expr) =>
-            // Expression::ConstArrayLiteral(_, dims, exprs) =>
-            Expression::ArrayLiteral(_, _, dims, exprs) => 
-                self.render_static_array(dims, exprs, cfg),
-
-                // Arithmetic
-            Expression::Add(_, l, r) => format!(
-                "({} + {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::Subtract(_, l, r) => format!(
-                "({} - {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::BitwiseOr(_, l, r) => format!(
-                "({}.iter().zip({}.iter()).map(|e| e.0 | e.1).collect::<Vec<u8>>())",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::BitwiseAnd(_, l, r) => format!(
-                "({}.iter().zip({}.iter()).map(|e| e.0 & e.1).collect::<Vec<u8>>())",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::BitwiseXor(_, l, r) => format!(
-                "({}.iter().zip({}.iter()).map(|e| e.0 ^ e.1).collect::<Vec<u8>>())",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            // Expression::ShiftLeft(_, l, r) => format!(
-            // Expression::ShiftRight(_, l, r, _) => format!(
-            Expression::Multiply(_, l, r) => format!(
-                "({} * {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::UDivide(_, l, r) | Expression::SDivide(_, l, r) => format!(
-                "({} / {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::UModulo(_, l, r) | Expression::SModulo(_, l, r) => format!(
-                "({} % {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::Power(_, l, r) => format!(
-                "{}.pow({})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
+    /// Write a value to a storage place. Struct members are set by updating
+    /// the whole struct.
+    fn render_storage_store(
+        &self,
+        place: StoragePlace,
+        value: Expr,
+        loc: &pt::Loc,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Expr {
+        if place.members.is_empty() {
+            return Expr::call(Path::new("set_key"), vec![place.key, value]);
+        }
+        let name = self.unused_name("value", cfg);
+        let mut member = Expr::path(&name);
+        for m in &place.members {
+            member = m.clone().access(member);
+        }
+        Expr::Block(Block::new(vec![
+            rust::Stmt::Let(
+                Pat::Ident(true, name.clone()),
+                None,
+                Some(self.render_storage_load(&place, loc, errors)),
             ),
+            rust::Stmt::Expr(Expr::assign(member, value)),
+            rust::Stmt::Expr(Expr::call(
+                Path::new("set_key"),
+                vec![place.key, Expr::path(&name)],
+            )),
+        ]))
+    }
 
-            // Data
-            Expression::Variable(_, res) => self.render_local_var(*res, cfg),
-            // Expression::Load(_, expr) => {
-            Expression::StorageLoad(_, ty, expr) => {
-                // println!("// storage load {:?}", expr);
-                match self.render_var_name_or_default(&expr, cfg).as_str() {
-                    GET_CALLER => GET_CALLER.to_string(),
-                    result => format!(
-                        "get_key::<{}>({})",
-                        self.render_type(ty),
-                        result
-                    )
-                }
-            },
-            Expression::ZeroExt(_, ty, expr) =>
-                self.render_expression(&expr, cfg),
-            // Expression::SignExt(_, ty, e) => format!(
-            // Expression::Trunc(_, ty, e) => format!(
-            
-            // Comparators 
-            Expression::SMore(_, l, r) => format!(
-                "({} > {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::SLess(_, l, r) => format!(
-                "({} < {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::SMoreEqual(_, l, r) => format!(
-                "({} >= {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::SLessEqual(_, l, r) => format!(
-                "({} <= {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::UMore(_, l, r) => format!(
-                "({} > {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::ULess(_, l, r) => format!(
-                "({} < {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::UMoreEqual(_, l, r) => format!(
-                "({} >= {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::ULessEqual(_, l, r) => format!(
-                "({} <= {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::Equal(_, l, r) => format!(
-                "({} == {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::NotEqual(_, l, r) => format!(
-                "({} != {})",
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            
-            // Arrays and Structs
-            Expression::ArraySubscript(_, a, i) => format!(
-                "{}[{} as usize]",
-                self.render_expression(a, cfg),
-                self.render_expression(i, cfg)
-            ),
-            // Expression::DynamicArraySubscript(_, a, _, i) => format!(
-            // Expression::StorageBytesSubscript(_, a, i) => format!(
-            // Expression::StorageBytesPush(_, a, i) => format!(
-            // Expression::StorageBytesPop(_, a) => format!(
-            // Expression::StorageBytesLength(_, a) => format!(
-            // Expression::StructMember(_, a, f) => format!(
+    /// Read the value stored under the key of a storage place
+    fn render_storage_load(
+        &self,
+        place: &StoragePlace,
+        loc: &pt::Loc,
+        errors: &mut Vec<Output>,
+    ) -> Expr {
+        self.render_get_key(place.key.clone(), &place.ty, loc, errors)
+    }
 
-            // Bool operators
-            // Expression::Or(_, l, r) => format!(
-            //     "({} || {})", 
-            //     self.render_expression(&l, cfg),
-            //     self.render_expression(&r, cfg)
-            // ),
-            // Expression::And(_, l, r) => format!(
-            //     "({} && {})", 
-            //     self.render_expression(&l, cfg),
-            //     self.render_expression(&r, cfg)
-            // ),
-            Expression::Ternary(_, c, l, r) => format!(
-                "if {} {{ {} }} else {{ {} }}",
-                self.render_expression(&c, cfg),
-                self.render_expression(&l, cfg),
-                self.render_expression(&r, cfg)
-            ),
-            Expression::Not(_, expr) => format!(
-                "!({})", 
-                self.render_expression(&expr, cfg)
-            ),
-            // Expression::Complement(_, e) => format!("~{}", self.expr_to_string(contract, ns, e)),
-            Expression::UnaryMinus(_, expr) => format!(
-                "-({})", 
-                self.render_expression(&expr, cfg)
-            ),
+    /// Read the value stored under a key, or the zero value of its type if
+    /// the key is not set
+    fn render_get_key(
+        &self,
+        key: Expr,
+        ty: &Type,
+        loc: &pt::Loc,
+        errors: &mut Vec<Output>,
+    ) -> Expr {
+        if self.has_default(ty) {
+            Expr::call(
+                Path::new("get_key").with_args(vec![self.render_type(ty, loc, errors)]),
+                vec![key],
+            )
+        } else {
+            Expr::call(
+                Path::new("get_key_or"),
+                vec![key, self.render_zero(ty, loc, errors)],
+            )
+        }
+    }
 
-            // Others
-            // Expression::Poison => "☠".to_string(),
-            // Expression::Unreachable => "❌".to_string(),
-            // Expression::AllocDynamicArray(_, ty, size, None) => format!(
-            Expression::AllocDynamicArray(_, ty, size, Some(init)) => 
-                format!(""),
-            // Expression::DynamicArrayLength(_, a) => {
-            // Expression::StringCompare(_, l, r) => format!(
-            // Expression::StringConcat(_, l, r) => format!(
-            // Expression::LocalFunctionCall(_, f, args) => format!(
-            // Expression::Constructor {
-            //     contract_no,
-            //     constructor_no,
-            //     args,
-            //     ..
-            // } =>
-            // Expression::CodeLiteral(_, contract_no, runtime) => format!(
-            // Expression::ExternalFunctionCall {
-            //     function_no,
-            //     contract_no,
-            //     address,
-            //     args,
-            //     ..
-            // } => format!(
-            Expression::Keccak256(_, exprs) => {
-                // println!("// expres: {:?}", exprs);
-                match exprs.len() {
-                    // 1 => {
-                    //     let first = &exprs.get(0).unwrap().0;
-                    //     match first {
-                    //         Expression::NumberLiteral(_, bits, n) => {
-                    //             let position: usize = n.to_usize().unwrap();
-                    //             self.render_local_var(position, cfg)
-                    //         }
-                    //         _ => panic!("Unexpected keccak argument type")
-                    //     }
-                    // },
-                    2 => {
-                        let first = &exprs.get(0).unwrap().0;
-                        let second = &exprs.get(1).unwrap().0;
-                        format!(
-                            "&new_key({}, {})",
-                            self.render_var_name_or_default(first, cfg),
-                            self.render_expression(second, cfg)
-                        )
-                    },
-                    _ => panic!("Unsupportet number of keccak arguments")
+    /// The type of an expression which refers to memory, or whose type follows
+    /// from its operands
+    fn expression_type(&self, expr: &Expression, cfg: &ControlFlowGraph) -> Option<Type> {
+        match expr {
+            Expression::BytesLiteral(_, bytes) => Some(Type::Bytes(bytes.len() as u8)),
+            Expression::BitwiseAnd(_, l, r)
+            | Expression::BitwiseOr(_, l, r)
+            | Expression::BitwiseXor(_, l, r) => self
+                .expression_type(l, cfg)
+                .or_else(|| self.expression_type(r, cfg)),
+            Expression::Add(_, l, r)
+            | Expression::Subtract(_, l, r)
+            | Expression::Multiply(_, l, r)
+            | Expression::UDivide(_, l, r)
+            | Expression::SDivide(_, l, r)
+            | Expression::UModulo(_, l, r)
+            | Expression::SModulo(_, l, r)
+            | Expression::Power(_, l, r) => self
+                .expression_type(l, cfg)
+                .or_else(|| self.expression_type(r, cfg)),
+            Expression::ShiftLeft(_, expr, _)
+            | Expression::ShiftRight(_, expr, _, _)
+            | Expression::Complement(_, expr)
+            | Expression::UnaryMinus(_, expr) => self.expression_type(expr, cfg),
+            Expression::Sender(_) => Some(Type::Address(true)),
+            Expression::GetAddress(_) => self
+                .ns
+                .contracts
+                .iter()
+                .position(|contract| std::ptr::eq(contract, self.contract))
+                .map(Type::Contract),
+            Expression::Variable(_, pos) | Expression::FunctionArg(_, pos) => {
+                Some(cfg.vars[*pos].ty.clone())
+            }
+            Expression::StructLiteral(_, ty, _) | Expression::StorageLoad(_, ty, _) => {
+                Some(ty.clone())
+            }
+            Expression::Load(_, expr) => self.expression_type(expr, cfg),
+            Expression::ZeroExt(_, ty, _)
+            | Expression::SignExt(_, ty, _)
+            | Expression::Trunc(_, ty, _) => Some(ty.clone()),
+            Expression::Value(_) | Expression::Balance(..) => {
+                Some(Type::Uint(self.ns.value_length as u16 * 8))
+            }
+            // rendered as a `u32`, like in Solang's IR
+            Expression::DynamicArrayLength(..) | Expression::StorageBytesLength(..) => {
+                Some(Type::Uint(32))
+            }
+            Expression::StructMember(_, expr, field) => {
+                match self.expression_type(expr, cfg)?.deref() {
+                    Type::Struct(n) => Some(self.ns.structs[*n].fields[*field].ty.clone()),
+                    _ => None,
+                }
+            }
+            Expression::DynamicArraySubscript(_, _, ty, _) => Some(ty.deref().clone()),
+            Expression::AllocDynamicArray(_, ty, _, _) => Some(ty.clone()),
+            Expression::ArraySubscript(_, array, _) => {
+                match self.expression_type(array, cfg)?.deref() {
+                    ty @ Type::Array(..) => Some(ty.array_deref().deref().clone()),
+                    _ => None,
                 }
-            },
-            _ => {
-                println!("// Unknown expression {:?}", expression);
-                format!("unknown_expresson")
             }
+            _ => None,
         }
     }
 
-    // fn render_if(&self, )
+    /// A name for a temporary which does not clash with any variable of the function
+    fn unused_name(&self, name: &str, cfg: &ControlFlowGraph) -> String {
+        let mut name = name.to_string();
+        while cfg.vars.iter().any(|v| v.id.name == name) {
+            name.push('_');
+        }
+        name
+    }
 
-    fn render_static_array(&self,  dims: &Vec<u32>, exprs: &Vec<Expression>, cfg: &ControlFlowGraph) -> String {
+    fn render_static_array(
+        &self,
+        dims: &Vec<u32>,
+        exprs: &Vec<Expression>,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Expr {
         let mut result = Vec::new();
         for expr in exprs {
-            result.push(self.render_expression(expr, cfg));
+            result.push(self.render_expression(expr, cfg, errors));
         }
         for dim in dims {
             let mut data = Vec::new();
             for elem in result.chunks(*dim as usize) {
-                data.push(format!("[{}]", elem.join(", ")));
+                data.push(Expr::Array(elem.to_vec()));
             }
-            println!("// {:?}", data);
             result = data;
         }
-        result.join(",")
+        // the outermost dimension leaves a single array
+        result.pop().unwrap_or_else(|| Expr::Array(Vec::new()))
     }
 
-    fn render_var_name_or_default(&self, expression: &Expression, cfg: &ControlFlowGraph) -> String {
+    fn render_var_name_or_default(
+        &self,
+        expression: &Expression,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Expr {
         match expression {
             Expression::NumberLiteral(_, _bits, n) => {
                 let position: usize = n.to_usize().unwrap();
                 match self.variable_name(position) {
-                    Some(name) => name,
-                    None => format!("\"{}\"", position)
+                    Some(name) => Expr::str(&name),
+                    None => Expr::str(&format!("{}", position)),
                 }
-            },
-            Expression::Add(_, _, _,) | Expression::Multiply(_, _, _) => {
-                format!("&format!(\"{{}}\", {})", self.render_expression(expression, cfg))
-            },
-            _ => {
-                self.render_expression(expression, cfg)
+            }
+            Expression::Add(_, _, _) | Expression::Multiply(_, _, _) => Expr::macro_call(
+                "format",
+                Delimiter::Paren,
+                vec![
+                    Expr::str("{}"),
+                    self.render_expression(expression, cfg, errors),
+                ],
+            )
+            .reference(),
+            _ => self.render_expression(expression, cfg, errors),
+        }
+    }
+
+    fn render_local_var(&self, id: usize, cfg: &ControlFlowGraph) -> Expr {
+        Expr::path(&self.render_local_name(id, cfg))
+    }
+
+    /// Variables of different scopes, and the parameters of inlined modifiers,
+    /// may have the name of an earlier variable, so they are suffixed with
+    /// their number. So are the names which cannot be a Rust identifier, like
+    /// `self`.
+    fn render_local_name(&self, id: usize, cfg: &ControlFlowGraph) -> String {
+        let source_name = |var: &Variable| var.id.name.replace(".", "");
+        let mut name = source_name(&cfg.vars[id]);
+        if rust::is_path_keyword(&name) || cfg.vars[..id].iter().any(|var| source_name(var) == name)
+        {
+            name = format!("{}_{}", name, id);
+            while cfg.vars.iter().any(|var| source_name(var) == name) {
+                name.push('_');
             }
         }
+        name
     }
 
-    fn render_local_var(&self, id: usize, cfg: &ControlFlowGraph) -> String {
-        cfg.vars[id].id.name.replace(".", "").clone()
+    fn render_type(&self, ty: &Type, loc: &pt::Loc, errors: &mut Vec<Output>) -> rust::Type {
+        let name = match ty {
+            Type::Bool => "bool",
+            Type::String => "String",
+            Type::Uint(_) | Type::Int(_) => {
+                let (name, _) = int_type(ty).unwrap();
+                if name == "I256" {
+                    self.use_helper("I256");
+                }
+                name
+            }
+            Type::Address(_) => "AccountHash",
+            Type::Contract(_) => "ContractHash",
+            Type::Bytes(n) => {
+                return rust::Type::Array(Box::new(rust::Type::path("u8")), *n as usize)
+            }
+            Type::DynamicBytes => return rust::Type::generic("Vec", vec![rust::Type::path("u8")]),
+            Type::Array(inner_ty, dims) => {
+                return self.render_array_type(inner_ty, dims, loc, errors)
+            }
+            Type::Ref(ty) => return self.render_type(ty, loc, errors),
+            Type::StorageRef(ty) => return self.render_type(ty, loc, errors),
+            Type::Enum(n) => return rust::Type::path(&self.render_enum_name(*n)),
+            Type::Struct(n) => return rust::Type::path(&self.render_struct_name(*n)),
+            _ => {
+                self.unsupported::<()>(loc, &format!("type ‘{}’", ty.to_string(self.ns)), errors);
+                return rust::Type::unit();
+            }
+        };
+        rust::Type::path(name)
     }
 
-    fn render_type(&self, ty: &Type) -> String {
+    /// Does the Rust type of a type implement `Default`. An account hash does
+    /// not, nor does an array of them; a struct always does, see
+    /// `render_structs()`.
+    fn has_default(&self, ty: &Type) -> bool {
         match ty {
-            Type::Bool => "bool".to_string(),
-            Type::String => "String".to_string(),
-            Type::Uint(8) => "u8".to_string(),
-            Type::Uint(16) => "u16".to_string(),
-            Type::Uint(32) => "u32".to_string(),
-            Type::Uint(64) => "u64".to_string(),
-            Type::Uint(128) => "u128".to_string(),
-            Type::Uint(256) => "U256".to_string(),
-            Type::Int(8) => "i8".to_string(),
-            Type::Int(16) => "i16".to_string(),
-            Type::Int(32) => "i32".to_string(),
-            Type::Int(64) => "i64".to_string(),
-            Type::Int(128) => "i128".to_string(),
-            Type::Address(false) => "AccountHash".to_string(),
-            Type::Bytes(_) => "Vec<u8>".to_string(),
-            Type::Array(inner_ty, dims) => 
-                self.render_array_type(inner_ty, dims),
-            Type::Ref(ty) => self.render_type(ty),
-            Type::StorageRef(ty) => self.render_type(ty),
-            Type::Enum(_) => "u8".to_string(),
+            Type::Address(_) => false,
+            Type::Array(_, dims) => match dims.last() {
+                Some(None) => true,
+                _ => self.has_default(&ty.array_elem()),
+            },
+            Type::Ref(ty) | Type::StorageRef(ty) => self.has_default(ty),
+            _ => true,
+        }
+    }
+
+    /// The value of a variable which is not set, which is zero for an address
+    fn render_zero(&self, ty: &Type, loc: &pt::Loc, errors: &mut Vec<Output>) -> Expr {
+        let default = Expr::call(Path::new("Default::default"), vec![]);
+        if self.has_default(ty) {
+            return default;
+        }
+        match ty.deref() {
+            Type::Address(_) => Expr::call(Path::new("AccountHash::new"), vec![default]),
             _ => {
-                print!("// unknow_type: {:?}", ty);
-                "unknown_type".to_string()
+                self.unsupported::<()>(
+                    loc,
+                    &format!("default value of type ‘{}’", ty.to_string(self.ns)),
+                    errors,
+                );
+                default
             }
         }
     }
 
-    fn render_array_type(&self, inner_ty: &Type, dims: &Vec<Option<BigInt>>) -> String {
-        let mut result = self.render_type(inner_ty);
+    /// The `CLType` of the values of an entry point argument, or of the values returned by an entry point
+    fn render_cl_type(
+        &self,
+        values: &[Parameter],
+        loc: &pt::Loc,
+        errors: &mut Vec<Output>,
+    ) -> Expr {
+        if values.is_empty() {
+            return Expr::path("CLType::Unit");
+        }
+        let ty = self.render_return_type(values, loc, errors);
+        Expr::call(Path::new("cl_type").with_args(vec![ty]), vec![])
+    }
+
+    /// The type of the values returned by a function; a tuple if there are several
+    fn render_return_type(
+        &self,
+        returns: &[Parameter],
+        loc: &pt::Loc,
+        errors: &mut Vec<Output>,
+    ) -> rust::Type {
+        match returns {
+            [ret] => self.render_type(&ret.ty, loc, errors),
+            _ => rust::Type::Tuple(
+                returns
+                    .iter()
+                    .map(|ret| self.render_type(&ret.ty, loc, errors))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Contracts are called by their contract hash, and an address is the
+    /// contract hash of the contract at that address
+    fn render_contract_hash(
+        &self,
+        address: &Expression,
+        cfg: &ControlFlowGraph,
+        errors: &mut Vec<Output>,
+    ) -> Expr {
+        let hash = self.render_expression(address, cfg, errors);
+        match self.expression_type(address, cfg) {
+            Some(Type::Address(_)) => hash.method("value", vec![]),
+            _ => hash,
+        }
+    }
+
+    fn render_array_type(
+        &self,
+        inner_ty: &Type,
+        dims: &[Option<BigInt>],
+        loc: &pt::Loc,
+        errors: &mut Vec<Output>,
+    ) -> rust::Type {
+        let mut result = self.render_type(inner_ty, loc, errors);
         for dim in dims.iter() {
             match dim {
                 Some(dim) => {
-                    result = format!("[{}; {}]", result, dim);
-                },
+                    result = rust::Type::Array(Box::new(result), dim.to_usize().unwrap());
+                }
                 None => {
-                    result = format!("Vec<{}>", result)
+                    result = rust::Type::generic("Vec", vec![result]);
                 }
             }
         }
@@ -694,6 +5443,334 @@ impl<'a> CasperlabsContract<'a> {
     }
 }
 
+/// A storage location: the key under which a value of type `ty` is stored,
+/// and the struct fields and array elements which lead from that value to
+/// the location
+struct StoragePlace {
+    key: Expr,
+    ty: Type,
+    members: Vec<Member>,
+}
+
+#[derive(Clone)]
+enum Member {
+    Field(String),
+    Index(Expr),
+}
+
+impl Member {
+    fn access(self, expr: Expr) -> Expr {
+        match self {
+            Member::Field(name) => Expr::Field(Box::new(expr), name),
+            Member::Index(index) => Expr::Index(
+                Box::new(expr),
+                Box::new(Expr::Cast(Box::new(index), rust::Type::path("usize"))),
+            ),
+        }
+    }
+}
+
+/// A value stored under its own key. A dynamic array stores its length under
+/// its key, and each element under a key of its own.
+fn keyed_place(key: Expr, ty: Type) -> (StoragePlace, Type) {
+    let stored = if is_dynamic_array(&ty) {
+        Type::Uint(256)
+    } else {
+        ty.clone()
+    };
+    (
+        StoragePlace {
+            key,
+            ty: stored,
+            members: Vec::new(),
+        },
+        ty,
+    )
+}
+
+/// Split the storage slot of a dynamic array element into the slot of the
+/// array and the offset of the element, see `array_offset()`
+fn element_slot(slot: &Expression) -> Option<(&Expression, &Expression)> {
+    match slot {
+        Expression::Add(_, start, offset) | Expression::ShiftLeft(_, start, offset) => {
+            match &**start {
+                Expression::Keccak256(_, exprs) if exprs.len() == 1 => Some((&exprs[0].0, offset)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The index of an array element, given its storage offset. Casts of the
+/// index are dropped, since the index is converted where it is used.
+fn element_index<'a>(offset: &'a Expression, elem_size: &BigInt) -> Option<&'a Expression> {
+    match offset {
+        Expression::ZeroExt(_, _, expr) | Expression::Trunc(_, _, expr) => {
+            element_index(expr, elem_size)
+        }
+        Expression::Multiply(_, index, size) | Expression::ShiftLeft(_, index, size) => {
+            match &**size {
+                Expression::NumberLiteral(..) => element_index(index, &BigInt::one()),
+                _ => None,
+            }
+        }
+        _ if elem_size.is_one() => Some(offset),
+        _ => None,
+    }
+}
+
+/// The value of an integer literal, or of a power of literals
+fn literal_value(expr: &Expression) -> Option<BigInt> {
+    match expr {
+        Expression::NumberLiteral(_, _, n) => Some(n.clone()),
+        // larger exponents do not fit in any integer type
+        Expression::Power(_, base, exp) => match literal_value(exp)?.to_usize()? {
+            exp if exp <= 512 => Some(num_traits::pow(literal_value(base)?, exp)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Is the variable set more than once
+fn is_mutable(var: usize, cfg: &ControlFlowGraph) -> bool {
+    cfg.bb
+        .iter()
+        .flat_map(|bb| bb.instr.iter())
+        .filter(|instr| sets_var(instr, var))
+        .count()
+        > 1
+}
+
+fn sets_var(instr: &Instr, var: usize) -> bool {
+    match instr {
+        Instr::Set { res, .. } | Instr::Constructor { res, .. } | Instr::Hash { res, .. } => {
+            *res == var
+        }
+        Instr::Call { res, .. } | Instr::AbiDecode { res, .. } => res.contains(&var),
+        Instr::ExternalCall { success, .. } => *success == Some(var),
+        _ => false,
+    }
+}
+
+fn reads_var(instr: &Instr, var: usize) -> bool {
+    match instr {
+        Instr::Set { expr, .. }
+        | Instr::Eval { expr }
+        | Instr::Print { expr }
+        | Instr::Hash { expr, .. }
+        | Instr::AssertFailure { expr: Some(expr) }
+        | Instr::BranchCond { cond: expr, .. }
+        | Instr::AbiDecode { data: expr, .. }
+        | Instr::SelfDestruct { recipient: expr }
+        | Instr::ClearStorage { storage: expr, .. } => expr.reads_variable(var),
+        Instr::SetStorage { local, storage, .. } => *local == var || storage.reads_variable(var),
+        Instr::SetStorageBytes {
+            local,
+            storage,
+            offset,
+        } => *local == var || storage.reads_variable(var) || offset.reads_variable(var),
+        Instr::Store { dest, pos } => *pos == var || dest.reads_variable(var),
+        Instr::Call { args, .. } | Instr::EmitEvent { args, .. } => {
+            args.iter().any(|arg| arg.reads_variable(var))
+        }
+        Instr::Return { value } => value.iter().any(|v| v.reads_variable(var)),
+        Instr::Constructor {
+            args,
+            value,
+            gas,
+            salt,
+            ..
+        } => {
+            args.iter().any(|arg| arg.reads_variable(var))
+                || value.iter().any(|v| v.reads_variable(var))
+                || gas.reads_variable(var)
+                || salt.iter().any(|s| s.reads_variable(var))
+        }
+        Instr::ExternalCall {
+            address,
+            args,
+            value,
+            gas,
+            ..
+        } => {
+            address.reads_variable(var)
+                || args.iter().any(|arg| arg.reads_variable(var))
+                || value.reads_variable(var)
+                || gas.reads_variable(var)
+        }
+        Instr::AssertFailure { expr: None }
+        | Instr::Constant { .. }
+        | Instr::Branch { .. }
+        | Instr::Unreachable => false,
+    }
+}
+
+/// Locals which may be read where no definition of them is in scope, like a
+/// variable without initializer which is set in a branch and read after it.
+/// These start with their default value, as they do in Solidity.
+fn uninitialized_vars(stmts: &[Stmt], cfg: &ControlFlowGraph) -> BTreeSet<usize> {
+    let mut blocks = HashMap::new();
+    block_scopes(stmts, &mut Vec::new(), &mut 0, &mut blocks);
+    let mut result = BTreeSet::new();
+    for var in 0..cfg.vars.len() {
+        let mut sets = Vec::new();
+        let mut reads = Vec::new();
+        for (bb_no, bb) in cfg.bb.iter().enumerate() {
+            if let Some((order, scopes)) = blocks.get(&bb_no) {
+                for (instr_no, instr) in bb.instr.iter().enumerate() {
+                    if reads_var(instr, var) {
+                        reads.push(((*order, instr_no), scopes));
+                    }
+                    if sets_var(instr, var) {
+                        sets.push(((*order, instr_no), scopes));
+                    }
+                }
+            }
+        }
+        // a definition is in scope after it, in the same scope or an inner one
+        let uninitialized = reads.iter().any(|(at, scopes)| {
+            !sets
+                .iter()
+                .any(|(set_at, set_scopes)| set_at < at && scopes.starts_with(set_scopes))
+        });
+        if uninitialized {
+            result.insert(var);
+        }
+    }
+    result
+}
+
+/// Number the basic blocks in the order of the structured statements, and find
+/// the scopes which enclose each of them, outermost first
+fn block_scopes(
+    stmts: &[Stmt],
+    scopes: &mut Vec<usize>,
+    next: &mut usize,
+    result: &mut HashMap<usize, (usize, Vec<usize>)>,
+) {
+    for stmt in stmts {
+        let bodies = match stmt {
+            Stmt::BasicBlock(bb) => {
+                let order = result.len();
+                result.insert(*bb, (order, scopes.clone()));
+                continue;
+            }
+            Stmt::If(_, true_, false_) => vec![true_, false_],
+            Stmt::Loop(_, body) | Stmt::Block(_, body) => vec![body],
+            Stmt::Break(_) | Stmt::Continue(_) => continue,
+        };
+        for body in bodies {
+            *next += 1;
+            scopes.push(*next);
+            block_scopes(body, scopes, next, result);
+            scopes.pop();
+        }
+    }
+}
+
+/// The variable which the argument of the function is copied to
+fn argument_var(arg: usize, cfg: &ControlFlowGraph) -> Option<usize> {
+    cfg.bb
+        .iter()
+        .flat_map(|bb| bb.instr.iter())
+        .filter_map(|instr| match instr {
+            Instr::Set {
+                res,
+                expr: Expression::FunctionArg(_, n),
+            } if *n == arg => Some(*res),
+            _ => None,
+        })
+        .next()
+}
+
+/// Split the index of a fixed length byte array `(trunc bytes1 (a >> ((N - 1 - i) << 3)))`
+/// into the array and the index
+fn bytes_index(expr: &Expression) -> Option<(&Expression, &Expression)> {
+    let shifted = match expr {
+        Expression::Trunc(_, Type::Bytes(1), shifted) => shifted,
+        _ => return None,
+    };
+    let (array, offset) = match &**shifted {
+        Expression::ShiftRight(_, array, offset, false) => (array, offset),
+        _ => return None,
+    };
+    match &**offset {
+        Expression::ShiftLeft(_, position, _) => match &**position {
+            Expression::Subtract(_, _, index) => Some((array, uncast(index))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// An integer without the casts to the width of the value it shifts
+fn uncast(expr: &Expression) -> &Expression {
+    match expr {
+        Expression::ZeroExt(_, _, expr)
+        | Expression::SignExt(_, _, expr)
+        | Expression::Trunc(_, _, expr) => uncast(expr),
+        _ => expr,
+    }
+}
+
+/// Return the value from an entry point to its caller
+fn render_ret(value: Expr) -> Expr {
+    let value = Expr::call(Path::new("CLValue::from_t"), vec![value]);
+    Expr::call(
+        Path::new("runtime::ret"),
+        vec![value.method("unwrap_or_revert", vec![])],
+    )
+}
+
+/// The variants of the enum with their values, in order of value
+fn enum_variants(def: &EnumDecl) -> Vec<(&String, usize)> {
+    let mut variants: Vec<(&String, usize)> =
+        def.values.iter().map(|(name, (_, n))| (name, *n)).collect();
+    variants.sort_by_key(|(_, n)| *n);
+    variants
+}
+
+fn is_dynamic_array(ty: &Type) -> bool {
+    match ty.deref() {
+        Type::Array(_, dims) => dims.last().unwrap().is_none(),
+        _ => false,
+    }
+}
+
+/// The Rust type of the values of an integer type, and its width. The Casper
+/// runtime only serializes some of the primitive integers, so the others are
+/// held by a wider type; signed integers wider than 64 bits are held by the
+/// `I256` helper type.
+fn int_type(ty: &Type) -> Option<(&'static str, u16)> {
+    match ty {
+        Type::Uint(bits) => Some(match *bits {
+            0..=8 => ("u8", 8),
+            9..=32 => ("u32", 32),
+            33..=64 => ("u64", 64),
+            65..=128 => ("U128", 128),
+            129..=256 => ("U256", 256),
+            _ => ("U512", 512),
+        }),
+        Type::Int(bits) => Some(match *bits {
+            0..=32 => ("i32", 32),
+            33..=64 => ("i64", 64),
+            _ => ("I256", 256),
+        }),
+        _ => None,
+    }
+}
+
+/// Values of these types are `Copy` in the generated code
+fn is_copy(ty: &Type) -> bool {
+    !ty.deref().is_reference_type()
+}
+
+fn is_if(stmt: &rust::Stmt) -> bool {
+    matches!(stmt, rust::Stmt::Expr(Expr::If(..)))
+}
+
 fn is_blacklisted_fn(name: &str) -> bool {
     let mut fns = BTreeSet::new();
     fns.insert("print(string)");
@@ -710,3 +5787,112 @@ fn is_blacklisted_fn(name: &str) -> bool {
     fns.insert("blake2_256(bytes)");
     fns.contains(name)
 }
+
+/// The hash computed by a builtin hash function, like `keccak256()`
+fn builtin_hash(function: &FunctionDecl) -> Option<&HashTy> {
+    if function.ast_index.is_some() {
+        return None;
+    }
+    match function.cfg.as_ref()?.bb[0].instr.first() {
+        Some(Instr::Hash { hash, .. }) => Some(hash),
+        _ => None,
+    }
+}
+
+/// A call to the helper in the footer which computes the hash of some bytes
+fn render_hash(hash: &HashTy, bytes: Expr) -> Expr {
+    Expr::call(Path::new(&hash.to_string()), vec![bytes.reference()])
+}
+
+/// Builtins which are implemented by the helper functions in the footer
+fn is_helper_fn(name: &str) -> bool {
+    matches!(
+        name,
+        "assert(bool)" | "revert()" | "revert(string)" | "require(bool)" | "require(bool,string)"
+    )
+}
+
+/// Description of an expression which cannot be transpiled, for error messages
+fn expression_name(expression: &Expression) -> &'static str {
+    match expression {
+        Expression::CodeLiteral(..) => "contract code",
+        Expression::ConstArrayLiteral(..) => "constant array",
+        Expression::Trunc(..) => "integer conversion",
+        Expression::StorageBytesSubscript(..) => "subscript of storage bytes",
+        Expression::StringCompare(..) => "string comparison",
+        Expression::StringConcat(..) => "string concatenation",
+        Expression::LocalFunctionCall(..) => "function call with return values",
+        Expression::ExternalFunctionCall { .. } => "calling other contracts",
+        Expression::Constructor { .. } => "creating contracts with ‘new’",
+        Expression::ReturnData(..) => "return data",
+        Expression::GetAddress(..) => "‘address(this)’",
+        Expression::Balance(..) => "‘balance’",
+        _ => "expression",
+    }
+}
+
+/// The helpers of the footer which the items call, directly or through other
+/// helpers. Of the methods of a type like `I256`, only those which are called
+/// are kept; its trait impls are always kept.
+fn used_helpers(footer: Vec<Item>, items: &[Item]) -> Vec<Item> {
+    let is_helper = |item: &Item| match item {
+        Item::Fn(_) => true,
+        Item::Impl(def) => def.trait_.is_none(),
+        _ => false,
+    };
+    let mut names = rust::names(items);
+    names.extend(rust::names(
+        &footer
+            .iter()
+            .filter(|item| !is_helper(item))
+            .cloned()
+            .collect::<Vec<Item>>(),
+    ));
+    let mut functions: Vec<&Function> = footer
+        .iter()
+        .flat_map(|item| match item {
+            Item::Fn(function) => vec![function],
+            Item::Impl(def) if def.trait_.is_none() => def.fns.iter().collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+    // the helpers which are called add the names they refer to, until no
+    // other helper is called
+    loop {
+        let (called, rest): (Vec<&Function>, Vec<&Function>) = functions
+            .into_iter()
+            .partition(|function| names.contains(&function.name));
+        if called.is_empty() {
+            break;
+        }
+        for function in called {
+            names.extend(rust::names(&[Item::Fn(function.clone())]));
+        }
+        functions = rest;
+    }
+    footer
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Fn(ref function) if !names.contains(&function.name) => None,
+            Item::Impl(mut def) if def.trait_.is_none() => {
+                def.fns.retain(|function| names.contains(&function.name));
+                Some(Item::Impl(def))
+            }
+            item => Some(item),
+        })
+        .collect()
+}
+
+/// The entry of `LOCKED_PACKAGES` with the given name and version
+fn locked_package((name, version): (&str, &str)) -> &'static LockedPackage {
+    LOCKED_PACKAGES
+        .iter()
+        .find(|(n, v, _, _)| *n == name && *v == version)
+        .expect("dependency should be locked")
+}
+
+/// Split a dependency of `LOCKED_PACKAGES` into its name and version
+fn package_id(dependency: &str) -> (&str, &str) {
+    let mut parts = dependency.splitn(2, ' ');
+    (parts.next().unwrap(), parts.next().unwrap())
+}
@@ -104,6 +104,10 @@ pub enum Instr {
         hash: HashTy,
         expr: Expression,
     },
+    EmitEvent {
+        event_no: usize,
+        args: Vec<Expression>,
+    },
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -146,6 +150,7 @@ pub struct ControlFlowGraph {
     pub bb: Vec<BasicBlock>,
     current: usize,
     pub writes_contract_storage: bool,
+    pub reads_value: bool,
 }
 
 impl ControlFlowGraph {
@@ -155,6 +160,7 @@ impl ControlFlowGraph {
             bb: Vec::new(),
             current: 0,
             writes_contract_storage: false,
+            reads_value: false,
         };
 
         cfg.new_basic_block("entry".to_string());
@@ -511,6 +517,9 @@ impl ControlFlowGraph {
             Expression::Balance(_, addr) => {
                 format!("(balance {})", self.expr_to_string(contract, ns, addr))
             }
+            Expression::Sender(_) => "(sender)".to_string(),
+            Expression::Value(_) => "(value)".to_string(),
+            Expression::Timestamp(_) => "(timestamp)".to_string(),
         }
     }
 
@@ -720,6 +729,14 @@ impl ControlFlowGraph {
                 hash,
                 self.expr_to_string(contract, ns, expr)
             ),
+            Instr::EmitEvent { event_no, args } => format!(
+                "emit event {} ({})",
+                contract.events[*event_no].name,
+                args.iter()
+                    .map(|expr| self.expr_to_string(contract, ns, expr))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
         }
     }
 
@@ -765,6 +782,7 @@ impl ControlFlowGraph {
 pub fn generate_cfg(
     ast_f: &pt::FunctionDefinition,
     resolve_f: &resolver::FunctionDecl,
+    defs: &[&pt::ContractDefinition],
     contract_no: usize,
     ns: &resolver::Namespace,
     errors: &mut Vec<output::Output>,
@@ -774,6 +792,8 @@ pub fn generate_cfg(
     let mut vartab = Vartable::new();
     let mut loops = LoopScopes::new();
 
+    vartab.base_contract = resolve_f.base_contract;
+
     // first add function parameters
     for (i, p) in ast_f.params.iter().enumerate() {
         let p = p.1.as_ref().unwrap();
@@ -792,13 +812,37 @@ pub fn generate_cfg(
         }
     }
 
+    // Modifiers are inlined into the function. Unknown modifiers have already been reported
+    // when the function declaration was resolved.
+    let mut modifiers = Vec::new();
+
+    if resolve_f.ty != pt::FunctionTy::Modifier {
+        for attr in &ast_f.attributes {
+            if let pt::FunctionAttribute::BaseOrModifier(base) = attr {
+                if let Ok(m) = ns.resolve_modifier(contract_no, &base.name, &mut Vec::new()) {
+                    let modifier = &ns.contracts[contract_no].modifiers[m];
+
+                    let def = defs[modifier.base_contract.unwrap_or(contract_no)];
+
+                    if let pt::ContractPart::FunctionDefinition(ref ast_m) =
+                        def.parts[modifier.ast_index.unwrap()]
+                    {
+                        modifiers.push((base, modifier, ast_m.as_ref()));
+                    }
+                }
+            }
+        }
+    }
+
     // If any of the return values are named, then the return statement can be omitted at
     // the end of the function, and return values may be omitted too. Create variables to
-    // store the return values
-    if ast_f
-        .returns
-        .iter()
-        .any(|v| v.1.as_ref().unwrap().name.is_some())
+    // store the return values. These are also needed if there are modifiers, since the
+    // modifier code after the placeholder runs after the function body returns.
+    if !modifiers.is_empty()
+        || ast_f
+            .returns
+            .iter()
+            .any(|v| v.1.as_ref().unwrap().name.is_some())
     {
         let mut returns = Vec::new();
 
@@ -825,7 +869,7 @@ pub fn generate_cfg(
                 // this variable can never be assigned but will need a zero value
                 let pos = vartab.temp(
                     &pt::Identifier {
-                        loc: pt::Loc(0, 0),
+                        loc: pt::Loc(0, 0, 0),
                         name: format!("arg{}", i),
                     },
                     &resolve_f.returns[i].ty.clone(),
@@ -847,16 +891,50 @@ pub fn generate_cfg(
         vartab.returns = returns;
     }
 
-    let reachable = statement(
-        &ast_f.body,
-        resolve_f,
-        &mut cfg,
-        contract_no,
-        ns,
-        &mut vartab,
-        &mut loops,
-        errors,
-    )?;
+    // the constructors of the base contracts are called before the constructor body
+    if resolve_f.is_constructor() {
+        base_constructors(
+            Some(ast_f),
+            &mut cfg,
+            defs,
+            contract_no,
+            ns,
+            &mut vartab,
+            errors,
+        )?;
+    }
+
+    let mut chain = ModifierChain {
+        modifiers,
+        body: &ast_f.body,
+        function: resolve_f,
+        function_scope: vartab.names.clone(),
+        level: 0,
+        return_bb: None,
+        no_returns: 0,
+        seen: 0,
+        errors: Vec::new(),
+    };
+
+    let reachable = if chain.modifiers.is_empty() {
+        statement(
+            &ast_f.body,
+            resolve_f,
+            &mut cfg,
+            contract_no,
+            ns,
+            &mut vartab,
+            &mut loops,
+            &mut chain,
+            errors,
+        )?
+    } else {
+        let reachable = inline_layer(0, &mut cfg, contract_no, ns, &mut vartab, &mut chain);
+
+        errors.append(&mut chain.errors);
+
+        reachable?
+    };
 
     // ensure we have a return instruction
     if reachable {
@@ -870,6 +948,308 @@ pub fn generate_cfg(
     Ok(cfg)
 }
 
+/// Generate the cfg for the implicit constructor of a contract, which calls the constructors
+/// of its base contracts
+pub fn generate_default_constructor_cfg(
+    contract_no: usize,
+    defs: &[&pt::ContractDefinition],
+    ns: &resolver::Namespace,
+    errors: &mut Vec<output::Output>,
+) -> Result<Box<ControlFlowGraph>, ()> {
+    let mut cfg = Box::new(ControlFlowGraph::new());
+    let mut vartab = Vartable::new();
+
+    base_constructors(None, &mut cfg, defs, contract_no, ns, &mut vartab, errors)?;
+
+    cfg.add(&mut vartab, Instr::Return { value: Vec::new() });
+    cfg.vars = vartab.drain();
+
+    Ok(cfg)
+}
+
+/// Call the constructors of the base contracts, most base contract first. The arguments are
+/// given either on the constructor of the derived contract, or in the list of base contracts
+/// of any contract in the inheritance graph.
+fn base_constructors(
+    ast_f: Option<&pt::FunctionDefinition>,
+    cfg: &mut ControlFlowGraph,
+    defs: &[&pt::ContractDefinition],
+    contract_no: usize,
+    ns: &resolver::Namespace,
+    vartab: &mut Vartable,
+    errors: &mut Vec<output::Output>,
+) -> Result<(), ()> {
+    let contract = &ns.contracts[contract_no];
+
+    for base_no in contract.bases.iter().rev() {
+        let base_name = &ns.contracts[*base_no].name;
+
+        let func =
+            match contract.functions.iter().position(|f| {
+                f.ty == pt::FunctionTy::Constructor && f.base_contract == Some(*base_no)
+            }) {
+                Some(func) => func,
+                None => continue,
+            };
+
+        let ctor = &contract.functions[func];
+
+        let mut bases: Vec<&pt::Base> = Vec::new();
+
+        if let Some(ast_f) = ast_f {
+            for attr in &ast_f.attributes {
+                if let pt::FunctionAttribute::BaseOrModifier(base) = attr {
+                    bases.push(base);
+                }
+            }
+        }
+
+        for c in std::iter::once(&contract_no).chain(contract.bases.iter()) {
+            bases.extend(defs[*c].base.iter());
+        }
+
+        let no_args = Vec::new();
+
+        let (loc, args) = match bases
+            .into_iter()
+            .find(|base| base.name.name == *base_name && base.args.is_some())
+        {
+            Some(base) => (base.loc, base.args.as_ref().unwrap()),
+            None => match ast_f {
+                Some(ast_f) => (ast_f.loc, &no_args),
+                None => (defs[contract_no].name.loc, &no_args),
+            },
+        };
+
+        if ctor.params.len() != args.len() {
+            errors.push(Output::error(
+                loc,
+                format!(
+                    "constructor of base contract ‘{}’ expects {} arguments, {} provided",
+                    base_name,
+                    ctor.params.len(),
+                    args.len()
+                ),
+            ));
+            return Err(());
+        }
+
+        let mut cast_args = Vec::new();
+
+        for (i, arg) in args.iter().enumerate() {
+            let (expr, expr_ty) =
+                expression(arg, cfg, Some(contract_no), ns, &mut Some(vartab), errors)?;
+
+            cast_args.push(cast(
+                &arg.loc(),
+                expr,
+                &expr_ty,
+                &ctor.params[i].ty,
+                true,
+                ns,
+                errors,
+            )?);
+        }
+
+        cfg.add(
+            vartab,
+            Instr::Call {
+                res: Vec::new(),
+                func,
+                args: cast_args,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+/// Modifiers are inlined into the function which uses them; the placeholder statement `_`
+/// in the modifier body is replaced by the next modifier, or the function body if this is
+/// the last modifier.
+struct ModifierChain<'a> {
+    modifiers: Vec<(
+        &'a pt::Base,
+        &'a resolver::FunctionDecl,
+        &'a pt::FunctionDefinition,
+    )>,
+    body: &'a pt::Statement,
+    function: &'a resolver::FunctionDecl,
+    function_scope: LinkedList<VarScope>,
+    // the modifier being inlined; if this is modifiers.len() then it is the function body
+    level: usize,
+    // return statements branch to the end of the modifier or function body being inlined
+    return_bb: Option<usize>,
+    no_returns: usize,
+    // a modifier may have more than one placeholder; only report errors the first time
+    seen: usize,
+    errors: Vec<output::Output>,
+}
+
+/// Inline the modifier at the given level, or the function body if all the modifiers
+/// have been inlined
+fn inline_layer(
+    level: usize,
+    cfg: &mut ControlFlowGraph,
+    contract_no: usize,
+    ns: &resolver::Namespace,
+    vartab: &mut Vartable,
+    chain: &mut ModifierChain,
+) -> Result<bool, ()> {
+    let end = cfg.new_basic_block("placeholder_end".to_string());
+
+    let report = level >= chain.seen;
+
+    if report {
+        chain.seen = level + 1;
+    }
+
+    let prev_level = chain.level;
+    let prev_return_bb = chain.return_bb;
+    let prev_no_returns = chain.no_returns;
+
+    chain.level = level;
+    chain.return_bb = Some(end);
+    chain.no_returns = 0;
+
+    vartab.new_dirty_tracker();
+
+    // the arguments to the modifier and the function body are resolved in the function scope
+    let prev_scopes = vartab.swap_scopes(chain.function_scope.clone());
+
+    let mut errors = Vec::new();
+
+    let reachable = if level < chain.modifiers.len() {
+        let (base, modifier, ast_m) = chain.modifiers[level];
+
+        let args = modifier_args(base, modifier, cfg, contract_no, ns, vartab, &mut errors);
+
+        if report {
+            chain.errors.append(&mut errors);
+        }
+
+        let args = args?;
+
+        // the modifier body cannot see the variables of the function. Any errors in the
+        // modifier body have already been reported
+        vartab.swap_scopes(LinkedList::new());
+        vartab.new_scope();
+
+        for (i, arg) in args.into_iter().enumerate() {
+            if let Some(ref name) = ast_m.params[i].1.as_ref().unwrap().name {
+                if let Some(pos) = vartab.add(name, modifier.params[i].ty.clone(), &mut errors) {
+                    cfg.add(
+                        vartab,
+                        Instr::Set {
+                            res: pos,
+                            expr: arg,
+                        },
+                    );
+                }
+            }
+        }
+
+        statement(
+            &ast_m.body,
+            modifier,
+            cfg,
+            contract_no,
+            ns,
+            vartab,
+            &mut LoopScopes::new(),
+            chain,
+            &mut Vec::new(),
+        )?
+    } else {
+        let body = chain.body;
+        let function = chain.function;
+
+        let reachable = statement(
+            body,
+            function,
+            cfg,
+            contract_no,
+            ns,
+            vartab,
+            &mut LoopScopes::new(),
+            chain,
+            &mut errors,
+        );
+
+        if report {
+            chain.errors.append(&mut errors);
+        }
+
+        reachable?
+    };
+
+    if reachable {
+        cfg.add(vartab, Instr::Branch { bb: end });
+    }
+
+    let reachable = reachable || chain.no_returns > 0;
+
+    vartab.swap_scopes(prev_scopes);
+
+    chain.level = prev_level;
+    chain.return_bb = prev_return_bb;
+    chain.no_returns = prev_no_returns;
+
+    cfg.set_phis(end, vartab.pop_dirty_tracker());
+
+    cfg.set_basic_block(end);
+
+    Ok(reachable)
+}
+
+/// Resolve the arguments to a modifier
+fn modifier_args(
+    base: &pt::Base,
+    modifier: &resolver::FunctionDecl,
+    cfg: &mut ControlFlowGraph,
+    contract_no: usize,
+    ns: &resolver::Namespace,
+    vartab: &mut Vartable,
+    errors: &mut Vec<output::Output>,
+) -> Result<Vec<Expression>, ()> {
+    let args = match &base.args {
+        Some(args) => args.as_slice(),
+        None => &[],
+    };
+
+    if modifier.params.len() != args.len() {
+        errors.push(Output::error(
+            base.loc,
+            format!(
+                "modifier ‘{}’ expects {} arguments, {} provided",
+                modifier.name,
+                modifier.params.len(),
+                args.len()
+            ),
+        ));
+        return Err(());
+    }
+
+    let mut cast_args = Vec::new();
+
+    for (i, arg) in args.iter().enumerate() {
+        let (expr, expr_ty) =
+            expression(arg, cfg, Some(contract_no), ns, &mut Some(vartab), errors)?;
+
+        cast_args.push(cast(
+            &arg.loc(),
+            expr,
+            &expr_ty,
+            &modifier.params[i].ty,
+            true,
+            ns,
+            errors,
+        )?);
+    }
+
+    Ok(cast_args)
+}
+
 fn check_return(
     f: &pt::FunctionDefinition,
     cfg: &mut ControlFlowGraph,
@@ -892,7 +1272,7 @@ fn check_return(
             value: vartab
                 .returns
                 .iter()
-                .map(|pos| Expression::Variable(pt::Loc(0, 0), *pos))
+                .map(|pos| Expression::Variable(pt::Loc(0, 0, 0), *pos))
                 .collect(),
         });
 
@@ -964,6 +1344,7 @@ fn statement(
     ns: &resolver::Namespace,
     vartab: &mut Vartable,
     loops: &mut LoopScopes,
+    chain: &mut ModifierChain,
     errors: &mut Vec<output::Output>,
 ) -> Result<bool, ()> {
     match stmt {
@@ -1009,13 +1390,26 @@ fn statement(
                     ));
                     return Err(());
                 }
-                reachable = statement(&stmt, f, cfg, contract_no, ns, vartab, loops, errors)?;
+                reachable =
+                    statement(&stmt, f, cfg, contract_no, ns, vartab, loops, chain, errors)?;
             }
 
             vartab.leave_scope();
 
             Ok(reachable)
         }
+        pt::Statement::Return(_, None) if chain.return_bb.is_some() => {
+            chain.no_returns += 1;
+
+            cfg.add(
+                vartab,
+                Instr::Branch {
+                    bb: chain.return_bb.unwrap(),
+                },
+            );
+
+            Ok(false)
+        }
         pt::Statement::Return(loc, None) => {
             let no_returns = f.returns.len();
 
@@ -1036,7 +1430,7 @@ fn statement(
                     value: vartab
                         .returns
                         .iter()
-                        .map(|pos| Expression::Variable(pt::Loc(0, 0), *pos))
+                        .map(|pos| Expression::Variable(pt::Loc(0, 0, 0), *pos))
                         .collect(),
                 },
             );
@@ -1044,7 +1438,7 @@ fn statement(
             Ok(false)
         }
         pt::Statement::Return(loc, Some(returns)) => {
-            return_with_values(returns, loc, f, cfg, contract_no, ns, vartab, errors)
+            return_with_values(returns, loc, f, cfg, contract_no, ns, vartab, chain, errors)
         }
         pt::Statement::Expression(_, expr) => {
             let (expr, _) =
@@ -1076,6 +1470,7 @@ fn statement(
             ns,
             vartab,
             loops,
+            chain,
             errors,
         ),
         pt::Statement::If(_, cond, then_stmt, Some(else_stmt)) => if_then_else(
@@ -1088,6 +1483,7 @@ fn statement(
             ns,
             vartab,
             loops,
+            chain,
             errors,
         ),
         pt::Statement::Break(_) => match loops.do_break() {
@@ -1129,8 +1525,17 @@ fn statement(
             vartab.new_dirty_tracker();
             loops.new_scope(end, cond);
 
-            let mut body_reachable =
-                statement(body_stmt, f, cfg, contract_no, ns, vartab, loops, errors)?;
+            let mut body_reachable = statement(
+                body_stmt,
+                f,
+                cfg,
+                contract_no,
+                ns,
+                vartab,
+                loops,
+                chain,
+                errors,
+            )?;
 
             if body_reachable {
                 cfg.add(vartab, Instr::Branch { bb: cond });
@@ -1223,8 +1628,17 @@ fn statement(
             vartab.new_dirty_tracker();
             loops.new_scope(end, cond);
 
-            let body_reachable =
-                statement(body_stmt, f, cfg, contract_no, ns, vartab, loops, errors)?;
+            let body_reachable = statement(
+                body_stmt,
+                f,
+                cfg,
+                contract_no,
+                ns,
+                vartab,
+                loops,
+                chain,
+                errors,
+            )?;
 
             if body_reachable {
                 cfg.add(vartab, Instr::Branch { bb: cond });
@@ -1248,7 +1662,17 @@ fn statement(
             vartab.new_scope();
 
             if let Some(init_stmt) = init_stmt {
-                statement(init_stmt, f, cfg, contract_no, ns, vartab, loops, errors)?;
+                statement(
+                    init_stmt,
+                    f,
+                    cfg,
+                    contract_no,
+                    ns,
+                    vartab,
+                    loops,
+                    chain,
+                    errors,
+                )?;
             }
 
             cfg.add(vartab, Instr::Branch { bb: body });
@@ -1265,9 +1689,17 @@ fn statement(
             vartab.new_dirty_tracker();
 
             let mut body_reachable = match body_stmt {
-                Some(body_stmt) => {
-                    statement(body_stmt, f, cfg, contract_no, ns, vartab, loops, errors)?
-                }
+                Some(body_stmt) => statement(
+                    body_stmt,
+                    f,
+                    cfg,
+                    contract_no,
+                    ns,
+                    vartab,
+                    loops,
+                    chain,
+                    errors,
+                )?,
                 None => true,
             };
 
@@ -1284,8 +1716,17 @@ fn statement(
             if body_reachable {
                 if let Some(next_stmt) = next_stmt {
                     cfg.set_basic_block(next);
-                    body_reachable =
-                        statement(next_stmt, f, cfg, contract_no, ns, vartab, loops, errors)?;
+                    body_reachable = statement(
+                        next_stmt,
+                        f,
+                        cfg,
+                        contract_no,
+                        ns,
+                        vartab,
+                        loops,
+                        chain,
+                        errors,
+                    )?;
                 }
 
                 if body_reachable {
@@ -1314,7 +1755,17 @@ fn statement(
             vartab.new_scope();
 
             if let Some(init_stmt) = init_stmt {
-                statement(init_stmt, f, cfg, contract_no, ns, vartab, loops, errors)?;
+                statement(
+                    init_stmt,
+                    f,
+                    cfg,
+                    contract_no,
+                    ns,
+                    vartab,
+                    loops,
+                    chain,
+                    errors,
+                )?;
             }
 
             cfg.add(vartab, Instr::Branch { bb: cond });
@@ -1360,9 +1811,17 @@ fn statement(
             vartab.new_dirty_tracker();
 
             let mut body_reachable = match body_stmt {
-                Some(body_stmt) => {
-                    statement(body_stmt, f, cfg, contract_no, ns, vartab, loops, errors)?
-                }
+                Some(body_stmt) => statement(
+                    body_stmt,
+                    f,
+                    cfg,
+                    contract_no,
+                    ns,
+                    vartab,
+                    loops,
+                    chain,
+                    errors,
+                )?,
                 None => true,
             };
 
@@ -1380,8 +1839,17 @@ fn statement(
                 cfg.set_basic_block(next);
 
                 if let Some(next_stmt) = next_stmt {
-                    body_reachable =
-                        statement(next_stmt, f, cfg, contract_no, ns, vartab, loops, errors)?;
+                    body_reachable = statement(
+                        next_stmt,
+                        f,
+                        cfg,
+                        contract_no,
+                        ns,
+                        vartab,
+                        loops,
+                        chain,
+                        errors,
+                    )?;
                 }
 
                 if body_reachable {
@@ -1404,7 +1872,7 @@ fn statement(
             Ok(true)
         }
         pt::Statement::Try(_, _, _, _, _) => {
-            try_catch(stmt, f, cfg, contract_no, ns, vartab, loops, errors)
+            try_catch(stmt, f, cfg, contract_no, ns, vartab, loops, chain, errors)
         }
         pt::Statement::Args(_, _) => {
             errors.push(Output::error(
@@ -1413,10 +1881,95 @@ fn statement(
             ));
             Err(())
         }
+        pt::Statement::Emit(loc, id, args) => {
+            emit_event(loc, id, args, cfg, contract_no, ns, vartab, errors)
+        }
+        pt::Statement::PlaceHolder(loc) => {
+            if chain.level < chain.modifiers.len() {
+                inline_layer(chain.level + 1, cfg, contract_no, ns, vartab, chain)
+            } else if f.ty == pt::FunctionTy::Modifier {
+                // modifier bodies are checked by themselves; the placeholder is only
+                // replaced once the modifier is inlined
+                Ok(true)
+            } else {
+                errors.push(Output::error(
+                    *loc,
+                    "placeholder statement ‘_’ only allowed in modifier".to_string(),
+                ));
+                Err(())
+            }
+        }
         _ => panic!("not implemented"),
     }
 }
 
+/// Resolve emit statement
+fn emit_event(
+    loc: &pt::Loc,
+    id: &pt::Identifier,
+    args: &[pt::Expression],
+    cfg: &mut ControlFlowGraph,
+    contract_no: usize,
+    ns: &resolver::Namespace,
+    vartab: &mut Vartable,
+    errors: &mut Vec<output::Output>,
+) -> Result<bool, ()> {
+    let event_no = ns.resolve_event(contract_no, id, errors)?;
+    let event = &ns.contracts[contract_no].events[event_no];
+
+    if event.fields.len() != args.len() {
+        errors.push(Output::error(
+            *loc,
+            format!(
+                "event ‘{}’ expects {} arguments, {} provided",
+                event.name,
+                event.fields.len(),
+                args.len()
+            ),
+        ));
+        return Err(());
+    }
+
+    let mut cast_args = Vec::new();
+
+    for (i, arg) in args.iter().enumerate() {
+        let (expr, expr_ty) =
+            expression(arg, cfg, Some(contract_no), ns, &mut Some(vartab), errors)?;
+
+        cast_args.push(cast(
+            &arg.loc(),
+            expr,
+            &expr_ty,
+            &event.fields[i].ty,
+            true,
+            ns,
+            errors,
+        )?);
+    }
+
+    if ns.target != crate::Target::Casperlabs {
+        errors.push(Output::warning(
+            *loc,
+            format!(
+                "events are not supported on target {}, event not emitted",
+                ns.target
+            ),
+        ));
+    }
+
+    cfg.writes_contract_storage = true;
+
+    cfg.add(
+        vartab,
+        Instr::EmitEvent {
+            event_no,
+            args: cast_args,
+        },
+    );
+
+    Ok(true)
+}
+
 /// Parse return statement with values
 fn return_with_values(
     returns: &pt::Expression,
@@ -1426,8 +1979,17 @@ fn return_with_values(
     contract_no: usize,
     ns: &resolver::Namespace,
     vartab: &mut Vartable,
+    chain: &mut ModifierChain,
     errors: &mut Vec<output::Output>,
 ) -> Result<bool, ()> {
+    if f.ty == pt::FunctionTy::Modifier {
+        errors.push(Output::error(
+            *loc,
+            "modifier cannot return values".to_string(),
+        ));
+        return Err(());
+    }
+
     let returns = parameter_list_to_expr_list(returns, errors)?;
 
     let no_returns = f.returns.len();
@@ -1471,6 +2033,38 @@ fn return_with_values(
         exprs.push(cast(&r.loc(), e, &ty, &f.returns[i].ty, true, ns, errors)?);
     }
 
+    // with modifiers, the return values are stored and the modifier code after the
+    // placeholder is executed
+    if let Some(bb) = chain.return_bb {
+        let mut temps = Vec::new();
+
+        for (i, expr) in exprs.into_iter().enumerate() {
+            let pos = vartab.temp_anonymous(&f.returns[i].ty);
+
+            cfg.add(vartab, Instr::Set { res: pos, expr });
+
+            temps.push(pos);
+        }
+
+        for (i, pos) in temps.into_iter().enumerate() {
+            let res = vartab.returns[i];
+
+            cfg.add(
+                vartab,
+                Instr::Set {
+                    res,
+                    expr: Expression::Variable(pt::Loc(0, 0, 0), pos),
+                },
+            );
+        }
+
+        chain.no_returns += 1;
+
+        cfg.add(vartab, Instr::Branch { bb });
+
+        return Ok(false);
+    }
+
     cfg.add(vartab, Instr::Return { value: exprs });
 
     Ok(false)
@@ -1486,6 +2080,7 @@ fn if_then(
     ns: &resolver::Namespace,
     vartab: &mut Vartable,
     loops: &mut LoopScopes,
+    chain: &mut ModifierChain,
     errors: &mut Vec<output::Output>,
 ) -> Result<bool, ()> {
     let (expr, expr_ty) = expression(cond, cfg, Some(contract_no), ns, &mut Some(vartab), errors)?;
@@ -1515,7 +2110,17 @@ fn if_then(
     vartab.new_scope();
     vartab.new_dirty_tracker();
 
-    let reachable = statement(then_stmt, f, cfg, contract_no, ns, vartab, loops, errors)?;
+    let reachable = statement(
+        then_stmt,
+        f,
+        cfg,
+        contract_no,
+        ns,
+        vartab,
+        loops,
+        chain,
+        errors,
+    )?;
 
     if reachable {
         cfg.add(vartab, Instr::Branch { bb: endif });
@@ -1540,6 +2145,7 @@ fn if_then_else(
     ns: &resolver::Namespace,
     vartab: &mut Vartable,
     loops: &mut LoopScopes,
+    chain: &mut ModifierChain,
     errors: &mut Vec<output::Output>,
 ) -> Result<bool, ()> {
     let (expr, expr_ty) = expression(cond, cfg, Some(contract_no), ns, &mut Some(vartab), errors)?;
@@ -1571,7 +2177,17 @@ fn if_then_else(
     vartab.new_scope();
     vartab.new_dirty_tracker();
 
-    let then_reachable = statement(then_stmt, f, cfg, contract_no, ns, vartab, loops, errors)?;
+    let then_reachable = statement(
+        then_stmt,
+        f,
+        cfg,
+        contract_no,
+        ns,
+        vartab,
+        loops,
+        chain,
+        errors,
+    )?;
 
     if then_reachable {
         cfg.add(vartab, Instr::Branch { bb: endif });
@@ -1584,7 +2200,17 @@ fn if_then_else(
 
     vartab.new_scope();
 
-    let else_reachable = statement(else_stmt, f, cfg, contract_no, ns, vartab, loops, errors)?;
+    let else_reachable = statement(
+        else_stmt,
+        f,
+        cfg,
+        contract_no,
+        ns,
+        vartab,
+        loops,
+        chain,
+        errors,
+    )?;
 
     if else_reachable {
         cfg.add(vartab, Instr::Branch { bb: endif });
@@ -1607,6 +2233,7 @@ fn try_catch(
     ns: &resolver::Namespace,
     vartab: &mut Vartable,
     loops: &mut LoopScopes,
+    chain: &mut ModifierChain,
     errors: &mut Vec<output::Output>,
 ) -> Result<bool, ()> {
     if let pt::Statement::Try(_, expr, returns_and_ok, error_stmt, catch_stmt) = &try {
@@ -1718,10 +2345,10 @@ fn try_catch(
             Some(ok) => ok,
             None => {
                 // position after the expression
-                let pos = expr.loc().1;
+                let loc = expr.loc();
 
                 errors.push(Output::error(
-                    pt::Loc(pos, pos),
+                    pt::Loc(loc.0, loc.2, loc.2),
                     "code block missing for no catch".to_string(),
                 ));
                 return Err(());
@@ -1730,7 +2357,7 @@ fn try_catch(
 
         let success = vartab.temp(
             &pt::Identifier {
-                loc: pt::Loc(0, 0),
+                loc: pt::Loc(0, 0, 0),
                 name: "success".to_owned(),
             },
             &resolver::Type::Bool,
@@ -1768,7 +2395,7 @@ fn try_catch(
                 cfg.add(
                     vartab,
                     Instr::BranchCond {
-                        cond: Expression::Variable(pt::Loc(0, 0), success),
+                        cond: Expression::Variable(pt::Loc(0, 0, 0), success),
                         true_: success_block,
                         false_: catch_block,
                     },
@@ -1793,7 +2420,7 @@ fn try_catch(
                     let mut res = Vec::new();
                     for ret in &ftype.returns {
                         let id = pt::Identifier {
-                            loc: pt::Loc(0, 0),
+                            loc: pt::Loc(0, 0, 0),
                             name: "".to_owned(),
                         };
                         let temp_pos = vartab.temp(&id, &ret.ty);
@@ -1807,7 +2434,7 @@ fn try_catch(
                             selector: None,
                             exception: None,
                             tys: ftype.returns.clone(),
-                            data: Expression::ReturnData(pt::Loc(0, 0)),
+                            data: Expression::ReturnData(pt::Loc(0, 0, 0)),
                         },
                     );
                     returns
@@ -1844,7 +2471,7 @@ fn try_catch(
                 cfg.add(
                     vartab,
                     Instr::BranchCond {
-                        cond: Expression::Variable(pt::Loc(0, 0), success),
+                        cond: Expression::Variable(pt::Loc(0, 0, 0), success),
                         true_: success_block,
                         false_: catch_block,
                     },
@@ -1921,7 +2548,8 @@ fn try_catch(
             return Err(());
         }
 
-        let mut finally_reachable = statement(&ok, f, cfg, contract_no, ns, vartab, loops, errors)?;
+        let mut finally_reachable =
+            statement(&ok, f, cfg, contract_no, ns, vartab, loops, chain, errors)?;
 
         if finally_reachable {
             cfg.add(vartab, Instr::Branch { bb: finally_block });
@@ -1975,7 +2603,7 @@ fn try_catch(
                         name: "error".to_string(),
                         ty: resolver::Type::String,
                     }],
-                    data: Expression::ReturnData(pt::Loc(0, 0)),
+                    data: Expression::ReturnData(pt::Loc(0, 0, 0)),
                 },
             );
 
@@ -1988,7 +2616,7 @@ fn try_catch(
                         vartab,
                         Instr::Set {
                             res: pos,
-                            expr: Expression::Variable(pt::Loc(0, 0), error_var),
+                            expr: Expression::Variable(pt::Loc(0, 0, 0), error_var),
                         },
                     );
                 }
@@ -2002,6 +2630,7 @@ fn try_catch(
                 ns,
                 vartab,
                 loops,
+                chain,
                 errors,
             )?;
 
@@ -2045,7 +2674,7 @@ fn try_catch(
                     vartab,
                     Instr::Set {
                         res: pos,
-                        expr: Expression::ReturnData(pt::Loc(0, 0)),
+                        expr: Expression::ReturnData(pt::Loc(0, 0, 0)),
                     },
                 );
             }
@@ -2059,6 +2688,7 @@ fn try_catch(
             ns,
             vartab,
             loops,
+            chain,
             errors,
         )?;
 
@@ -2103,6 +2733,7 @@ pub struct Variable {
     pub storage: Storage,
 }
 
+#[derive(Clone)]
 struct VarScope(HashMap<String, usize>, Option<HashSet<usize>>);
 
 #[derive(Default)]
@@ -2112,6 +2743,8 @@ pub struct Vartable {
     storage_vars: HashMap<String, usize>,
     dirty: Vec<DirtyTracker>,
     returns: Vec<usize>,
+    /// The base contract the function was inherited from; needed for resolving `super`
+    pub base_contract: Option<usize>,
 }
 
 pub struct DirtyTracker {
@@ -2129,6 +2762,7 @@ impl Vartable {
             storage_vars: HashMap::new(),
             dirty: Vec::new(),
             returns: Vec::new(),
+            base_contract: None,
         }
     }
 
@@ -2218,7 +2852,7 @@ impl Vartable {
         self.vars.push(Variable {
             id: pt::Identifier {
                 name: format!("temp.{}", pos),
-                loc: pt::Loc(0, 0),
+                loc: pt::Loc(0, 0, 0),
             },
             ty: ty.clone(),
             pos,
@@ -2252,6 +2886,12 @@ impl Vartable {
         self.names.pop_front();
     }
 
+    /// Replace all the scopes, returning the previous ones. This is used when inlining
+    /// modifiers, which have their own scope.
+    fn swap_scopes(&mut self, names: LinkedList<VarScope>) -> LinkedList<VarScope> {
+        std::mem::replace(&mut self.names, names)
+    }
+
     pub fn drain(self) -> Vec<Variable> {
         self.vars
     }
@@ -2329,22 +2969,22 @@ impl resolver::Type {
     fn default(&self, ns: &resolver::Namespace) -> Expression {
         match self {
             resolver::Type::Uint(b) | resolver::Type::Int(b) => {
-                Expression::NumberLiteral(pt::Loc(0, 0), *b, BigInt::from(0))
+                Expression::NumberLiteral(pt::Loc(0, 0, 0), *b, BigInt::from(0))
             }
-            resolver::Type::Bool => Expression::BoolLiteral(pt::Loc(0, 0), false),
+            resolver::Type::Bool => Expression::BoolLiteral(pt::Loc(0, 0, 0), false),
             resolver::Type::Address(_) => Expression::NumberLiteral(
-                pt::Loc(0, 0),
+                pt::Loc(0, 0, 0),
                 ns.address_length as u16 * 8,
                 BigInt::from(0),
             ),
             resolver::Type::Bytes(n) => {
                 let mut l = Vec::new();
                 l.resize(*n as usize, 0);
-                Expression::BytesLiteral(pt::Loc(0, 0), l)
+                Expression::BytesLiteral(pt::Loc(0, 0, 0), l)
             }
             resolver::Type::Enum(e) => ns.enums[*e].ty.default(ns),
             resolver::Type::Struct(_) => {
-                Expression::StructLiteral(pt::Loc(0, 0), self.clone(), Vec::new())
+                Expression::StructLiteral(pt::Loc(0, 0, 0), self.clone(), Vec::new())
             }
             resolver::Type::Ref(_) => unreachable!(),
             resolver::Type::StorageRef(_) => unreachable!(),
@@ -0,0 +1,120 @@
+use super::{EventDecl, EventField, Namespace, Symbol};
+use output::Output;
+use parser::pt;
+
+/// Resolve all the event declarations in a contract
+pub fn contract_events(
+    def: &pt::ContractDefinition,
+    contract_no: usize,
+    ns: &mut Namespace,
+    errors: &mut Vec<Output>,
+) -> bool {
+    let mut broken = false;
+
+    for parts in &def.parts {
+        if let pt::ContractPart::EventDefinition(ref e) = parts {
+            if !event_decl(e, contract_no, ns, errors) {
+                broken = true;
+            }
+        }
+    }
+
+    broken
+}
+
+/// Resolve a single event declaration. The event is only added to the contract if all
+/// of its fields are valid.
+fn event_decl(
+    def: &pt::EventDefinition,
+    contract_no: usize,
+    ns: &mut Namespace,
+    errors: &mut Vec<Output>,
+) -> bool {
+    let mut valid = true;
+    let mut fields: Vec<EventField> = Vec::new();
+    let mut indexed_fields = 0;
+
+    for field in &def.fields {
+        let ty = match ns.resolve_type(Some(contract_no), false, &field.ty, errors) {
+            Ok(ty) => ty,
+            Err(()) => {
+                valid = false;
+                continue;
+            }
+        };
+
+        if ty.contains_mapping(ns) {
+            errors.push(Output::error(
+                field.ty.loc(),
+                "mapping type is not permitted as event field".to_string(),
+            ));
+            valid = false;
+        }
+
+        let name = match &field.name {
+            Some(name) => {
+                if let Some(other) = fields.iter().find(|f| f.name == name.name) {
+                    errors.push(Output::error_with_note(
+                        name.loc,
+                        format!(
+                            "event ‘{}’ has duplicate field name ‘{}’",
+                            def.name.name, name.name
+                        ),
+                        other.loc,
+                        format!("location of previous declaration of ‘{}’", other.name),
+                    ));
+                    valid = false;
+                    continue;
+                }
+
+                name.name.to_owned()
+            }
+            None => String::new(),
+        };
+
+        if field.indexed {
+            indexed_fields += 1;
+        }
+
+        fields.push(EventField {
+            loc: match &field.name {
+                Some(name) => name.loc,
+                None => field.ty.loc(),
+            },
+            name,
+            ty,
+            indexed: field.indexed,
+        });
+    }
+
+    // ethereum has four topics; the first one is used for the event signature unless
+    // the event is anonymous
+    let max_indexed_fields = if def.anonymous { 4 } else { 3 };
+
+    if indexed_fields > max_indexed_fields {
+        errors.push(Output::error(
+            def.name.loc,
+            format!(
+                "event ‘{}’ has {} indexed fields where {} is the maximum",
+                def.name.name, indexed_fields, max_indexed_fields
+            ),
+        ));
+        valid = false;
+    }
+
+    if !valid {
+        return false;
+    }
+
+    let decl = EventDecl::new(def, fields, ns);
+    let pos = ns.contracts[contract_no].events.len();
+
+    ns.contracts[contract_no].events.push(decl);
+
+    ns.add_symbol(
+        Some(contract_no),
+        &def.name,
+        Symbol::Event(def.name.loc, pos),
+        errors,
+    )
+}
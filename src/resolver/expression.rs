@@ -112,6 +112,9 @@ pub enum Expression {
     ReturnData(Loc),
     GetAddress(Loc),
     Balance(Loc, Box<Expression>),
+    Sender(Loc),
+    Value(Loc),
+    Timestamp(Loc),
     Poison,
     Unreachable,
 }
@@ -186,6 +189,9 @@ impl Expression {
             | Expression::Constructor { loc, .. }
             | Expression::GetAddress(loc)
             | Expression::Balance(loc, _)
+            | Expression::Sender(loc)
+            | Expression::Value(loc)
+            | Expression::Timestamp(loc)
             | Expression::And(loc, _, _) => *loc,
             Expression::Poison | Expression::Unreachable => unreachable!(),
         }
@@ -311,11 +317,116 @@ impl Expression {
             Expression::ReturnData(_) => false,
             Expression::GetAddress(_) => false,
             Expression::Balance(_, s) => s.reads_contract_storage(),
+            Expression::Sender(_) => false,
+            Expression::Value(_) => false,
+            Expression::Timestamp(_) => false,
             Expression::Poison => false,
             Expression::Unreachable => false,
         }
     }
 
+    /// Returns true if the Expression reads the given local variable
+    pub fn reads_variable(&self, var: usize) -> bool {
+        match self {
+            Expression::Variable(_, pos) => *pos == var,
+            Expression::FunctionArg(_, _)
+            | Expression::BoolLiteral(_, _)
+            | Expression::BytesLiteral(_, _)
+            | Expression::CodeLiteral(_, _, _)
+            | Expression::NumberLiteral(_, _, _)
+            | Expression::ConstArrayLiteral(_, _, _)
+            | Expression::ReturnData(_)
+            | Expression::GetAddress(_)
+            | Expression::Sender(_)
+            | Expression::Value(_)
+            | Expression::Timestamp(_)
+            | Expression::Poison
+            | Expression::Unreachable => false,
+            Expression::StructLiteral(_, _, exprs)
+            | Expression::ArrayLiteral(_, _, _, exprs)
+            | Expression::LocalFunctionCall(_, _, exprs) => {
+                exprs.iter().any(|e| e.reads_variable(var))
+            }
+            Expression::Add(_, l, r)
+            | Expression::Subtract(_, l, r)
+            | Expression::Multiply(_, l, r)
+            | Expression::UDivide(_, l, r)
+            | Expression::SDivide(_, l, r)
+            | Expression::UModulo(_, l, r)
+            | Expression::SModulo(_, l, r)
+            | Expression::Power(_, l, r)
+            | Expression::BitwiseOr(_, l, r)
+            | Expression::BitwiseAnd(_, l, r)
+            | Expression::BitwiseXor(_, l, r)
+            | Expression::ShiftLeft(_, l, r)
+            | Expression::ShiftRight(_, l, r, _)
+            | Expression::UMore(_, l, r)
+            | Expression::ULess(_, l, r)
+            | Expression::UMoreEqual(_, l, r)
+            | Expression::ULessEqual(_, l, r)
+            | Expression::SMore(_, l, r)
+            | Expression::SLess(_, l, r)
+            | Expression::SMoreEqual(_, l, r)
+            | Expression::SLessEqual(_, l, r)
+            | Expression::Equal(_, l, r)
+            | Expression::NotEqual(_, l, r)
+            | Expression::ArraySubscript(_, l, r)
+            | Expression::DynamicArraySubscript(_, l, _, r)
+            | Expression::StorageBytesSubscript(_, l, r)
+            | Expression::StorageBytesPush(_, l, r)
+            | Expression::And(_, l, r)
+            | Expression::Or(_, l, r) => l.reads_variable(var) || r.reads_variable(var),
+            Expression::Load(_, e)
+            | Expression::StorageLoad(_, _, e)
+            | Expression::ZeroExt(_, _, e)
+            | Expression::SignExt(_, _, e)
+            | Expression::Trunc(_, _, e)
+            | Expression::Not(_, e)
+            | Expression::Complement(_, e)
+            | Expression::UnaryMinus(_, e)
+            | Expression::StructMember(_, e, _)
+            | Expression::AllocDynamicArray(_, _, e, _)
+            | Expression::DynamicArrayLength(_, e)
+            | Expression::StorageBytesPop(_, e)
+            | Expression::StorageBytesLength(_, e)
+            | Expression::Balance(_, e) => e.reads_variable(var),
+            Expression::Ternary(_, c, l, r) => {
+                c.reads_variable(var) || l.reads_variable(var) || r.reads_variable(var)
+            }
+            Expression::ExternalFunctionCall {
+                address,
+                args,
+                value,
+                gas,
+                ..
+            } => {
+                address.reads_variable(var)
+                    || args.iter().any(|a| a.reads_variable(var))
+                    || value.reads_variable(var)
+                    || gas.reads_variable(var)
+            }
+            Expression::Constructor {
+                args,
+                gas,
+                value,
+                salt,
+                ..
+            } => {
+                args.iter().any(|a| a.reads_variable(var))
+                    || gas.reads_variable(var)
+                    || value.iter().any(|e| e.reads_variable(var))
+                    || salt.iter().any(|e| e.reads_variable(var))
+            }
+            Expression::Keccak256(_, exprs) => exprs.iter().any(|e| e.0.reads_variable(var)),
+            Expression::StringConcat(_, l, r) | Expression::StringCompare(_, l, r) => {
+                [l, r].iter().any(|s| match s {
+                    StringLocation::RunTime(e) => e.reads_variable(var),
+                    StringLocation::CompileTime(_) => false,
+                })
+            }
+        }
+    }
+
     /// Is this expression 0
     fn const_zero(&self) -> bool {
         let mut nullsink = Vec::new();
@@ -329,7 +440,12 @@ impl Expression {
 }
 
 /// Unescape a string literal
-fn unescape(literal: &str, start: usize, errors: &mut Vec<output::Output>) -> String {
+fn unescape(
+    literal: &str,
+    start: usize,
+    file_no: usize,
+    errors: &mut Vec<output::Output>,
+) -> String {
     let mut s = String::new();
     let mut indeces = literal.char_indices();
 
@@ -355,14 +471,18 @@ fn unescape(literal: &str, start: usize, errors: &mut Vec<output::Output>) -> St
                     Some(ch) => s.push(ch),
                     None => {
                         errors.push(Output::error(
-                            pt::Loc(start + i, start + i + 4),
+                            pt::Loc(file_no, start + i, start + i + 4),
                             format!("\\x{:02x} is not a valid unicode character", ch),
                         ));
                     }
                 },
                 Err(offset) => {
                     errors.push(Output::error(
-                        pt::Loc(start + i, start + std::cmp::min(literal.len(), offset)),
+                        pt::Loc(
+                            file_no,
+                            start + i,
+                            start + std::cmp::min(literal.len(), offset),
+                        ),
                         "\\x escape should be followed by two hex digits".to_string(),
                     ));
                 }
@@ -372,21 +492,25 @@ fn unescape(literal: &str, start: usize, errors: &mut Vec<output::Output>) -> St
                     Some(ch) => s.push(ch),
                     None => {
                         errors.push(Output::error(
-                            pt::Loc(start + i, start + i + 6),
+                            pt::Loc(file_no, start + i, start + i + 6),
                             format!("\\u{:04x} is not a valid unicode character", ch),
                         ));
                     }
                 },
                 Err(offset) => {
                     errors.push(Output::error(
-                        pt::Loc(start + i, start + std::cmp::min(literal.len(), offset)),
+                        pt::Loc(
+                            file_no,
+                            start + i,
+                            start + std::cmp::min(literal.len(), offset),
+                        ),
                         "\\u escape should be followed by four hex digits".to_string(),
                     ));
                 }
             },
             Some((i, ch)) => {
                 errors.push(Output::error(
-                    pt::Loc(start + i, start + i + ch.len_utf8()),
+                    pt::Loc(file_no, start + i, start + i + ch.len_utf8()),
                     format!("unknown escape character '{}'", ch),
                 ));
             }
@@ -446,6 +570,11 @@ fn coerce(
         return Ok(l.clone());
     }
 
+    // a payable address can be compared with an address which is not
+    if let (resolver::Type::Address(_), resolver::Type::Address(_)) = (l, r) {
+        return Ok(resolver::Type::Address(false));
+    }
+
     coerce_int(l, l_loc, r, r_loc, true, ns, errors)
 }
 
@@ -1112,11 +1241,11 @@ pub fn expression(
         pt::Expression::StringLiteral(v) => {
             // Concatenate the strings
             let mut result = Vec::new();
-            let mut loc = pt::Loc(v[0].loc.0, 0);
+            let mut loc = pt::Loc(v[0].loc.0, v[0].loc.1, 0);
 
             for s in v {
-                result.extend_from_slice(unescape(&s.string, s.loc.0, errors).as_bytes());
-                loc.1 = s.loc.1;
+                result.extend_from_slice(unescape(&s.string, s.loc.1, s.loc.0, errors).as_bytes());
+                loc.2 = s.loc.2;
             }
 
             let length = result.len();
@@ -1128,7 +1257,7 @@ pub fn expression(
         }
         pt::Expression::HexLiteral(v) => {
             let mut result = Vec::new();
-            let mut loc = pt::Loc(0, 0);
+            let mut loc = pt::Loc(v[0].loc.0, v[0].loc.1, 0);
 
             for s in v {
                 if (s.hex.len() % 2) != 0 {
@@ -1139,10 +1268,7 @@ pub fn expression(
                     return Err(());
                 } else {
                     result.extend_from_slice(&hex::decode(&s.hex).unwrap());
-                    if loc.0 == 0 {
-                        loc.0 = s.loc.0;
-                    }
-                    loc.1 = s.loc.1;
+                    loc.2 = s.loc.2;
                 }
             }
 
@@ -1191,6 +1317,12 @@ pub fn expression(
                 bigint_to_expression(loc, &BigInt::from_str_radix(&s, 16).unwrap(), errors)
             }
         }
+        // `now` is an alias for `block.timestamp`
+        pt::Expression::Variable(id)
+            if id.name == "now" && ns.target == crate::Target::Casperlabs =>
+        {
+            Ok((Expression::Timestamp(id.loc), resolver::Type::Uint(64)))
+        }
         pt::Expression::Variable(id) => {
             if let Some(ref mut tab) = *vartab {
                 let v = tab.find(id, contract_no.unwrap(), ns, errors)?;
@@ -2565,7 +2697,7 @@ pub fn constructor_named_args(
             };
 
             match cast(
-                &pt::Loc(0, 0),
+                &pt::Loc(0, 0, 0),
                 arg.0.clone(),
                 &arg.1,
                 &param.ty,
@@ -3740,6 +3872,76 @@ fn member_access(
         }
     }
 
+    // is of the form "msg.sender"
+    if let pt::Expression::Variable(namespace) = e {
+        if namespace.name == "msg" && id.name == "sender" {
+            if ns.target != crate::Target::Casperlabs {
+                errors.push(Output::error(
+                    *loc,
+                    format!("‘msg.sender’ is not supported on target {}", ns.target),
+                ));
+                return Err(());
+            }
+
+            return Ok((Expression::Sender(*loc), resolver::Type::Address(true)));
+        }
+
+        if namespace.name == "msg" && id.name == "value" {
+            if ns.target != crate::Target::Casperlabs {
+                errors.push(Output::error(
+                    *loc,
+                    format!("‘msg.value’ is not supported on target {}", ns.target),
+                ));
+                return Err(());
+            }
+
+            cfg.reads_value = true;
+
+            return Ok((
+                Expression::Value(*loc),
+                resolver::Type::Uint(ns.value_length as u16 * 8),
+            ));
+        }
+
+        if namespace.name == "block" && id.name == "timestamp" {
+            if ns.target != crate::Target::Casperlabs {
+                errors.push(Output::error(
+                    *loc,
+                    format!("‘block.timestamp’ is not supported on target {}", ns.target),
+                ));
+                return Err(());
+            }
+
+            return Ok((Expression::Timestamp(*loc), resolver::Type::Uint(64)));
+        }
+
+        // chain globals which no target provides; the account which signed the
+        // deploy is not known to a contract on CasperLabs
+        let unsupported = [
+            ("block", "coinbase"),
+            ("block", "difficulty"),
+            ("block", "gaslimit"),
+            ("block", "number"),
+            ("block", "chainid"),
+            ("tx", "gasprice"),
+            ("tx", "origin"),
+            ("msg", "data"),
+            ("msg", "sig"),
+            ("msg", "gas"),
+        ];
+
+        if unsupported.contains(&(namespace.name.as_str(), id.name.as_str())) {
+            errors.push(Output::error(
+                *loc,
+                format!(
+                    "‘{}.{}’ is not supported on target {}",
+                    namespace.name, id.name, ns.target
+                ),
+            ));
+            return Err(());
+        }
+    }
+
     let (expr, expr_ty) = expression(e, cfg, contract_no, ns, vartab, errors)?;
 
     // Dereference if need to. This could be struct-in-struct for
@@ -4273,6 +4475,21 @@ fn function_call_pos_args(
     // Try to resolve as a function call
     let funcs = ns.resolve_func(contract_no.unwrap(), &id, errors)?;
 
+    internal_function_call(loc, funcs, args, cfg, contract_no, ns, vartab, errors)
+}
+
+/// Resolve a call to one of the given functions of the contract, picking the overloaded
+/// function which matches the arguments
+fn internal_function_call(
+    loc: &pt::Loc,
+    funcs: &[(pt::Loc, usize)],
+    args: &[pt::Expression],
+    cfg: &mut ControlFlowGraph,
+    contract_no: Option<usize>,
+    ns: &resolver::Namespace,
+    vartab: &mut Option<&mut Vartable>,
+    errors: &mut Vec<output::Output>,
+) -> Result<(Expression, resolver::Type), ()> {
     let mut resolved_args = Vec::new();
     let mut resolved_types = Vec::new();
 
@@ -4309,7 +4526,7 @@ fn function_call_pos_args(
             let arg = &resolved_args[i];
 
             match cast(
-                &pt::Loc(0, 0),
+                &pt::Loc(0, 0, 0),
                 *arg.clone(),
                 &resolved_types[i],
                 &param.ty,
@@ -4414,7 +4631,7 @@ fn function_call_with_named_args(
             };
 
             match cast(
-                &pt::Loc(0, 0),
+                &pt::Loc(0, 0, 0),
                 arg.0.clone(),
                 &arg.1,
                 &param.ty,
@@ -4641,7 +4858,7 @@ fn method_call_pos_args(
             for (i, param) in ftype.params.iter().enumerate() {
                 let arg = &resolved_args[i];
                 match cast(
-                    &pt::Loc(0, 0),
+                    &pt::Loc(0, 0, 0),
                     *arg.clone(),
                     &resolved_types[i],
                     &param.ty,
@@ -4680,7 +4897,7 @@ fn method_call_pos_args(
                     value
                 } else {
                     Box::new(Expression::NumberLiteral(
-                        pt::Loc(0, 0),
+                        pt::Loc(0, 0, 0),
                         ns.value_length as u16 * 8,
                         BigInt::zero(),
                     ))
@@ -4766,7 +4983,7 @@ fn method_call_pos_args(
             } else {
                 Some(tab.temp(
                     &pt::Identifier {
-                        loc: pt::Loc(0, 0),
+                        loc: pt::Loc(0, 0, 0),
                         name: "success".to_owned(),
                     },
                     &resolver::Type::Bool,
@@ -4888,7 +5105,7 @@ fn method_call_with_named_args(
                     }
                 };
                 match cast(
-                    &pt::Loc(0, 0),
+                    &pt::Loc(0, 0, 0),
                     arg.0.clone(),
                     &arg.1,
                     &param.ty,
@@ -4928,7 +5145,7 @@ fn method_call_with_named_args(
                     value
                 } else {
                     Box::new(Expression::NumberLiteral(
-                        pt::Loc(0, 0),
+                        pt::Loc(0, 0, 0),
                         ns.value_length as u16 * 8,
                         BigInt::zero(),
                     ))
@@ -5202,7 +5419,7 @@ pub fn collect_call_args<'a>(
         match block.as_ref() {
             pt::Statement::Args(_, args) => {
                 if let Some(l) = loc {
-                    loc = Some(pt::Loc(l.0, block.loc().1));
+                    loc = Some(pt::Loc(l.0, l.1, block.loc().2));
                 } else {
                     loc = Some(block.loc());
                 }
@@ -5266,7 +5483,11 @@ fn parse_call_args(
     }
 
     let mut res = CallArgs {
-        gas: Box::new(Expression::NumberLiteral(pt::Loc(0, 0), 64, BigInt::zero())),
+        gas: Box::new(Expression::NumberLiteral(
+            pt::Loc(0, 0, 0),
+            64,
+            BigInt::zero(),
+        )),
         value: None,
         salt: None,
     };
@@ -5353,6 +5574,17 @@ pub fn function_call_expr(
     let (ty, call_args, call_args_loc) = collect_call_args(ty, errors)?;
 
     match ty {
+        pt::Expression::MemberAccess(_, member, func) if is_super(member) => {
+            if let Some(loc) = call_args_loc {
+                errors.push(Output::error(
+                    loc,
+                    "call arguments not permitted for internal calls".to_string(),
+                ));
+                return Err(());
+            }
+
+            super_function_call(loc, func, args, cfg, contract_no, ns, vartab, errors)
+        }
         pt::Expression::MemberAccess(_, member, func) => method_call_pos_args(
             loc,
             member,
@@ -5391,6 +5623,80 @@ pub fn function_call_expr(
     }
 }
 
+fn is_super(expr: &pt::Expression) -> bool {
+    match expr {
+        pt::Expression::Variable(id) => id.name == "super",
+        _ => false,
+    }
+}
+
+/// Resolve a function call through `super`. This calls the function which the current
+/// function overrides, i.e. the next one in the linearization of the base contracts.
+fn super_function_call(
+    loc: &pt::Loc,
+    func: &pt::Identifier,
+    args: &[pt::Expression],
+    cfg: &mut ControlFlowGraph,
+    contract_no: Option<usize>,
+    ns: &resolver::Namespace,
+    vartab: &mut Option<&mut Vartable>,
+    errors: &mut Vec<output::Output>,
+) -> Result<(Expression, resolver::Type), ()> {
+    let contract = match (contract_no, &vartab) {
+        (Some(contract_no), Some(_)) => &ns.contracts[contract_no],
+        _ => {
+            errors.push(Output::error(
+                *loc,
+                "‘super’ is only allowed in functions".to_string(),
+            ));
+            return Err(());
+        }
+    };
+
+    // position in the linearization; the contract itself comes first
+    let level = |base_contract: Option<usize>| match base_contract {
+        None => 0,
+        Some(base_no) => 1 + contract.bases.iter().position(|b| *b == base_no).unwrap(),
+    };
+
+    let current = level(vartab.as_ref().unwrap().base_contract);
+
+    let mut candidates: Vec<usize> = (0..contract.functions.len())
+        .filter(|n| {
+            let f = &contract.functions[*n];
+
+            f.ty == pt::FunctionTy::Function
+                && f.name == func.name
+                && level(f.base_contract) > current
+        })
+        .collect();
+
+    candidates.sort_by_key(|n| level(contract.functions[*n].base_contract));
+
+    let mut funcs: Vec<(pt::Loc, usize)> = Vec::new();
+
+    for n in candidates {
+        let signature = &contract.functions[n].signature;
+
+        if !funcs
+            .iter()
+            .any(|(_, f)| contract.functions[*f].signature == *signature)
+        {
+            funcs.push((func.loc, n));
+        }
+    }
+
+    if funcs.is_empty() {
+        errors.push(Output::error(
+            func.loc,
+            format!("function ‘{}’ not found in base contracts", func.name),
+        ));
+        return Err(());
+    }
+
+    internal_function_call(loc, &funcs, args, cfg, contract_no, ns, vartab, errors)
+}
+
 /// Resolve function call expression with named arguments
 pub fn named_function_call_expr(
     loc: &pt::Loc,
@@ -5466,7 +5772,7 @@ fn emit_function_call(
 
                 for ret in &ftype.returns {
                     let id = pt::Identifier {
-                        loc: pt::Loc(0, 0),
+                        loc: pt::Loc(0, 0, 0),
                         name: ret.name.to_owned(),
                     };
 
@@ -5528,7 +5834,7 @@ fn emit_function_call(
 
                 for ret in &ftype.returns {
                     let id = pt::Identifier {
-                        loc: pt::Loc(0, 0),
+                        loc: pt::Loc(0, 0, 0),
                         name: "".to_owned(),
                     };
                     let temp_pos = tab.temp(&id, &ret.ty);
@@ -7,6 +7,7 @@ pub fn function_decl(
     f: &pt::FunctionDefinition,
     i: usize,
     contract_no: usize,
+    base_contract: Option<usize>,
     ns: &mut Namespace,
     errors: &mut Vec<Output>,
 ) -> bool {
@@ -72,10 +73,13 @@ pub fn function_decl(
                 return false;
             }
         }
+        pt::FunctionTy::Modifier => unreachable!(),
     }
 
     let mut mutability: Option<pt::StateMutability> = None;
     let mut visibility: Option<pt::Visibility> = None;
+    let mut is_virtual: Option<pt::Loc> = None;
+    let mut is_override: Option<pt::Loc> = None;
 
     for a in &f.attributes {
         match &a {
@@ -107,6 +111,60 @@ pub fn function_decl(
 
                 visibility = Some(v.clone());
             }
+            pt::FunctionAttribute::BaseOrModifier(base) => {
+                // constructors can pass arguments to the constructors of base contracts
+                if f.ty == pt::FunctionTy::Constructor
+                    && is_base_contract(contract_no, &base.name, ns)
+                {
+                    continue;
+                }
+
+                if ns
+                    .resolve_modifier(contract_no, &base.name, errors)
+                    .is_err()
+                {
+                    success = false;
+                }
+            }
+            pt::FunctionAttribute::Virtual(loc) => {
+                if let Some(prev) = is_virtual {
+                    errors.push(Output::error_with_note(
+                        *loc,
+                        "function redeclared `virtual'".to_string(),
+                        prev,
+                        "location of previous declaration of `virtual'".to_string(),
+                    ));
+                    success = false;
+                }
+
+                is_virtual = Some(*loc);
+            }
+            pt::FunctionAttribute::Override(loc, bases) => {
+                if let Some(prev) = is_override {
+                    errors.push(Output::error_with_note(
+                        *loc,
+                        "function redeclared `override'".to_string(),
+                        prev,
+                        "location of previous declaration of `override'".to_string(),
+                    ));
+                    success = false;
+                }
+
+                for base in bases {
+                    if !is_base_contract(contract_no, base, ns) {
+                        errors.push(Output::error(
+                            base.loc,
+                            format!(
+                                "override ‘{}’ is not a base contract of ‘{}’",
+                                base.name, ns.contracts[contract_no].name
+                            ),
+                        ));
+                        success = false;
+                    }
+                }
+
+                is_override = Some(*loc);
+            }
         }
     }
 
@@ -116,7 +174,7 @@ pub fn function_decl(
             errors.push(Output::error(f.loc, "no visibility specified".to_string()));
             success = false;
             // continue processing while assuming it's a public
-            pt::Visibility::Public(pt::Loc(0, 0))
+            pt::Visibility::Public(pt::Loc(0, 0, 0))
         }
     };
 
@@ -149,7 +207,7 @@ pub fn function_decl(
         None => "".to_owned(),
     };
 
-    let fdecl = FunctionDecl::new(
+    let mut fdecl = FunctionDecl::new(
         f.loc,
         name,
         f.doc.clone(),
@@ -162,9 +220,13 @@ pub fn function_decl(
         ns,
     );
 
+    fdecl.base_contract = base_contract;
+    fdecl.is_virtual = is_virtual.is_some();
+
     if f.ty == pt::FunctionTy::Constructor {
-        // In the eth solidity, only one constructor is allowed
-        if ns.target == Target::Ewasm {
+        // In the eth solidity, only one constructor is allowed. The casperlabs contract
+        // has a single constructor entry point, so the same goes there.
+        if ns.target == Target::Ewasm || ns.target == Target::Casperlabs {
             if let Some(prev) = ns.contracts[contract_no]
                 .functions
                 .iter()
@@ -297,8 +359,14 @@ pub fn function_decl(
             ns.symbols.get_mut(&(Some(contract_no), id.name.to_owned()))
         {
             // check if signature already present
-            for o in v.iter() {
-                if ns.contracts[contract_no].functions[o.1].signature == fdecl.signature {
+            for o in v.iter_mut() {
+                let prev = &ns.contracts[contract_no].functions[o.1];
+
+                if prev.signature != fdecl.signature {
+                    continue;
+                }
+
+                if prev.base_contract == base_contract {
                     errors.push(Output::error_with_note(
                         f.loc,
                         "overloaded function with this signature already exist".to_string(),
@@ -307,6 +375,64 @@ pub fn function_decl(
                     ));
                     return false;
                 }
+
+                // the function is declared in a base contract, so it is overridden
+                if !prev.is_virtual {
+                    errors.push(Output::error_with_note(
+                        f.loc,
+                        format!(
+                            "function ‘{}’ overrides function which is not virtual",
+                            id.name
+                        ),
+                        o.0,
+                        "location of previous definition".to_string(),
+                    ));
+                    return false;
+                }
+
+                if is_override.is_none() {
+                    errors.push(Output::error_with_note(
+                        f.loc,
+                        format!("function ‘{}’ should specify ‘override’", id.name),
+                        o.0,
+                        "location of previous definition".to_string(),
+                    ));
+                    return false;
+                }
+
+                if prev
+                    .returns
+                    .iter()
+                    .map(|p| &p.ty)
+                    .ne(fdecl.returns.iter().map(|p| &p.ty))
+                {
+                    errors.push(Output::error_with_note(
+                        f.loc,
+                        format!(
+                            "function ‘{}’ overrides function with different return types",
+                            id.name
+                        ),
+                        o.0,
+                        "location of previous definition".to_string(),
+                    ));
+                    return false;
+                }
+
+                let pos = ns.contracts[contract_no].functions.len();
+
+                ns.contracts[contract_no].functions[o.1].overridden = true;
+                ns.contracts[contract_no].functions.push(fdecl);
+
+                *o = (f.loc, pos);
+                return true;
+            }
+
+            if let Some(loc) = is_override {
+                errors.push(Output::error(
+                    loc,
+                    format!("function ‘{}’ does not override anything", id.name),
+                ));
+                return false;
             }
 
             let pos = ns.contracts[contract_no].functions.len();
@@ -317,6 +443,14 @@ pub fn function_decl(
             return true;
         }
 
+        if let Some(loc) = is_override {
+            errors.push(Output::error(
+                loc,
+                format!("function ‘{}’ does not override anything", id.name),
+            ));
+            return false;
+        }
+
         let pos = ns.contracts[contract_no].functions.len();
 
         ns.contracts[contract_no].functions.push(fdecl);
@@ -332,6 +466,96 @@ pub fn function_decl(
     }
 }
 
+/// Resolve a modifier declaration. Modifiers cannot be called; their bodies are inlined
+/// into the functions which use them when the cfg is generated.
+pub fn modifier_decl(
+    f: &pt::FunctionDefinition,
+    i: usize,
+    contract_no: usize,
+    base_contract: Option<usize>,
+    ns: &mut Namespace,
+    errors: &mut Vec<Output>,
+) -> bool {
+    let mut success = true;
+
+    for a in &f.attributes {
+        match &a {
+            pt::FunctionAttribute::StateMutability(m) => {
+                errors.push(Output::error(
+                    m.loc(),
+                    format!("modifier cannot be declared `{}'", m.to_string()),
+                ));
+                success = false;
+            }
+            pt::FunctionAttribute::Visibility(v) => {
+                errors.push(Output::error(
+                    v.loc(),
+                    format!("modifier cannot be declared `{}'", v.to_string()),
+                ));
+                success = false;
+            }
+            pt::FunctionAttribute::BaseOrModifier(base) => {
+                errors.push(Output::error(
+                    base.loc,
+                    "modifier cannot have modifiers".to_string(),
+                ));
+                success = false;
+            }
+            pt::FunctionAttribute::Virtual(loc) => {
+                errors.push(Output::error(
+                    *loc,
+                    "modifier cannot be declared `virtual'".to_string(),
+                ));
+                success = false;
+            }
+            pt::FunctionAttribute::Override(loc, _) => {
+                errors.push(Output::error(
+                    *loc,
+                    "modifier cannot be declared `override'".to_string(),
+                ));
+                success = false;
+            }
+        }
+    }
+
+    let (params, params_success) = resolve_params(f, true, contract_no, ns, errors);
+
+    if !success || !params_success {
+        return false;
+    }
+
+    let id = f.name.as_ref().unwrap();
+
+    let mut mdecl = FunctionDecl::new(
+        f.loc,
+        id.name.to_owned(),
+        f.doc.clone(),
+        f.ty.clone(),
+        Some(i),
+        None,
+        pt::Visibility::Internal(pt::Loc(0, 0, 0)),
+        params,
+        Vec::new(),
+        ns,
+    );
+
+    mdecl.base_contract = base_contract;
+
+    let pos = ns.contracts[contract_no].modifiers.len();
+
+    ns.contracts[contract_no].modifiers.push(mdecl);
+
+    ns.add_symbol(Some(contract_no), id, Symbol::Modifier(id.loc, pos), errors)
+}
+
+/// Is the given name one of the base contracts of the contract
+fn is_base_contract(contract_no: usize, name: &pt::Identifier, ns: &Namespace) -> bool {
+    ns.contracts[contract_no]
+        .bases
+        .iter()
+        .any(|base_no| ns.contracts[*base_no].name == name.name)
+}
+
 /// Resolve the parameters
 fn resolve_params(
     f: &pt::FunctionDefinition,
@@ -499,13 +723,13 @@ fn signatures() {
     let ns = Namespace::new(Target::Ewasm, 20);
 
     let fdecl = FunctionDecl::new(
-        pt::Loc(0, 0),
+        pt::Loc(0, 0, 0),
         "foo".to_owned(),
         vec![],
         pt::FunctionTy::Function,
         Some(0),
         None,
-        pt::Visibility::Public(pt::Loc(0, 0)),
+        pt::Visibility::Public(pt::Loc(0, 0, 0)),
         vec![
             Parameter {
                 name: "".to_string(),
@@ -0,0 +1,219 @@
+use super::{Namespace, Symbol};
+use output::Output;
+use parser::pt;
+
+/// Resolve the base contracts of each contract and linearize the inheritance graph. Base
+/// contracts may be defined after the contracts which inherit from them, so the contracts
+/// are returned in the order in which they should be resolved, each after its bases.
+pub fn resolve_base_contracts(
+    defs: &[&pt::ContractDefinition],
+    ns: &mut Namespace,
+    errors: &mut Vec<Output>,
+) -> Vec<usize> {
+    let mut direct_bases: Vec<Vec<usize>> = Vec::new();
+    let mut valid = vec![true; defs.len()];
+
+    for (contract_no, def) in defs.iter().enumerate() {
+        let mut bases: Vec<usize> = Vec::new();
+
+        for base in &def.base {
+            let base_no = match ns.resolve_contract(&base.name) {
+                Some(no) => no,
+                None => {
+                    errors.push(Output::error(
+                        base.name.loc,
+                        format!("contract ‘{}’ not found", base.name.name),
+                    ));
+                    valid[contract_no] = false;
+                    continue;
+                }
+            };
+
+            if base_no == contract_no {
+                errors.push(Output::error(
+                    base.name.loc,
+                    format!("contract ‘{}’ cannot inherit from itself", base.name.name),
+                ));
+                valid[contract_no] = false;
+            } else if bases.contains(&base_no) {
+                errors.push(Output::error(
+                    base.name.loc,
+                    format!(
+                        "base contract ‘{}’ specified more than once",
+                        base.name.name
+                    ),
+                ));
+                valid[contract_no] = false;
+            } else {
+                bases.push(base_no);
+            }
+        }
+
+        direct_bases.push(bases);
+    }
+
+    let mut order = Vec::new();
+    let mut visiting = vec![false; defs.len()];
+
+    for contract_no in 0..defs.len() {
+        order_contract(
+            contract_no,
+            defs,
+            &direct_bases,
+            &mut visiting,
+            &mut order,
+            errors,
+        );
+    }
+
+    for contract_no in order.iter().cloned() {
+        let def = defs[contract_no];
+        let bases = &direct_bases[contract_no];
+
+        if !valid[contract_no] || bases.is_empty() {
+            continue;
+        }
+
+        match linearize(bases, ns) {
+            Some(linearized) => {
+                ns.contracts[contract_no].bases = linearized;
+            }
+            None => {
+                errors.push(Output::error(
+                    def.name.loc,
+                    format!(
+                        "linearization of inheritance graph impossible for contract ‘{}’",
+                        def.name.name
+                    ),
+                ));
+                continue;
+            }
+        }
+
+        inherit_types(contract_no, ns, errors);
+    }
+
+    order
+}
+
+/// Add the contract to the order after its base contracts, unless it is there already. A
+/// contract which is being visited when it is reached again inherits from itself.
+fn order_contract(
+    contract_no: usize,
+    defs: &[&pt::ContractDefinition],
+    direct_bases: &[Vec<usize>],
+    visiting: &mut [bool],
+    order: &mut Vec<usize>,
+    errors: &mut Vec<Output>,
+) {
+    if order.contains(&contract_no) {
+        return;
+    }
+
+    visiting[contract_no] = true;
+
+    for base_no in &direct_bases[contract_no] {
+        if visiting[*base_no] {
+            let def = defs[contract_no];
+            let base = def
+                .base
+                .iter()
+                .find(|base| base.name.name == defs[*base_no].name.name)
+                .unwrap();
+
+            errors.push(Output::error_with_note(
+                base.name.loc,
+                format!(
+                    "contract ‘{}’ cannot inherit from ‘{}’, which inherits from ‘{}’",
+                    def.name.name, base.name.name, def.name.name
+                ),
+                defs[*base_no].name.loc,
+                format!("location of definition of ‘{}’", base.name.name),
+            ));
+        } else {
+            order_contract(*base_no, defs, direct_bases, visiting, order, errors);
+        }
+    }
+
+    visiting[contract_no] = false;
+    order.push(contract_no);
+}
+
+/// C3 linearization of the base contracts. The bases are listed from "most base-like" to
+/// "most derived", so the last one listed is the first one in the linearization.
+fn linearize(bases: &[usize], ns: &Namespace) -> Option<Vec<usize>> {
+    let mut sequences: Vec<Vec<usize>> = bases
+        .iter()
+        .rev()
+        .map(|base_no| {
+            let mut seq = vec![*base_no];
+
+            seq.extend(ns.contracts[*base_no].bases.iter());
+
+            seq
+        })
+        .collect();
+
+    sequences.push(bases.iter().rev().cloned().collect());
+
+    let mut linearized = Vec::new();
+
+    loop {
+        sequences.retain(|seq| !seq.is_empty());
+
+        if sequences.is_empty() {
+            return Some(linearized);
+        }
+
+        // the next contract is the first head which does not occur in the tail of any sequence
+        let head = sequences
+            .iter()
+            .map(|seq| seq[0])
+            .find(|head| !sequences.iter().any(|seq| seq[1..].contains(head)))?;
+
+        linearized.push(head);
+
+        for seq in &mut sequences {
+            if seq[0] == head {
+                seq.remove(0);
+            }
+        }
+    }
+}
+
+/// The structs and enums declared in base contracts can be used in the derived contract
+fn inherit_types(contract_no: usize, ns: &mut Namespace, errors: &mut Vec<Output>) {
+    let mut inherited = Vec::new();
+
+    for base_no in &ns.contracts[contract_no].bases {
+        let base_name = Some(ns.contracts[*base_no].name.to_owned());
+
+        for ((scope, name), symbol) in &ns.symbols {
+            if *scope != Some(*base_no) {
+                continue;
+            }
+
+            match symbol {
+                Symbol::Struct(loc, n) if ns.structs[*n].contract == base_name => {
+                    inherited.push((*loc, name.to_owned(), Symbol::Struct(*loc, *n)));
+                }
+                Symbol::Enum(loc, n) if ns.enums[*n].contract == base_name => {
+                    inherited.push((*loc, name.to_owned(), Symbol::Enum(*loc, *n)));
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // symbols are stored in a hashmap; report any conflicts in a stable order
+    inherited.sort_by_key(|(loc, _, _)| (loc.0, loc.1));
+
+    for (loc, name, symbol) in inherited {
+        ns.add_symbol(
+            Some(contract_no),
+            &pt::Identifier { loc, name },
+            symbol,
+            errors,
+        );
+    }
+}
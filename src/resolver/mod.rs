@@ -3,7 +3,7 @@ use emit;
 use num_bigint::BigInt;
 use num_traits::Signed;
 use num_traits::{One, Zero};
-use output::{any_errors, Note, Output};
+use output::{any_errors, Level, Note, Output};
 use parser::pt;
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -15,9 +15,13 @@ mod address;
 mod builtin;
 pub mod cfg;
 mod eval;
+mod events;
 pub mod expression;
 mod functions;
+mod inheritance;
+mod rust;
 mod storage;
+mod structure;
 mod types;
 mod variables;
 pub mod casperlabs;
@@ -415,6 +419,45 @@ impl EnumDecl {
     }
 }
 
+pub struct EventField {
+    pub name: String,
+    pub loc: pt::Loc,
+    pub ty: Type,
+    pub indexed: bool,
+}
+
+pub struct EventDecl {
+    pub doc: Vec<String>,
+    pub loc: pt::Loc,
+    pub name: String,
+    pub signature: String,
+    pub fields: Vec<EventField>,
+    pub anonymous: bool,
+}
+
+impl EventDecl {
+    fn new(def: &pt::EventDefinition, fields: Vec<EventField>, ns: &Namespace) -> Self {
+        let signature = format!(
+            "{}({})",
+            def.name.name,
+            fields
+                .iter()
+                .map(|f| f.ty.to_signature_string(ns))
+                .collect::<Vec<String>>()
+                .join(",")
+        );
+
+        EventDecl {
+            doc: def.doc.clone(),
+            loc: def.name.loc,
+            name: def.name.name.to_owned(),
+            signature,
+            fields,
+            anonymous: def.anonymous,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Parameter {
     pub name: String,
@@ -435,6 +478,11 @@ pub struct FunctionDecl {
     pub noreturn: bool,
     pub check_nonpayable: bool,
     pub cfg: Option<Box<cfg::ControlFlowGraph>>,
+    /// The base contract this function was inherited from
+    pub base_contract: Option<usize>,
+    pub is_virtual: bool,
+    /// Overridden functions can only be called through `super`
+    pub overridden: bool,
 }
 
 impl FunctionDecl {
@@ -474,6 +522,9 @@ impl FunctionDecl {
             noreturn: false,
             check_nonpayable: false,
             cfg: None,
+            base_contract: None,
+            is_virtual: false,
+            overridden: false,
         }
     }
 
@@ -484,9 +535,10 @@ impl FunctionDecl {
         u32::from_le_bytes([res[0], res[1], res[2], res[3]])
     }
 
-    /// Is this a constructor
+    /// Is this a constructor. The constructors of base contracts are called from the
+    /// constructor of the derived contract.
     pub fn is_constructor(&self) -> bool {
-        self.ty == pt::FunctionTy::Constructor
+        self.ty == pt::FunctionTy::Constructor && self.base_contract.is_none()
     }
 
     /// Does this function have the payable state
@@ -500,6 +552,12 @@ impl FunctionDecl {
 
     /// Is this function accessable externally
     pub fn is_public(&self) -> bool {
+        if self.overridden
+            || (self.ty == pt::FunctionTy::Constructor && self.base_contract.is_some())
+        {
+            return false;
+        }
+
         match self.visibility {
             pt::Visibility::Public(_) | pt::Visibility::External(_) => true,
             _ => false,
@@ -606,6 +664,8 @@ pub enum Symbol {
     Variable(pt::Loc, usize),
     Struct(pt::Loc, usize),
     Contract(pt::Loc, usize),
+    Event(pt::Loc, usize),
+    Modifier(pt::Loc, usize),
 }
 
 /// When resolving a Solidity file, this holds all the resolved items
@@ -627,7 +687,11 @@ impl Namespace {
             structs: Vec::new(),
             contracts: Vec::new(),
             address_length,
-            value_length: 16,
+            value_length: match target {
+                // CasperLabs motes are U512
+                Target::Casperlabs => 64,
+                _ => 16,
+            },
             symbols: HashMap::new(),
         }
     }
@@ -688,6 +752,22 @@ impl Namespace {
                         "location of previous definition".to_string(),
                     ));
                 }
+                Symbol::Event(c, _) => {
+                    errors.push(Output::error_with_note(
+                        id.loc,
+                        format!("{} is already defined as an event", id.name.to_string()),
+                        *c,
+                        "location of previous definition".to_string(),
+                    ));
+                }
+                Symbol::Modifier(c, _) => {
+                    errors.push(Output::error_with_note(
+                        id.loc,
+                        format!("{} is already defined as a modifier", id.name.to_string()),
+                        *c,
+                        "location of previous definition".to_string(),
+                    ));
+                }
             }
 
             return false;
@@ -743,6 +823,22 @@ impl Namespace {
                             "location of previous definition".to_string(),
                         ));
                     }
+                    Symbol::Event(c, _) => {
+                        errors.push(Output::warning_with_note(
+                            id.loc,
+                            format!("{} is already defined as an event", id.name.to_string()),
+                            *c,
+                            "location of previous definition".to_string(),
+                        ));
+                    }
+                    Symbol::Modifier(c, _) => {
+                        errors.push(Output::warning_with_note(
+                            id.loc,
+                            format!("{} is already defined as a modifier", id.name.to_string()),
+                            *c,
+                            "location of previous definition".to_string(),
+                        ));
+                    }
                 }
             }
         }
@@ -775,6 +871,44 @@ impl Namespace {
         None
     }
 
+    pub fn resolve_event(
+        &self,
+        contract_no: usize,
+        id: &pt::Identifier,
+        errors: &mut Vec<Output>,
+    ) -> Result<usize, ()> {
+        match self.symbols.get(&(Some(contract_no), id.name.to_owned())) {
+            Some(Symbol::Event(_, n)) => Ok(*n),
+            _ => {
+                errors.push(Output::error(
+                    id.loc,
+                    format!("event ‘{}’ not found", id.name),
+                ));
+
+                Err(())
+            }
+        }
+    }
+
+    pub fn resolve_modifier(
+        &self,
+        contract_no: usize,
+        id: &pt::Identifier,
+        errors: &mut Vec<Output>,
+    ) -> Result<usize, ()> {
+        match self.symbols.get(&(Some(contract_no), id.name.to_owned())) {
+            Some(Symbol::Modifier(_, n)) => Ok(*n),
+            _ => {
+                errors.push(Output::error(
+                    id.loc,
+                    format!("unknown modifier ‘{}’", id.name),
+                ));
+
+                Err(())
+            }
+        }
+    }
+
     pub fn resolve_func(
         &self,
         contract_no: usize,
@@ -842,6 +976,20 @@ impl Namespace {
                 ));
                 Err(())
             }
+            Some(Symbol::Event(_, _)) => {
+                errors.push(Output::decl_error(
+                    id.loc,
+                    format!("`{}' is an event", id.name),
+                ));
+                Err(())
+            }
+            Some(Symbol::Modifier(_, _)) => {
+                errors.push(Output::decl_error(
+                    id.loc,
+                    format!("`{}' is a modifier", id.name),
+                ));
+                Err(())
+            }
             Some(Symbol::Variable(_, n)) => Ok(*n),
         }
     }
@@ -905,6 +1053,22 @@ impl Namespace {
                     "previous declaration of contract name".to_string(),
                 ));
             }
+            Some(Symbol::Event(loc, _)) => {
+                errors.push(Output::warning_with_note(
+                    id.loc,
+                    format!("declaration of `{}' shadows event", id.name),
+                    *loc,
+                    "previous declaration of event".to_string(),
+                ));
+            }
+            Some(Symbol::Modifier(loc, _)) => {
+                errors.push(Output::warning_with_note(
+                    id.loc,
+                    format!("declaration of `{}' shadows modifier", id.name),
+                    *loc,
+                    "previous declaration of modifier".to_string(),
+                ));
+            }
             None => {}
         }
     }
@@ -1052,6 +1216,20 @@ impl Namespace {
                     ));
                     return Err(());
                 }
+                Some(Symbol::Event(_, _)) => {
+                    errors.push(Output::decl_error(
+                        id.loc,
+                        format!("‘{}’ is an event", id.name),
+                    ));
+                    return Err(());
+                }
+                Some(Symbol::Modifier(_, _)) => {
+                    errors.push(Output::decl_error(
+                        id.loc,
+                        format!("‘{}’ is a modifier", id.name),
+                    ));
+                    return Err(());
+                }
             }
         } else {
             contract_no
@@ -1101,6 +1279,20 @@ impl Namespace {
                 ));
                 Err(())
             }
+            Some(Symbol::Event(_, _)) => {
+                errors.push(Output::decl_error(
+                    id.loc,
+                    format!("‘{}’ is an event", id.name),
+                ));
+                Err(())
+            }
+            Some(Symbol::Modifier(_, _)) => {
+                errors.push(Output::decl_error(
+                    id.loc,
+                    format!("‘{}’ is a modifier", id.name),
+                ));
+                Err(())
+            }
         }
     }
 
@@ -1184,8 +1376,13 @@ impl Namespace {
 
 pub struct Contract {
     pub doc: Vec<String>,
+    pub loc: pt::Loc,
     pub name: String,
-    // events
+    pub ty: pt::ContractType,
+    /// The base contracts in linearized order, most derived first
+    pub bases: Vec<usize>,
+    pub events: Vec<EventDecl>,
+    pub modifiers: Vec<FunctionDecl>,
     pub functions: Vec<FunctionDecl>,
     pub variables: Vec<ContractVariable>,
     pub constants: Vec<Expression>,
@@ -1195,10 +1392,15 @@ pub struct Contract {
 }
 
 impl Contract {
-    pub fn new(name: &str) -> Self {
+    pub fn new(name: &str, ty: pt::ContractType, loc: pt::Loc) -> Self {
         Contract {
             name: name.to_owned(),
+            loc,
+            ty,
             doc: Vec::new(),
+            bases: Vec::new(),
+            events: Vec::new(),
+            modifiers: Vec::new(),
             functions: Vec::new(),
             variables: Vec::new(),
             constants: Vec::new(),
@@ -1208,6 +1410,16 @@ impl Contract {
         }
     }
 
+    /// The contracts which this contract creates with `new`
+    pub fn creates(&self) -> Vec<usize> {
+        self.creates.borrow().clone()
+    }
+
+    /// Is this an interface, which only declares functions
+    pub fn is_interface(&self) -> bool {
+        self.ty == pt::ContractType::Interface
+    }
+
     /// Return the index of the fallback function, if any
     pub fn fallback_function(&self) -> Option<usize> {
         for (i, f) in self.functions.iter().enumerate() {
@@ -1266,24 +1478,41 @@ pub fn resolver(s: pt::SourceUnit, target: Target) -> (Option<Namespace>, Vec<Ou
         return (None, errors);
     }
 
+    // the contract definitions, in the same order as the contracts in the namespace
+    let defs: Vec<&pt::ContractDefinition> =
+        s.0.iter()
+            .filter_map(|part| match part {
+                pt::SourceUnitPart::ContractDefinition(def) => Some(def.as_ref()),
+                _ => None,
+            })
+            .collect();
+
+    // the base contracts have to be known before we can resolve any declarations, and
+    // each contract is resolved after its base contracts
+    let order = inheritance::resolve_base_contracts(&defs, &mut ns, &mut errors);
+
+    // give up if we failed
+    if any_errors(&errors) {
+        return (None, errors);
+    }
+
+    // A contract is not resolved if any of its base contracts failed to resolve, since
+    // the errors would be reported again
+    let mut broken = vec![false; defs.len()];
+
     // we need to resolve declarations first, so we call functions/constructors of
     // contracts before they are declared
-    let mut contract_no = 0;
-    for part in &s.0 {
-        if let pt::SourceUnitPart::ContractDefinition(def) = part {
-            resolve_contract_declarations(def, contract_no, target, &mut errors, &mut ns);
-
-            contract_no += 1;
-        }
+    for contract_no in order.iter().cloned() {
+        broken[contract_no] = ns.contracts[contract_no].bases.iter().any(|b| broken[*b])
+            || resolve_contract_declarations(&defs, contract_no, target, &mut errors, &mut ns);
     }
 
     // Now we can resolve the bodies
-    let mut contract_no = 0;
-    for part in &s.0 {
-        if let pt::SourceUnitPart::ContractDefinition(def) = part {
-            resolve_contract_bodies(def, contract_no, &mut errors, &mut ns);
-
-            contract_no += 1;
+    for contract_no in order.iter().cloned() {
+        if ns.contracts[contract_no].bases.iter().any(|b| broken[*b])
+            || resolve_contract_bodies(&defs, contract_no, &mut errors, &mut ns)
+        {
+            broken[contract_no] = true;
         }
     }
 
@@ -1294,14 +1523,44 @@ pub fn resolver(s: pt::SourceUnit, target: Target) -> (Option<Namespace>, Vec<Ou
     }
 }
 
+/// The base contracts of a contract followed by the contract itself, most base contract
+/// first. The declarations of the base contracts are resolved again as part of the derived
+/// contract, so that storage layout and function numbering is per contract.
+fn contract_layers(contract_no: usize, ns: &Namespace) -> Vec<Option<usize>> {
+    ns.contracts[contract_no]
+        .bases
+        .iter()
+        .rev()
+        .map(|b| Some(*b))
+        .chain(std::iter::once(None))
+        .collect()
+}
+
+/// Any warnings for declarations inherited from a base contract have already been reported
+/// when the base contract itself was resolved. Only errors can be new, e.g. conflicting
+/// declarations in two base contracts.
+fn inherited_errors(
+    base_contract: Option<usize>,
+    mut layer_errors: Vec<Output>,
+    errors: &mut Vec<Output>,
+) {
+    if base_contract.is_some() {
+        layer_errors.retain(|e| e.level == Level::Error);
+    }
+
+    errors.append(&mut layer_errors);
+}
+
 /// Resolve functions declarations, constructor declarations, and contract variables
 fn resolve_contract_declarations(
-    def: &pt::ContractDefinition,
+    defs: &[&pt::ContractDefinition],
     contract_no: usize,
     target: Target,
     errors: &mut Vec<Output>,
     ns: &mut Namespace,
 ) -> bool {
+    let def = defs[contract_no];
+
     errors.push(Output::info(
         def.loc,
         format!("found contract {}", def.name.name),
@@ -1311,48 +1570,132 @@ fn resolve_contract_declarations(
 
     let mut broken = false;
 
+    let layers = contract_layers(contract_no, ns);
+
+    // resolve event declarations
+    for base_contract in &layers {
+        let mut layer_errors = Vec::new();
+
+        if events::contract_events(
+            defs[base_contract.unwrap_or(contract_no)],
+            contract_no,
+            ns,
+            &mut layer_errors,
+        ) {
+            broken = true;
+        }
+
+        inherited_errors(*base_contract, layer_errors, errors);
+    }
+
+    // resolve modifiers, so that the function declarations can refer to them
+    for base_contract in &layers {
+        let mut layer_errors = Vec::new();
+
+        for (i, parts) in defs[base_contract.unwrap_or(contract_no)]
+            .parts
+            .iter()
+            .enumerate()
+        {
+            if let pt::ContractPart::FunctionDefinition(ref f) = parts {
+                if f.ty == pt::FunctionTy::Modifier
+                    && !functions::modifier_decl(
+                        f,
+                        i,
+                        contract_no,
+                        *base_contract,
+                        ns,
+                        &mut layer_errors,
+                    )
+                {
+                    broken = true;
+                }
+            }
+        }
+
+        inherited_errors(*base_contract, layer_errors, errors);
+    }
+
     // resolve function signatures
-    for (i, parts) in def.parts.iter().enumerate() {
-        if let pt::ContractPart::FunctionDefinition(ref f) = parts {
-            if !functions::function_decl(f, i, contract_no, ns, errors) {
-                broken = true;
+    for base_contract in &layers {
+        let mut layer_errors = Vec::new();
+
+        for (i, parts) in defs[base_contract.unwrap_or(contract_no)]
+            .parts
+            .iter()
+            .enumerate()
+        {
+            if let pt::ContractPart::FunctionDefinition(ref f) = parts {
+                if f.ty != pt::FunctionTy::Modifier
+                    && !functions::function_decl(
+                        f,
+                        i,
+                        contract_no,
+                        *base_contract,
+                        ns,
+                        &mut layer_errors,
+                    )
+                {
+                    broken = true;
+                }
             }
         }
+
+        inherited_errors(*base_contract, layer_errors, errors);
     }
 
-    // resolve state variables
-    if variables::contract_variables(&def, contract_no, ns, errors) {
-        broken = true;
+    // resolve state variables; the storage of the base contracts comes first
+    let mut vartab = Vartable::new();
+    let mut cfg = ControlFlowGraph::new();
+
+    for base_contract in &layers {
+        let mut layer_errors = Vec::new();
+
+        if variables::contract_variables(
+            defs[base_contract.unwrap_or(contract_no)],
+            contract_no,
+            ns,
+            &mut cfg,
+            &mut vartab,
+            &mut layer_errors,
+        ) {
+            broken = true;
+        }
+
+        inherited_errors(*base_contract, layer_errors, errors);
     }
 
-    // Substrate requires one constructor
+    cfg.add(&mut vartab, Instr::Return { value: Vec::new() });
+
+    cfg.vars = vartab.drain();
+
+    ns.contracts[contract_no].initializer = cfg;
+
+    // Substrate requires one constructor. If any of the base contracts have a constructor,
+    // we need one to call it. The cfg for it is generated with the function bodies.
     if !ns.contracts[contract_no]
         .functions
         .iter()
         .any(|f| f.is_constructor())
-        && target == Target::Substrate
+        && (target == Target::Substrate
+            || ns.contracts[contract_no]
+                .functions
+                .iter()
+                .any(|f| f.ty == pt::FunctionTy::Constructor))
     {
-        let mut fdecl = FunctionDecl::new(
-            pt::Loc(0, 0),
+        let fdecl = FunctionDecl::new(
+            pt::Loc(0, 0, 0),
             "".to_owned(),
             vec![],
             pt::FunctionTy::Constructor,
             None,
             None,
-            pt::Visibility::Public(pt::Loc(0, 0)),
+            pt::Visibility::Public(pt::Loc(0, 0, 0)),
             Vec::new(),
             Vec::new(),
             ns,
         );
 
-        let mut vartab = Vartable::new();
-        let mut cfg = ControlFlowGraph::new();
-
-        cfg.add(&mut vartab, Instr::Return { value: Vec::new() });
-        cfg.vars = vartab.drain();
-
-        fdecl.cfg = Some(Box::new(cfg));
-
         ns.contracts[contract_no].functions.push(fdecl);
     }
 
@@ -1360,36 +1703,76 @@ fn resolve_contract_declarations(
 }
 
 fn resolve_contract_bodies(
-    def: &pt::ContractDefinition,
+    defs: &[&pt::ContractDefinition],
     contract_no: usize,
     errors: &mut Vec<Output>,
     ns: &mut Namespace,
 ) -> bool {
     let mut broken = false;
 
+    // resolve modifier bodies; the cfg is only used for checking, since the modifier is
+    // inlined into each function which uses it
+    for m in &ns.contracts[contract_no].modifiers {
+        let def = defs[m.base_contract.unwrap_or(contract_no)];
+
+        if let pt::ContractPart::FunctionDefinition(ref ast_f) = def.parts[m.ast_index.unwrap()] {
+            let mut fn_errors = Vec::new();
+
+            if cfg::generate_cfg(ast_f, m, defs, contract_no, &ns, &mut fn_errors).is_err() {
+                broken = true;
+            }
+
+            inherited_errors(m.base_contract, fn_errors, errors);
+        }
+    }
+
     // resolve function bodies
     for f in 0..ns.contracts[contract_no].functions.len() {
+        let base_contract = ns.contracts[contract_no].functions[f].base_contract;
+        let mut fn_errors = Vec::new();
+
         if let Some(ast_index) = ns.contracts[contract_no].functions[f].ast_index {
+            let def = defs[base_contract.unwrap_or(contract_no)];
+
             if let pt::ContractPart::FunctionDefinition(ref ast_f) = def.parts[ast_index] {
+                // functions without a body, like those of an interface, have no cfg
+                if let pt::Statement::Empty(_) = ast_f.body {
+                    continue;
+                }
+
                 match cfg::generate_cfg(
                     ast_f,
                     &ns.contracts[contract_no].functions[f],
+                    defs,
                     contract_no,
                     &ns,
-                    errors,
+                    &mut fn_errors,
                 ) {
                     Ok(c) => {
+                        let func = &ns.contracts[contract_no].functions[f];
+
+                        // like solc, only payable functions may read msg.value
+                        // when called externally
+                        if c.reads_value && func.is_public() && !func.is_payable() {
+                            fn_errors.push(Output::error(
+                                func.loc,
+                                "‘msg.value’ can only be used in payable public functions"
+                                    .to_string(),
+                            ));
+                            broken = true;
+                        }
+
                         match &ns.contracts[contract_no].functions[f].mutability {
                             Some(pt::StateMutability::Pure(loc)) => {
                                 if c.writes_contract_storage {
-                                    errors.push(Output::error(
+                                    fn_errors.push(Output::error(
                                         *loc,
                                         "function declared pure but writes contract storage"
                                             .to_string(),
                                     ));
                                     broken = true;
                                 } else if c.reads_contract_storage() {
-                                    errors.push(Output::error(
+                                    fn_errors.push(Output::error(
                                         *loc,
                                         "function declared pure but reads contract storage"
                                             .to_string(),
@@ -1399,14 +1782,14 @@ fn resolve_contract_bodies(
                             }
                             Some(pt::StateMutability::View(loc)) => {
                                 if c.writes_contract_storage {
-                                    errors.push(Output::error(
+                                    fn_errors.push(Output::error(
                                         *loc,
                                         "function declared view but writes contract storage"
                                             .to_string(),
                                     ));
                                     broken = true;
                                 } else if !c.reads_contract_storage() {
-                                    errors.push(Output::warning(
+                                    fn_errors.push(Output::warning(
                                         *loc,
                                         "function can be declared pure".to_string(),
                                     ));
@@ -1419,12 +1802,12 @@ fn resolve_contract_bodies(
                                 let loc = &ns.contracts[contract_no].functions[f].loc;
 
                                 if !c.writes_contract_storage && !c.reads_contract_storage() {
-                                    errors.push(Output::warning(
+                                    fn_errors.push(Output::warning(
                                         *loc,
                                         "function can be declare pure".to_string(),
                                     ));
                                 } else if !c.writes_contract_storage {
-                                    errors.push(Output::warning(
+                                    fn_errors.push(Output::warning(
                                         *loc,
                                         "function can be declared view".to_string(),
                                     ));
@@ -1436,7 +1819,14 @@ fn resolve_contract_bodies(
                     Err(_) => broken = true,
                 }
             }
+        } else if ns.contracts[contract_no].functions[f].is_constructor() {
+            match cfg::generate_default_constructor_cfg(contract_no, defs, &ns, &mut fn_errors) {
+                Ok(c) => ns.contracts[contract_no].functions[f].cfg = Some(c),
+                Err(_) => broken = true,
+            }
         }
+
+        inherited_errors(base_contract, fn_errors, errors);
     }
 
     broken
@@ -0,0 +1,1674 @@
+use num_bigint::BigInt;
+use num_traits::Signed;
+use std::collections::BTreeSet;
+
+/// A small model of Rust source code, just enough for the code generated by the casperlabs
+/// target. Code is built as a tree and printed with `print_file`, which inserts parentheses
+/// where the precedence of the operators requires them, so the output always parses.
+#[derive(Debug, PartialEq, Clone)]
+pub struct File {
+    /// Inner attributes like `#![no_main]`
+    pub attrs: Vec<Attribute>,
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Item {
+    ExternCrate(String),
+    /// Import of one or more names from a path
+    Use(Path, Vec<String>),
+    Fn(Function),
+    Mod(Module),
+    Struct(Struct),
+    Enum(Enum),
+    Impl(Impl),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Attribute {
+    pub path: Path,
+    pub args: Vec<Path>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Module {
+    pub attrs: Vec<Attribute>,
+    pub name: String,
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Struct {
+    pub attrs: Vec<Attribute>,
+    pub name: String,
+    pub fields: Vec<(String, Type)>,
+}
+
+/// Enum with unit variants, which are numbered in order from zero
+#[derive(Debug, PartialEq, Clone)]
+pub struct Enum {
+    pub attrs: Vec<Attribute>,
+    pub name: String,
+    pub variants: Vec<String>,
+}
+
+/// Implementation of a trait for a type, or of methods if there is no trait
+#[derive(Debug, PartialEq, Clone)]
+pub struct Impl {
+    pub trait_: Option<Path>,
+    pub ty: Type,
+    pub fns: Vec<Function>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Function {
+    pub attrs: Vec<Attribute>,
+    pub linkage: Linkage,
+    pub name: String,
+    pub generics: Vec<Generic>,
+    /// Does the function take `&self`
+    pub receiver: bool,
+    pub params: Vec<Param>,
+    pub returns: Option<Type>,
+    pub body: Block,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Linkage {
+    Private,
+    /// Visible in the parent module, `pub(super)`
+    Super,
+    /// Exported from the wasm module, as the entry points of a contract are
+    Export,
+}
+
+/// Type parameter with its trait bounds
+#[derive(Debug, PartialEq, Clone)]
+pub struct Generic {
+    pub name: String,
+    pub bounds: Vec<Path>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Param {
+    pub pat: Pat,
+    pub ty: Type,
+}
+
+/// Statements, optionally followed by an expression which is the value of the block
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub expr: Option<Box<Expr>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Stmt {
+    Let(Pat, Option<Type>, Option<Expr>),
+    /// Expression statement; a semicolon is added unless it ends with a block
+    Expr(Expr),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expr {
+    Lit(Lit),
+    Path(Path),
+    Tuple(Vec<Expr>),
+    Array(Vec<Expr>),
+    Macro(Path, Delimiter, MacroArgs),
+    Call(Box<Expr>, Vec<Expr>),
+    MethodCall(Box<Expr>, Segment, Vec<Expr>),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Unary(UnOp, Box<Expr>),
+    Ref(Box<Expr>),
+    Cast(Box<Expr>, Type),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Assign(Box<Expr>, Box<Expr>),
+    Closure(Vec<Pat>, Box<Expr>),
+    /// Condition, then block, and the else branch which is either a block or an if
+    If(Box<Expr>, Block, Option<Box<Expr>>),
+    Match(Box<Expr>, Vec<Arm>),
+    /// Loop with an optional label
+    Loop(Option<String>, Block),
+    ForLoop(Pat, Box<Expr>, Block),
+    Block(Block),
+    Break(Option<String>),
+    Continue(Option<String>),
+    Return(Option<Box<Expr>>),
+    /// Struct literal with its field values
+    Struct(Path, Vec<(String, Expr)>),
+    /// The `?` operator
+    Try(Box<Expr>),
+    SelfValue,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Lit {
+    Bool(bool),
+    /// Non-negative integer with an optional type suffix
+    Int(BigInt, Option<String>),
+    Char(char),
+    Str(String),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Delimiter {
+    Paren,
+    Bracket,
+    Brace,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum MacroArgs {
+    List(Vec<Expr>),
+    /// A repeated value, as in `vec![value; count]`
+    Repeat(Box<Expr>, Box<Expr>),
+    /// Key value pairs, as in `runtime_args! { "key" => value }`
+    Map(Vec<(Expr, Expr)>),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum UnOp {
+    Not,
+    Neg,
+    Deref,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BinOp {
+    Mul,
+    Div,
+    Rem,
+    Add,
+    Sub,
+    BitAnd,
+    BitXor,
+    BitOr,
+    Shl,
+    Shr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Arm {
+    pub pat: Pat,
+    pub body: Expr,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pat {
+    /// Binding, which is mutable if the flag is set
+    Ident(bool, String),
+    Tuple(Vec<Pat>),
+    TupleStruct(Path, Vec<Pat>),
+    Path(Path),
+    Lit(Lit),
+    Wild,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Type {
+    Path(Path),
+    Ref(Box<Type>),
+    Array(Box<Type>, usize),
+    Slice(Box<Type>),
+    Tuple(Vec<Type>),
+    /// The type `!` of functions which do not return
+    Never,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Path {
+    pub segments: Vec<Segment>,
+}
+
+/// Path segment with its generic arguments
+#[derive(Debug, PartialEq, Clone)]
+pub struct Segment {
+    pub ident: String,
+    pub args: Vec<Type>,
+}
+
+impl Path {
+    /// Path from a string like `runtime::get_key`
+    pub fn new(path: &str) -> Self {
+        Path {
+            segments: path.split("::").map(Segment::new).collect(),
+        }
+    }
+
+    /// Add generic arguments to the last segment
+    pub fn with_args(mut self, args: Vec<Type>) -> Self {
+        self.segments.last_mut().unwrap().args = args;
+        self
+    }
+}
+
+impl Segment {
+    pub fn new(ident: &str) -> Self {
+        Segment {
+            ident: ident.to_owned(),
+            args: Vec::new(),
+        }
+    }
+}
+
+impl Item {
+    pub fn use_(path: &str, names: &[&str]) -> Self {
+        Item::Use(
+            Path::new(path),
+            names.iter().map(|name| (*name).to_owned()).collect(),
+        )
+    }
+}
+
+impl Attribute {
+    pub fn new(path: &str, args: &[&str]) -> Self {
+        Attribute {
+            path: Path::new(path),
+            args: args.iter().map(|arg| Path::new(arg)).collect(),
+        }
+    }
+}
+
+impl Param {
+    pub fn new(name: &str, ty: Type) -> Self {
+        Param {
+            pat: Pat::ident(name),
+            ty,
+        }
+    }
+}
+
+impl Block {
+    pub fn new(stmts: Vec<Stmt>) -> Self {
+        Block { stmts, expr: None }
+    }
+
+    /// Block whose value is the given expression
+    pub fn value(stmts: Vec<Stmt>, expr: Expr) -> Self {
+        Block {
+            stmts,
+            expr: Some(Box::new(expr)),
+        }
+    }
+}
+
+impl Stmt {
+    /// Immutable binding with a type
+    pub fn let_(name: &str, ty: Option<Type>, init: Expr) -> Self {
+        Stmt::Let(Pat::ident(name), ty, Some(init))
+    }
+}
+
+impl Pat {
+    pub fn ident(name: &str) -> Self {
+        Pat::Ident(false, name.to_owned())
+    }
+
+    pub fn tuple_struct(path: &str, pats: Vec<Pat>) -> Self {
+        Pat::TupleStruct(Path::new(path), pats)
+    }
+}
+
+impl Type {
+    pub fn path(path: &str) -> Self {
+        Type::Path(Path::new(path))
+    }
+
+    /// Type with generic arguments, like `Vec<u8>`
+    pub fn generic(path: &str, args: Vec<Type>) -> Self {
+        Type::Path(Path::new(path).with_args(args))
+    }
+
+    pub fn unit() -> Self {
+        Type::Tuple(Vec::new())
+    }
+}
+
+impl Expr {
+    pub fn path(path: &str) -> Self {
+        Expr::Path(Path::new(path))
+    }
+
+    pub fn bool(value: bool) -> Self {
+        Expr::Lit(Lit::Bool(value))
+    }
+
+    /// Integer literal; negative numbers are negated literals
+    pub fn int(n: &BigInt) -> Self {
+        let lit = Expr::Lit(Lit::Int(n.abs(), None));
+
+        if n.is_negative() {
+            Expr::Unary(UnOp::Neg, Box::new(lit))
+        } else {
+            lit
+        }
+    }
+
+    /// Integer literal with a type suffix
+    pub fn typed_int(n: &BigInt, suffix: &str) -> Self {
+        let lit = Expr::Lit(Lit::Int(n.abs(), Some(suffix.to_owned())));
+
+        if n.is_negative() {
+            Expr::Unary(UnOp::Neg, Box::new(lit))
+        } else {
+            lit
+        }
+    }
+
+    pub fn str(s: &str) -> Self {
+        Expr::Lit(Lit::Str(s.to_owned()))
+    }
+
+    pub fn unit() -> Self {
+        Expr::Tuple(Vec::new())
+    }
+
+    /// Call the function with the given path
+    pub fn call(path: Path, args: Vec<Expr>) -> Self {
+        Expr::Call(Box::new(Expr::Path(path)), args)
+    }
+
+    pub fn method(self, method: &str, args: Vec<Expr>) -> Self {
+        Expr::MethodCall(Box::new(self), Segment::new(method), args)
+    }
+
+    pub fn macro_call(name: &str, delimiter: Delimiter, args: Vec<Expr>) -> Self {
+        Expr::Macro(Path::new(name), delimiter, MacroArgs::List(args))
+    }
+
+    pub fn binary(op: BinOp, left: Expr, right: Expr) -> Self {
+        Expr::Binary(op, Box::new(left), Box::new(right))
+    }
+
+    pub fn unary(op: UnOp, expr: Expr) -> Self {
+        Expr::Unary(op, Box::new(expr))
+    }
+
+    pub fn reference(self) -> Self {
+        Expr::Ref(Box::new(self))
+    }
+
+    pub fn assign(left: Expr, right: Expr) -> Self {
+        Expr::Assign(Box::new(left), Box::new(right))
+    }
+
+    pub fn if_(cond: Expr, then: Block, else_: Option<Expr>) -> Self {
+        Expr::If(Box::new(cond), then, else_.map(Box::new))
+    }
+
+    /// Expressions which end with a block do not need a semicolon as a statement
+    fn is_block_like(&self) -> bool {
+        matches!(
+            self,
+            Expr::If(..) | Expr::Match(..) | Expr::Loop(..) | Expr::ForLoop(..) | Expr::Block(..)
+        )
+    }
+
+    fn precedence(&self) -> Precedence {
+        match self {
+            Expr::Lit(_)
+            | Expr::Path(_)
+            | Expr::Tuple(_)
+            | Expr::Array(_)
+            | Expr::Macro(..)
+            | Expr::Struct(..)
+            | Expr::SelfValue => Precedence::Primary,
+            Expr::Call(..)
+            | Expr::MethodCall(..)
+            | Expr::Field(..)
+            | Expr::Index(..)
+            | Expr::Try(_) => Precedence::Postfix,
+            Expr::Unary(..) | Expr::Ref(_) => Precedence::Prefix,
+            Expr::Cast(..) => Precedence::Cast,
+            Expr::Binary(op, _, _) => op.precedence(),
+            // expressions which start or end with a block are parenthesized wherever an
+            // operator is applied to them
+            _ => Precedence::Lowest,
+        }
+    }
+}
+
+impl BinOp {
+    fn precedence(self) -> Precedence {
+        match self {
+            BinOp::Mul | BinOp::Div | BinOp::Rem => Precedence::Multiplicative,
+            BinOp::Add | BinOp::Sub => Precedence::Additive,
+            BinOp::BitAnd => Precedence::BitAnd,
+            BinOp::BitXor => Precedence::BitXor,
+            BinOp::BitOr => Precedence::BitOr,
+            BinOp::Shl | BinOp::Shr => Precedence::Shift,
+            BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+                Precedence::Compare
+            }
+            BinOp::And => Precedence::And,
+            BinOp::Or => Precedence::Or,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::BitAnd => "&",
+            BinOp::BitXor => "^",
+            BinOp::BitOr => "|",
+            BinOp::Shl => "<<",
+            BinOp::Shr => ">>",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+            BinOp::And => "&&",
+            BinOp::Or => "||",
+        }
+    }
+}
+
+/// Operator precedence, from loosest to tightest binding
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+enum Precedence {
+    Lowest,
+    Or,
+    And,
+    Compare,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shift,
+    Additive,
+    Multiplicative,
+    Cast,
+    Prefix,
+    Postfix,
+    Primary,
+}
+
+impl Precedence {
+    fn next(self) -> Precedence {
+        match self {
+            Precedence::Lowest => Precedence::Or,
+            Precedence::Or => Precedence::And,
+            Precedence::And => Precedence::Compare,
+            Precedence::Compare => Precedence::BitOr,
+            Precedence::BitOr => Precedence::BitXor,
+            Precedence::BitXor => Precedence::BitAnd,
+            Precedence::BitAnd => Precedence::Shift,
+            Precedence::Shift => Precedence::Additive,
+            Precedence::Additive => Precedence::Multiplicative,
+            Precedence::Multiplicative => Precedence::Cast,
+            Precedence::Cast => Precedence::Prefix,
+            Precedence::Prefix => Precedence::Postfix,
+            Precedence::Postfix | Precedence::Primary => Precedence::Primary,
+        }
+    }
+}
+
+/// Print the file with one statement per line and four spaces of indentation per block.
+/// Items are separated by blank lines, except for consecutive use declarations.
+pub fn print_file(file: &File) -> String {
+    let mut printer = Printer {
+        out: String::new(),
+        indent: 0,
+    };
+
+    for attr in &file.attrs {
+        printer.out.push_str("#!");
+        printer.attribute(attr);
+        printer.newline();
+    }
+
+    if !file.attrs.is_empty() && !file.items.is_empty() {
+        printer.newline();
+    }
+
+    printer.items(&file.items);
+    printer.newline();
+
+    printer.out
+}
+
+struct Printer {
+    out: String,
+    indent: usize,
+}
+
+impl Printer {
+    fn newline(&mut self) {
+        self.out.push('\n');
+
+        for _ in 0..self.indent {
+            self.out.push_str("    ");
+        }
+    }
+
+    /// Blank line without trailing whitespace, followed by the indentation of the next line
+    fn blank_line(&mut self) {
+        self.out.push('\n');
+        self.newline();
+    }
+
+    fn items(&mut self, items: &[Item]) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                match (&items[i - 1], item) {
+                    (Item::Use(..), Item::Use(..)) => self.newline(),
+                    _ => self.blank_line(),
+                }
+            }
+
+            self.item(item);
+        }
+    }
+
+    fn item(&mut self, item: &Item) {
+        match item {
+            Item::ExternCrate(name) => {
+                self.out.push_str("extern crate ");
+                self.ident(name);
+                self.out.push(';');
+            }
+            Item::Use(path, names) => {
+                self.out.push_str("use ");
+                self.path(path, false);
+                self.out.push_str("::");
+
+                if names.len() == 1 {
+                    self.ident(&names[0]);
+                } else {
+                    self.out.push('{');
+                    self.list(names, |p, name| p.ident(name));
+                    self.out.push('}');
+                }
+
+                self.out.push(';');
+            }
+            Item::Fn(function) => self.function(function),
+            Item::Struct(def) => {
+                self.outer_attributes(&def.attrs);
+                self.out.push_str("struct ");
+                self.ident(&def.name);
+
+                if def.fields.is_empty() {
+                    self.out.push_str(" {}");
+                } else {
+                    self.out.push_str(" {");
+                    self.indent += 1;
+
+                    for (name, ty) in &def.fields {
+                        self.newline();
+                        self.ident(name);
+                        self.out.push_str(": ");
+                        self.ty(ty);
+                        self.out.push(',');
+                    }
+
+                    self.indent -= 1;
+                    self.newline();
+                    self.out.push('}');
+                }
+            }
+            Item::Enum(def) => {
+                self.outer_attributes(&def.attrs);
+                self.out.push_str("enum ");
+                self.ident(&def.name);
+
+                if def.variants.is_empty() {
+                    self.out.push_str(" {}");
+                } else {
+                    self.out.push_str(" {");
+                    self.indent += 1;
+
+                    for name in &def.variants {
+                        self.newline();
+                        self.ident(name);
+                        self.out.push(',');
+                    }
+
+                    self.indent -= 1;
+                    self.newline();
+                    self.out.push('}');
+                }
+            }
+            Item::Impl(def) => {
+                self.out.push_str("impl ");
+
+                if let Some(trait_) = &def.trait_ {
+                    self.path(trait_, false);
+                    self.out.push_str(" for ");
+                }
+
+                self.ty(&def.ty);
+
+                if def.fns.is_empty() {
+                    self.out.push_str(" {}");
+                } else {
+                    self.out.push_str(" {");
+                    self.indent += 1;
+
+                    for (i, function) in def.fns.iter().enumerate() {
+                        if i > 0 {
+                            self.out.push('\n');
+                        }
+                        self.newline();
+                        self.function(function);
+                    }
+
+                    self.indent -= 1;
+                    self.newline();
+                    self.out.push('}');
+                }
+            }
+            Item::Mod(module) => {
+                self.outer_attributes(&module.attrs);
+                self.out.push_str("mod ");
+                self.ident(&module.name);
+
+                if module.items.is_empty() {
+                    self.out.push_str(" {}");
+                } else {
+                    self.out.push_str(" {");
+                    self.indent += 1;
+                    self.newline();
+                    self.items(&module.items);
+                    self.indent -= 1;
+                    self.newline();
+                    self.out.push('}');
+                }
+            }
+        }
+    }
+
+    fn outer_attributes(&mut self, attrs: &[Attribute]) {
+        for attr in attrs {
+            self.out.push('#');
+            self.attribute(attr);
+            self.newline();
+        }
+    }
+
+    fn attribute(&mut self, attr: &Attribute) {
+        self.out.push('[');
+        self.path(&attr.path, false);
+
+        if !attr.args.is_empty() {
+            self.out.push('(');
+            self.list(&attr.args, |p, arg| p.path(arg, false));
+            self.out.push(')');
+        }
+
+        self.out.push(']');
+    }
+
+    fn function(&mut self, function: &Function) {
+        self.outer_attributes(&function.attrs);
+        match function.linkage {
+            Linkage::Private => {}
+            Linkage::Super => self.out.push_str("pub(super) "),
+            Linkage::Export => {
+                self.out.push_str("#[no_mangle]");
+                self.newline();
+                self.out.push_str("pub extern \"C\" ");
+            }
+        }
+        self.out.push_str("fn ");
+        self.ident(&function.name);
+
+        if !function.generics.is_empty() {
+            self.out.push('<');
+            self.list(&function.generics, |p, generic| {
+                p.ident(&generic.name);
+
+                for (i, bound) in generic.bounds.iter().enumerate() {
+                    p.out.push_str(if i == 0 { ": " } else { " + " });
+                    p.path(bound, false);
+                }
+            });
+            self.out.push('>');
+        }
+
+        self.out.push('(');
+        if function.receiver {
+            self.out.push_str("&self");
+            if !function.params.is_empty() {
+                self.out.push_str(", ");
+            }
+        }
+        self.list(&function.params, |p, param| {
+            p.pat(&param.pat);
+            p.out.push_str(": ");
+            p.ty(&param.ty);
+        });
+        self.out.push(')');
+
+        if let Some(ty) = &function.returns {
+            self.out.push_str(" -> ");
+            self.ty(ty);
+        }
+
+        self.out.push(' ');
+        self.block(&function.body);
+    }
+
+    fn block(&mut self, block: &Block) {
+        if block.stmts.is_empty() && block.expr.is_none() {
+            self.out.push_str("{}");
+            return;
+        }
+
+        self.out.push('{');
+        self.indent += 1;
+
+        for stmt in &block.stmts {
+            self.newline();
+            self.stmt(stmt);
+        }
+
+        if let Some(expr) = &block.expr {
+            self.newline();
+            self.expr(expr, Precedence::Lowest);
+        }
+
+        self.indent -= 1;
+        self.newline();
+        self.out.push('}');
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Let(pat, ty, init) => {
+                self.out.push_str("let ");
+                self.pat(pat);
+
+                if let Some(ty) = ty {
+                    self.out.push_str(": ");
+                    self.ty(ty);
+                }
+
+                if let Some(init) = init {
+                    self.out.push_str(" = ");
+                    self.expr(init, Precedence::Lowest);
+                }
+
+                self.out.push(';');
+            }
+            Stmt::Expr(expr) => {
+                self.expr(expr, Precedence::Lowest);
+
+                if !expr.is_block_like() {
+                    self.out.push(';');
+                }
+            }
+        }
+    }
+
+    /// Print the expression, in parentheses if it binds looser than the context requires
+    fn expr(&mut self, expr: &Expr, min: Precedence) {
+        if expr.precedence() < min {
+            self.out.push('(');
+            self.expr(expr, Precedence::Lowest);
+            self.out.push(')');
+            return;
+        }
+
+        match expr {
+            Expr::Lit(lit) => self.lit(lit),
+            Expr::Path(path) => self.path(path, true),
+            Expr::Tuple(exprs) => {
+                self.out.push('(');
+                self.exprs(exprs);
+                if exprs.len() == 1 {
+                    self.out.push(',');
+                }
+                self.out.push(')');
+            }
+            Expr::Array(exprs) => {
+                self.out.push('[');
+                self.exprs(exprs);
+                self.out.push(']');
+            }
+            Expr::Macro(path, delimiter, args) => {
+                self.path(path, true);
+                self.out.push('!');
+                let (open, close) = match delimiter {
+                    Delimiter::Paren => ('(', ')'),
+                    Delimiter::Bracket => ('[', ']'),
+                    Delimiter::Brace => ('{', '}'),
+                };
+                if *delimiter == Delimiter::Brace {
+                    self.out.push(' ');
+                }
+                self.out.push(open);
+                match args {
+                    MacroArgs::List(exprs) => self.exprs(exprs),
+                    MacroArgs::Repeat(value, count) => {
+                        self.expr(value, Precedence::Lowest);
+                        self.out.push_str("; ");
+                        self.expr(count, Precedence::Lowest);
+                    }
+                    MacroArgs::Map(entries) if !entries.is_empty() => {
+                        self.out.push(' ');
+                        self.list(entries, |p, (key, value)| {
+                            p.expr(key, Precedence::Lowest);
+                            p.out.push_str(" => ");
+                            p.expr(value, Precedence::Lowest);
+                        });
+                        self.out.push(' ');
+                    }
+                    MacroArgs::Map(_) => {}
+                }
+                self.out.push(close);
+            }
+            Expr::Call(func, args) => {
+                self.expr(func, Precedence::Postfix);
+                self.out.push('(');
+                self.exprs(args);
+                self.out.push(')');
+            }
+            Expr::MethodCall(receiver, method, args) => {
+                self.expr(receiver, Precedence::Postfix);
+                self.out.push('.');
+                self.segment(method, true);
+                self.out.push('(');
+                self.exprs(args);
+                self.out.push(')');
+            }
+            Expr::Field(expr, field) => {
+                self.expr(expr, Precedence::Postfix);
+                self.out.push('.');
+                self.ident(field);
+            }
+            Expr::Index(expr, index) => {
+                self.expr(expr, Precedence::Postfix);
+                self.out.push('[');
+                self.expr(index, Precedence::Lowest);
+                self.out.push(']');
+            }
+            Expr::Unary(op, expr) => {
+                self.out.push(match op {
+                    UnOp::Not => '!',
+                    UnOp::Neg => '-',
+                    UnOp::Deref => '*',
+                });
+                self.expr(expr, Precedence::Prefix);
+            }
+            Expr::Ref(expr) => {
+                self.out.push('&');
+                self.expr(expr, Precedence::Prefix);
+            }
+            Expr::Cast(expr, ty) => {
+                self.expr(expr, Precedence::Cast);
+                self.out.push_str(" as ");
+                self.ty(ty);
+            }
+            Expr::Binary(op, left, right) => {
+                let precedence = op.precedence();
+
+                // `a as u64 < b` and `a as i32 << b` would parse as the start of
+                // generic arguments
+                match op {
+                    BinOp::Lt | BinOp::Le | BinOp::Shl if ends_with_cast(left) => {
+                        self.expr(left, Precedence::Primary)
+                    }
+                    // comparisons cannot be chained
+                    _ if precedence == Precedence::Compare => self.expr(left, precedence.next()),
+                    _ => self.expr(left, precedence),
+                }
+
+                self.out.push(' ');
+                self.out.push_str(op.as_str());
+                self.out.push(' ');
+                self.expr(right, precedence.next());
+            }
+            Expr::Assign(left, right) => {
+                self.expr(left, Precedence::Prefix);
+                self.out.push_str(" = ");
+                self.expr(right, Precedence::Lowest);
+            }
+            Expr::Closure(params, body) => {
+                self.out.push('|');
+                self.list(params, |p, pat| p.pat(pat));
+                self.out.push_str("| ");
+                self.expr(body, Precedence::Lowest);
+            }
+            Expr::If(cond, then, else_) => {
+                self.out.push_str("if ");
+                self.condition(cond);
+                self.out.push(' ');
+                self.block(then);
+
+                if let Some(else_) = else_ {
+                    self.out.push_str(" else ");
+
+                    match &**else_ {
+                        Expr::If(..) => self.expr(else_, Precedence::Lowest),
+                        Expr::Block(block) => self.block(block),
+                        expr => self.block(&Block::value(Vec::new(), expr.clone())),
+                    }
+                }
+            }
+            Expr::Match(expr, arms) => {
+                self.out.push_str("match ");
+                self.condition(expr);
+                self.out.push_str(" {");
+                self.indent += 1;
+
+                for arm in arms {
+                    self.newline();
+                    self.pat(&arm.pat);
+                    self.out.push_str(" => ");
+
+                    match &arm.body {
+                        Expr::Block(block) => self.block(block),
+                        body => {
+                            self.expr(body, Precedence::Lowest);
+                            self.out.push(',');
+                        }
+                    }
+                }
+
+                self.indent -= 1;
+                self.newline();
+                self.out.push('}');
+            }
+            Expr::Loop(label, body) => {
+                if let Some(label) = label {
+                    self.label(label);
+                    self.out.push_str(": ");
+                }
+                self.out.push_str("loop ");
+                self.block(body);
+            }
+            Expr::ForLoop(pat, expr, body) => {
+                self.out.push_str("for ");
+                self.pat(pat);
+                self.out.push_str(" in ");
+                self.condition(expr);
+                self.out.push(' ');
+                self.block(body);
+            }
+            Expr::Block(block) => self.block(block),
+            Expr::Break(label) | Expr::Continue(label) => {
+                self.out.push_str(match expr {
+                    Expr::Break(_) => "break",
+                    _ => "continue",
+                });
+
+                if let Some(label) = label {
+                    self.out.push(' ');
+                    self.label(label);
+                }
+            }
+            Expr::Return(value) => {
+                self.out.push_str("return");
+
+                if let Some(value) = value {
+                    self.out.push(' ');
+                    self.expr(value, Precedence::Lowest);
+                }
+            }
+            Expr::Struct(path, fields) => {
+                self.path(path, true);
+                self.out.push_str(" { ");
+                self.list(fields, |p, (name, value)| {
+                    p.ident(name);
+
+                    // field init shorthand
+                    if let Expr::Path(path) = value {
+                        if path.segments.len() == 1
+                            && path.segments[0].ident == *name
+                            && path.segments[0].args.is_empty()
+                        {
+                            return;
+                        }
+                    }
+
+                    p.out.push_str(": ");
+                    p.expr(value, Precedence::Lowest);
+                });
+                self.out.push_str(" }");
+            }
+            Expr::Try(expr) => {
+                self.expr(expr, Precedence::Postfix);
+                self.out.push('?');
+            }
+            Expr::SelfValue => self.out.push_str("self"),
+        }
+    }
+
+    /// The condition of an if, match or for; a struct literal would be parsed as its block
+    fn condition(&mut self, expr: &Expr) {
+        if contains_struct(expr) {
+            self.expr(expr, Precedence::Primary);
+        } else {
+            self.expr(expr, Precedence::Lowest);
+        }
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) {
+        self.list(exprs, |p, expr| p.expr(expr, Precedence::Lowest));
+    }
+
+    fn lit(&mut self, lit: &Lit) {
+        match lit {
+            Lit::Bool(value) => self.out.push_str(if *value { "true" } else { "false" }),
+            Lit::Int(n, suffix) => {
+                self.out.push_str(&n.to_str_radix(10));
+
+                if let Some(suffix) = suffix {
+                    self.out.push_str(suffix);
+                }
+            }
+            // the escapes of Debug are valid in Rust string literals
+            Lit::Char(c) => self.out.push_str(&format!("{:?}", c)),
+            Lit::Str(s) => self.out.push_str(&format!("{:?}", s)),
+        }
+    }
+
+    fn pat(&mut self, pat: &Pat) {
+        match pat {
+            Pat::Ident(mutable, name) => {
+                if *mutable {
+                    self.out.push_str("mut ");
+                }
+                self.ident(name);
+            }
+            Pat::Tuple(pats) => {
+                self.out.push('(');
+                self.list(pats, |p, pat| p.pat(pat));
+                if pats.len() == 1 {
+                    self.out.push(',');
+                }
+                self.out.push(')');
+            }
+            Pat::TupleStruct(path, pats) => {
+                self.path(path, true);
+                self.out.push('(');
+                self.list(pats, |p, pat| p.pat(pat));
+                self.out.push(')');
+            }
+            Pat::Path(path) => self.path(path, true),
+            Pat::Lit(lit) => self.lit(lit),
+            Pat::Wild => self.out.push('_'),
+        }
+    }
+
+    fn ty(&mut self, ty: &Type) {
+        match ty {
+            Type::Path(path) => self.path(path, false),
+            Type::Ref(ty) => {
+                self.out.push('&');
+                self.ty(ty);
+            }
+            Type::Array(ty, len) => {
+                self.out.push('[');
+                self.ty(ty);
+                self.out.push_str(&format!("; {}]", len));
+            }
+            Type::Slice(ty) => {
+                self.out.push('[');
+                self.ty(ty);
+                self.out.push(']');
+            }
+            Type::Tuple(tys) => {
+                self.out.push('(');
+                self.list(tys, |p, ty| p.ty(ty));
+                if tys.len() == 1 {
+                    self.out.push(',');
+                }
+                self.out.push(')');
+            }
+            Type::Never => self.out.push('!'),
+        }
+    }
+
+    /// Paths in expressions need `::` before generic arguments
+    fn path(&mut self, path: &Path, expr: bool) {
+        for (i, segment) in path.segments.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str("::");
+            }
+            self.segment(segment, expr);
+        }
+    }
+
+    fn segment(&mut self, segment: &Segment, expr: bool) {
+        self.ident(&segment.ident);
+
+        if !segment.args.is_empty() {
+            if expr {
+                self.out.push_str("::");
+            }
+            self.out.push('<');
+            self.list(&segment.args, |p, ty| p.ty(ty));
+            self.out.push('>');
+        }
+    }
+
+    /// Identifiers which are Rust keywords are printed as raw identifiers. The
+    /// keywords which start a path cannot be, so they are printed as they are;
+    /// the generated code only uses them as such.
+    fn ident(&mut self, ident: &str) {
+        match ident {
+            _ if is_keyword(ident) => {
+                self.out.push_str("r#");
+                self.out.push_str(ident);
+            }
+            _ => self.out.push_str(ident),
+        }
+    }
+
+    fn label(&mut self, label: &str) {
+        self.out.push('\'');
+        self.out.push_str(label);
+    }
+
+    fn list<T, F: Fn(&mut Printer, &T)>(&mut self, items: &[T], f: F) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            f(self, item);
+        }
+    }
+}
+
+/// The names which items refer to: the first segments of the paths of their expressions,
+/// patterns, types, trait bounds and attributes, and the methods which they call. Only what the
+/// generated code refers to is imported, and only the helpers which it calls are generated.
+pub fn names(items: &[Item]) -> BTreeSet<String> {
+    let mut names = Names(BTreeSet::new());
+    for item in items {
+        names.item(item);
+    }
+    names.0
+}
+
+struct Names(BTreeSet<String>);
+
+impl Names {
+    fn item(&mut self, item: &Item) {
+        match item {
+            Item::ExternCrate(_) | Item::Use(..) => (),
+            Item::Fn(function) => self.function(function),
+            Item::Mod(module) => {
+                self.attrs(&module.attrs);
+                for item in &module.items {
+                    self.item(item);
+                }
+            }
+            Item::Struct(def) => {
+                self.attrs(&def.attrs);
+                for (_, ty) in &def.fields {
+                    self.ty(ty);
+                }
+            }
+            Item::Enum(def) => self.attrs(&def.attrs),
+            Item::Impl(def) => {
+                if let Some(trait_) = &def.trait_ {
+                    self.path(trait_);
+                }
+                self.ty(&def.ty);
+                for function in &def.fns {
+                    self.function(function);
+                }
+            }
+        }
+    }
+
+    fn attrs(&mut self, attrs: &[Attribute]) {
+        for attr in attrs {
+            self.path(&attr.path);
+            for arg in &attr.args {
+                self.path(arg);
+            }
+        }
+    }
+
+    fn function(&mut self, function: &Function) {
+        self.attrs(&function.attrs);
+        for generic in &function.generics {
+            for bound in &generic.bounds {
+                self.path(bound);
+            }
+        }
+        for param in &function.params {
+            self.pat(&param.pat);
+            self.ty(&param.ty);
+        }
+        if let Some(ty) = &function.returns {
+            self.ty(ty);
+        }
+        self.block(&function.body);
+    }
+
+    fn block(&mut self, block: &Block) {
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Let(pat, ty, init) => {
+                    self.pat(pat);
+                    if let Some(ty) = ty {
+                        self.ty(ty);
+                    }
+                    if let Some(init) = init {
+                        self.expr(init);
+                    }
+                }
+                Stmt::Expr(expr) => self.expr(expr),
+            }
+        }
+        if let Some(expr) = &block.expr {
+            self.expr(expr);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Lit(_)
+            | Expr::Break(_)
+            | Expr::Continue(_)
+            | Expr::Return(None)
+            | Expr::SelfValue => (),
+            Expr::Path(path) => self.path(path),
+            Expr::Tuple(exprs) | Expr::Array(exprs) => {
+                for expr in exprs {
+                    self.expr(expr);
+                }
+            }
+            Expr::Macro(path, _, args) => {
+                self.path(path);
+                match args {
+                    MacroArgs::List(exprs) => {
+                        for expr in exprs {
+                            self.expr(expr);
+                        }
+                    }
+                    MacroArgs::Repeat(value, count) => {
+                        self.expr(value);
+                        self.expr(count);
+                    }
+                    MacroArgs::Map(pairs) => {
+                        for (key, value) in pairs {
+                            self.expr(key);
+                            self.expr(value);
+                        }
+                    }
+                }
+            }
+            Expr::Call(function, args) => {
+                self.expr(function);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::MethodCall(receiver, method, args) => {
+                self.expr(receiver);
+                self.0.insert(method.ident.clone());
+                for ty in &method.args {
+                    self.ty(ty);
+                }
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::Field(expr, _)
+            | Expr::Unary(_, expr)
+            | Expr::Ref(expr)
+            | Expr::Try(expr)
+            | Expr::Return(Some(expr)) => self.expr(expr),
+            Expr::Index(left, right) | Expr::Binary(_, left, right) | Expr::Assign(left, right) => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Cast(expr, ty) => {
+                self.expr(expr);
+                self.ty(ty);
+            }
+            Expr::Closure(pats, body) => {
+                for pat in pats {
+                    self.pat(pat);
+                }
+                self.expr(body);
+            }
+            Expr::If(cond, then, otherwise) => {
+                self.expr(cond);
+                self.block(then);
+                if let Some(otherwise) = otherwise {
+                    self.expr(otherwise);
+                }
+            }
+            Expr::Match(expr, arms) => {
+                self.expr(expr);
+                for arm in arms {
+                    self.pat(&arm.pat);
+                    self.expr(&arm.body);
+                }
+            }
+            Expr::Loop(_, body) | Expr::Block(body) => self.block(body),
+            Expr::ForLoop(pat, expr, body) => {
+                self.pat(pat);
+                self.expr(expr);
+                self.block(body);
+            }
+            Expr::Struct(path, fields) => {
+                self.path(path);
+                for (_, expr) in fields {
+                    self.expr(expr);
+                }
+            }
+        }
+    }
+
+    fn pat(&mut self, pat: &Pat) {
+        match pat {
+            Pat::Ident(..) | Pat::Lit(_) | Pat::Wild => (),
+            Pat::Tuple(pats) => {
+                for pat in pats {
+                    self.pat(pat);
+                }
+            }
+            Pat::TupleStruct(path, pats) => {
+                self.path(path);
+                for pat in pats {
+                    self.pat(pat);
+                }
+            }
+            Pat::Path(path) => self.path(path),
+        }
+    }
+
+    fn ty(&mut self, ty: &Type) {
+        match ty {
+            Type::Path(path) => self.path(path),
+            Type::Ref(ty) | Type::Array(ty, _) | Type::Slice(ty) => self.ty(ty),
+            Type::Tuple(tys) => {
+                for ty in tys {
+                    self.ty(ty);
+                }
+            }
+            Type::Never => (),
+        }
+    }
+
+    fn path(&mut self, path: &Path) {
+        // the other segments are members of the first, like `get_key` of
+        // `runtime::get_key`
+        self.0.insert(path.segments[0].ident.clone());
+        for segment in &path.segments {
+            for ty in &segment.args {
+                self.ty(ty);
+            }
+        }
+    }
+}
+
+fn contains_struct(expr: &Expr) -> bool {
+    match expr {
+        Expr::Struct(..) => true,
+        Expr::Tuple(exprs) | Expr::Array(exprs) | Expr::Macro(_, _, MacroArgs::List(exprs)) => {
+            exprs.iter().any(contains_struct)
+        }
+        Expr::Macro(_, _, MacroArgs::Repeat(left, right)) => {
+            contains_struct(left) || contains_struct(right)
+        }
+        Expr::Macro(_, _, MacroArgs::Map(entries)) => entries
+            .iter()
+            .any(|(key, value)| contains_struct(key) || contains_struct(value)),
+        Expr::Call(func, args) | Expr::MethodCall(func, _, args) => {
+            contains_struct(func) || args.iter().any(contains_struct)
+        }
+        Expr::Field(expr, _)
+        | Expr::Unary(_, expr)
+        | Expr::Ref(expr)
+        | Expr::Cast(expr, _)
+        | Expr::Closure(_, expr)
+        | Expr::Try(expr) => contains_struct(expr),
+        Expr::Index(left, right) | Expr::Binary(_, left, right) | Expr::Assign(left, right) => {
+            contains_struct(left) || contains_struct(right)
+        }
+        _ => false,
+    }
+}
+
+/// Does the expression end with a cast when printed without parentheses
+fn ends_with_cast(expr: &Expr) -> bool {
+    match expr {
+        Expr::Cast(..) => true,
+        Expr::Binary(_, _, right) => ends_with_cast(right),
+        _ => false,
+    }
+}
+
+/// Keywords which may start a path, and cannot be raw identifiers
+pub fn is_path_keyword(ident: &str) -> bool {
+    matches!(ident, "self" | "Self" | "super" | "crate")
+}
+
+fn is_keyword(ident: &str) -> bool {
+    matches!(
+        ident,
+        "abstract"
+            | "as"
+            | "async"
+            | "await"
+            | "become"
+            | "box"
+            | "break"
+            | "const"
+            | "continue"
+            | "do"
+            | "dyn"
+            | "else"
+            | "enum"
+            | "extern"
+            | "false"
+            | "final"
+            | "fn"
+            | "for"
+            | "if"
+            | "impl"
+            | "in"
+            | "let"
+            | "loop"
+            | "macro"
+            | "match"
+            | "mod"
+            | "move"
+            | "mut"
+            | "override"
+            | "priv"
+            | "pub"
+            | "ref"
+            | "return"
+            | "static"
+            | "struct"
+            | "trait"
+            | "true"
+            | "try"
+            | "type"
+            | "typeof"
+            | "unsafe"
+            | "unsized"
+            | "use"
+            | "virtual"
+            | "where"
+            | "while"
+            | "yield"
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BinOp, Block, Expr, Precedence, Printer, Stmt, Type, UnOp};
+
+    fn print(expr: &Expr) -> String {
+        let mut printer = Printer {
+            out: String::new(),
+            indent: 0,
+        };
+
+        printer.stmt(&Stmt::Expr(expr.clone()));
+
+        printer.out
+    }
+
+    fn var(name: &str) -> Expr {
+        Expr::path(name)
+    }
+
+    fn cast(expr: Expr, ty: &str) -> Expr {
+        Expr::Cast(Box::new(expr), Type::path(ty))
+    }
+
+    #[test]
+    fn cast_before_angle_bracket() {
+        let lt = Expr::binary(BinOp::Lt, cast(var("a"), "u64"), var("b"));
+        assert_eq!(print(&lt), "(a as u64) < b;");
+
+        let shl = Expr::binary(BinOp::Shl, cast(var("a"), "i32"), var("b"));
+        assert_eq!(print(&shl), "(a as i32) << b;");
+
+        // the cast is at the end of the left operand, even when it is not all of it
+        let sum = Expr::binary(BinOp::Add, var("c"), cast(var("a"), "u64"));
+        let le = Expr::binary(BinOp::Le, sum, var("b"));
+        assert_eq!(print(&le), "(c + a as u64) <= b;");
+
+        // other operators, and casts on the right, need no parentheses
+        let gt = Expr::binary(BinOp::Gt, cast(var("a"), "u64"), var("b"));
+        assert_eq!(print(&gt), "a as u64 > b;");
+
+        let lt = Expr::binary(BinOp::Lt, var("b"), cast(var("a"), "u64"));
+        assert_eq!(print(&lt), "b < a as u64;");
+    }
+
+    #[test]
+    fn struct_in_condition() {
+        let literal = Expr::Struct(
+            super::Path::new("S"),
+            vec![("x".to_string(), Expr::int(&1.into()))],
+        );
+        let cond = Expr::binary(BinOp::Eq, var("a"), literal.clone());
+        let if_ = Expr::if_(cond, Block::new(Vec::new()), None);
+        assert_eq!(print(&if_), "if (a == S { x: 1 }) {}");
+
+        // a struct literal anywhere in the condition needs the parentheses
+        let cond = Expr::call(super::Path::new("check"), vec![literal]);
+        let if_ = Expr::if_(cond, Block::new(Vec::new()), None);
+        assert_eq!(print(&if_), "if (check(S { x: 1 })) {}");
+
+        let if_ = Expr::if_(var("a"), Block::new(Vec::new()), None);
+        assert_eq!(print(&if_), "if a {}");
+    }
+
+    #[test]
+    fn precedence() {
+        let sum = Expr::binary(BinOp::Add, var("a"), var("b"));
+        let product = Expr::binary(BinOp::Mul, Expr::unary(UnOp::Neg, sum.clone()), var("c"));
+        assert_eq!(print(&product), "-(a + b) * c;");
+
+        let product = Expr::binary(BinOp::Mul, var("c"), sum.clone());
+        assert_eq!(print(&product), "c * (a + b);");
+
+        // operators are left associative
+        let left = Expr::binary(
+            BinOp::Sub,
+            Expr::binary(BinOp::Sub, var("a"), var("b")),
+            var("c"),
+        );
+        assert_eq!(print(&left), "a - b - c;");
+
+        let right = Expr::binary(
+            BinOp::Sub,
+            var("a"),
+            Expr::binary(BinOp::Sub, var("b"), var("c")),
+        );
+        assert_eq!(print(&right), "a - (b - c);");
+
+        // comparisons cannot be chained
+        let eq = Expr::binary(
+            BinOp::Eq,
+            Expr::binary(BinOp::Lt, var("a"), var("b")),
+            var("c"),
+        );
+        assert_eq!(print(&eq), "(a < b) == c;");
+
+        let not = Expr::unary(UnOp::Not, Expr::binary(BinOp::And, var("a"), var("b")));
+        assert_eq!(print(&not), "!(a && b);");
+
+        let neg = Expr::unary(UnOp::Neg, var("a").method("abs", Vec::new()));
+        assert_eq!(print(&neg), "-a.abs();");
+
+        let method = Expr::unary(UnOp::Neg, var("a")).method("abs", Vec::new());
+        assert_eq!(print(&method), "(-a).abs();");
+
+        let reference = cast(var("a"), "u8").reference();
+        assert_eq!(print(&reference), "&(a as u8);");
+
+        let cast = cast(Expr::unary(UnOp::Neg, var("a")), "u8");
+        assert_eq!(print(&cast), "-a as u8;");
+
+        let mut printer = Printer {
+            out: String::new(),
+            indent: 0,
+        };
+        printer.expr(&sum, Precedence::Lowest);
+        assert_eq!(printer.out, "a + b");
+    }
+
+    #[test]
+    fn keywords() {
+        assert_eq!(print(&var("type")), "r#type;");
+        assert_eq!(
+            print(&Expr::Field(Box::new(var("a")), "match".to_string())),
+            "a.r#match;"
+        );
+        assert_eq!(print(&var("a").method("loop", Vec::new())), "a.r#loop();");
+        assert_eq!(print(&var("types")), "types;");
+
+        // the keywords which start a path cannot be raw identifiers
+        assert_eq!(
+            print(&Expr::call(super::Path::new("Self::new"), Vec::new())),
+            "Self::new();"
+        );
+        assert_eq!(print(&var("crate::get_key")), "crate::get_key;");
+        assert!(super::is_path_keyword("self"));
+        assert!(!super::is_path_keyword("type"));
+    }
+
+    #[test]
+    fn names() {
+        let expr = Expr::call(
+            super::Path::new("runtime::get_key"),
+            vec![var("a").method("try_into", Vec::new())],
+        );
+        let item = super::Item::Fn(super::Function {
+            attrs: Vec::new(),
+            linkage: super::Linkage::Private,
+            name: "f".to_string(),
+            generics: Vec::new(),
+            receiver: false,
+            params: Vec::new(),
+            returns: Some(Type::generic("Vec", vec![Type::path("U256")])),
+            body: Block::new(vec![Stmt::Expr(expr)]),
+        });
+
+        let names: Vec<String> = super::names(&[item]).into_iter().collect();
+
+        // only the first segment of a path is in scope
+        assert_eq!(names, vec!["U256", "Vec", "a", "runtime", "try_into"]);
+    }
+}
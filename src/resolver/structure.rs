@@ -0,0 +1,306 @@
+use output::Output;
+use parser::pt;
+use resolver::cfg::{ControlFlowGraph, Instr};
+
+/// Structured control flow recovered from a control flow graph. Targets which do not have
+/// `goto` (like Rust) can be generated from this.
+#[derive(Debug, PartialEq)]
+pub enum Stmt {
+    /// The instructions of a basic block, except for the branch which ends it
+    BasicBlock(usize),
+    /// The conditional branch which ends the basic block
+    If(usize, Vec<Stmt>, Vec<Stmt>),
+    /// Loop with the given loop header; `Continue` with the same basic block restarts it
+    Loop(usize, Vec<Stmt>),
+    /// Block which is followed by the given basic block; `Break` with the same basic block
+    /// exits it
+    Block(usize, Vec<Stmt>),
+    Break(usize),
+    Continue(usize),
+}
+
+/// Turn a reducible control flow graph into nested loops, blocks and ifs. Each basic block
+/// which can be reached from the entry block occurs exactly once.
+///
+/// This follows "Beyond Relooper" by Norman Ramsey: loop headers (the targets of back edges)
+/// become loops, and basic blocks with more than one forward predecessor (merge nodes) are
+/// placed after a block which encloses all the branches to it. All other basic blocks are
+/// dominated by their only predecessor and are placed at the branch.
+///
+/// An irreducible control flow graph has no such structure, so it is reported as an error at
+/// the location of its function.
+pub fn structure(
+    cfg: &ControlFlowGraph,
+    loc: &pt::Loc,
+    errors: &mut Vec<Output>,
+) -> Result<Vec<Stmt>, ()> {
+    match Structurer::new(cfg) {
+        Some(structurer) => Ok(structurer.do_tree(0)),
+        None => {
+            errors.push(Output::error(
+                *loc,
+                "irreducible control flow cannot be structured".to_string(),
+            ));
+            Err(())
+        }
+    }
+}
+
+struct Structurer<'a> {
+    cfg: &'a ControlFlowGraph,
+    rpo_index: Vec<usize>,
+    loop_header: Vec<bool>,
+    merge_node: Vec<bool>,
+    /// Children in the dominator tree, in reverse postorder
+    children: Vec<Vec<usize>>,
+}
+
+impl<'a> Structurer<'a> {
+    /// None if a loop can be entered other than through its loop header
+    fn new(cfg: &'a ControlFlowGraph) -> Option<Self> {
+        let count = cfg.bb.len();
+
+        let rpo = reverse_postorder(cfg);
+
+        let mut rpo_index = vec![std::usize::MAX; count];
+
+        for (i, bb) in rpo.iter().enumerate() {
+            rpo_index[*bb] = i;
+        }
+
+        let mut preds = vec![Vec::new(); count];
+
+        for bb in &rpo {
+            for succ in successors(cfg, *bb) {
+                preds[succ].push(*bb);
+            }
+        }
+
+        let idom = dominators(&rpo, &rpo_index, &preds);
+
+        let mut loop_header = vec![false; count];
+        let mut merge_node = vec![false; count];
+        let mut children = vec![Vec::new(); count];
+
+        for bb in &rpo {
+            let mut forward_preds = 0;
+
+            for pred in &preds[*bb] {
+                if rpo_index[*pred] >= rpo_index[*bb] {
+                    if !dominates(*bb, *pred, &idom) {
+                        return None;
+                    }
+
+                    loop_header[*bb] = true;
+                } else {
+                    forward_preds += 1;
+                }
+            }
+
+            merge_node[*bb] = forward_preds > 1;
+
+            if *bb != 0 {
+                children[idom[*bb]].push(*bb);
+            }
+        }
+
+        Some(Structurer {
+            cfg,
+            rpo_index,
+            loop_header,
+            merge_node,
+            children,
+        })
+    }
+
+    /// Generate the basic block and everything it dominates
+    fn do_tree(&self, bb: usize) -> Vec<Stmt> {
+        let merges: Vec<usize> = self.children[bb]
+            .iter()
+            .filter(|child| self.merge_node[**child])
+            .cloned()
+            .collect();
+
+        let mut stmts = vec![Stmt::BasicBlock(bb)];
+
+        stmts.extend(self.node_within(bb, &merges));
+
+        if self.loop_header[bb] {
+            vec![Stmt::Loop(bb, stmts)]
+        } else {
+            stmts
+        }
+    }
+
+    /// Generate the branch at the end of the basic block, enclosed in a block for each merge
+    /// node. The merge node which comes last is the outermost, so that any merge node can be
+    /// reached by breaking out of its block.
+    fn node_within(&self, bb: usize, merges: &[usize]) -> Vec<Stmt> {
+        match merges.split_last() {
+            Some((merge, rest)) => {
+                let mut stmts = vec![Stmt::Block(*merge, self.node_within(bb, rest))];
+
+                stmts.extend(self.do_tree(*merge));
+
+                stmts
+            }
+            None => match self.cfg.bb[bb].instr.last() {
+                Some(Instr::Branch { bb: dest }) => self.branch(bb, *dest),
+                Some(Instr::BranchCond { true_, false_, .. }) => vec![Stmt::If(
+                    bb,
+                    self.branch(bb, *true_),
+                    self.branch(bb, *false_),
+                )],
+                _ => Vec::new(),
+            },
+        }
+    }
+
+    fn branch(&self, from: usize, to: usize) -> Vec<Stmt> {
+        if self.rpo_index[to] <= self.rpo_index[from] {
+            vec![Stmt::Continue(to)]
+        } else if self.merge_node[to] {
+            vec![Stmt::Break(to)]
+        } else {
+            self.do_tree(to)
+        }
+    }
+}
+
+fn successors(cfg: &ControlFlowGraph, bb: usize) -> Vec<usize> {
+    match cfg.bb[bb].instr.last() {
+        Some(Instr::Branch { bb }) => vec![*bb],
+        Some(Instr::BranchCond { true_, false_, .. }) => vec![*true_, *false_],
+        _ => Vec::new(),
+    }
+}
+
+/// The basic blocks which are reachable from the entry block, in reverse postorder
+fn reverse_postorder(cfg: &ControlFlowGraph) -> Vec<usize> {
+    let mut visited = vec![false; cfg.bb.len()];
+    let mut postorder = Vec::new();
+    // depth first search without recursion; each entry is the block and its next successor
+    let mut stack = vec![(0, 0)];
+
+    visited[0] = true;
+
+    while let Some((bb, next)) = stack.pop() {
+        let succs = successors(cfg, bb);
+
+        if next < succs.len() {
+            stack.push((bb, next + 1));
+
+            let succ = succs[next];
+
+            if !visited[succ] {
+                visited[succ] = true;
+                stack.push((succ, 0));
+            }
+        } else {
+            postorder.push(bb);
+        }
+    }
+
+    postorder.reverse();
+
+    postorder
+}
+
+/// Immediate dominators, using "A Simple, Fast Dominance Algorithm" by Cooper, Harvey and
+/// Kennedy. The entry block is its own immediate dominator.
+fn dominators(rpo: &[usize], rpo_index: &[usize], preds: &[Vec<usize>]) -> Vec<usize> {
+    let mut idom = vec![std::usize::MAX; rpo_index.len()];
+
+    idom[0] = 0;
+
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+
+        for bb in rpo.iter().skip(1) {
+            let mut new_idom = std::usize::MAX;
+
+            for pred in &preds[*bb] {
+                if idom[*pred] == std::usize::MAX {
+                    continue;
+                }
+
+                new_idom = if new_idom == std::usize::MAX {
+                    *pred
+                } else {
+                    intersect(*pred, new_idom, &idom, rpo_index)
+                };
+            }
+
+            if idom[*bb] != new_idom {
+                idom[*bb] = new_idom;
+                changed = true;
+            }
+        }
+    }
+
+    idom
+}
+
+fn intersect(mut a: usize, mut b: usize, idom: &[usize], rpo_index: &[usize]) -> usize {
+    while a != b {
+        while rpo_index[a] > rpo_index[b] {
+            a = idom[a];
+        }
+        while rpo_index[b] > rpo_index[a] {
+            b = idom[b];
+        }
+    }
+
+    a
+}
+
+fn dominates(a: usize, mut b: usize, idom: &[usize]) -> bool {
+    loop {
+        if a == b {
+            return true;
+        }
+
+        if b == 0 {
+            return false;
+        }
+
+        b = idom[b];
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::structure;
+    use parser::pt::Loc;
+    use resolver::cfg::{ControlFlowGraph, Instr};
+    use resolver::expression::Expression;
+
+    #[test]
+    fn irreducible() {
+        let loc = Loc(0, 10, 20);
+        let mut cfg = ControlFlowGraph::new();
+        let left = cfg.new_basic_block("left".to_string());
+        let right = cfg.new_basic_block("right".to_string());
+
+        // the loop of left and right can be entered at either
+        cfg.bb[0].instr.push(Instr::BranchCond {
+            cond: Expression::BoolLiteral(loc, true),
+            true_: left,
+            false_: right,
+        });
+        cfg.bb[left].instr.push(Instr::Branch { bb: right });
+        cfg.bb[right].instr.push(Instr::Branch { bb: left });
+
+        let mut errors = Vec::new();
+
+        assert!(structure(&cfg, &loc, &mut errors).is_err());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message,
+            "irreducible control flow cannot be structured"
+        );
+        assert_eq!(errors[0].pos, loc);
+    }
+}
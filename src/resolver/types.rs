@@ -13,7 +13,8 @@ pub fn resolve(s: &pt::SourceUnit, target: Target) -> (Namespace, Vec<Output>) {
         match target {
             Target::Ewasm => 20,
             Target::Substrate => 32,
-            Target::Sabre => 0, // substrate has no address type
+            Target::Sabre => 0,       // substrate has no address type
+            Target::Casperlabs => 32, // account hash
         },
     );
     let mut structs = Vec::new();
@@ -26,17 +27,17 @@ pub fn resolve(s: &pt::SourceUnit, target: Target) -> (Namespace, Vec<Output>) {
             pt::SourceUnitPart::PragmaDirective(name, value) => {
                 if name.name == "solidity" {
                     errors.push(Output::info(
-                        pt::Loc(name.loc.0, value.loc.1),
+                        pt::Loc(name.loc.0, name.loc.1, value.loc.2),
                         "pragma ‘solidity’ is ignored".to_string(),
                     ));
                 } else if name.name == "experimental" && value.string == "ABIEncoderV2" {
                     errors.push(Output::info(
-                        pt::Loc(name.loc.0, value.loc.1),
+                        pt::Loc(name.loc.0, name.loc.1, value.loc.2),
                         "pragma ‘experimental’ with value ‘ABIEncoderV2’ is ignored".to_string(),
                     ));
                 } else {
                     errors.push(Output::warning(
-                        pt::Loc(name.loc.0, value.loc.1),
+                        pt::Loc(name.loc.0, name.loc.1, value.loc.2),
                         format!(
                             "unknown pragma ‘{}’ with value ‘{}’ ignored",
                             name.name, value.string
@@ -128,7 +129,8 @@ fn resolve_contract<'a>(
     ns: &mut Namespace,
 ) -> bool {
     let contract_no = ns.contracts.len();
-    ns.contracts.push(Contract::new(&def.name.name));
+    ns.contracts
+        .push(Contract::new(&def.name.name, def.ty, def.loc));
 
     let mut broken = !ns.add_symbol(
         None,
@@ -326,7 +328,7 @@ fn enum_256values_is_uint8() {
     let mut e = pt::EnumDefinition {
         doc: vec![],
         name: pt::Identifier {
-            loc: pt::Loc(0, 0),
+            loc: pt::Loc(0, 0, 0),
             name: "foo".into(),
         },
         values: Vec::new(),
@@ -335,7 +337,7 @@ fn enum_256values_is_uint8() {
     let mut ns = Namespace::new(Target::Ewasm, 20);
 
     e.values.push(pt::Identifier {
-        loc: pt::Loc(0, 0),
+        loc: pt::Loc(0, 0, 0),
         name: "first".into(),
     });
 
@@ -344,7 +346,7 @@ fn enum_256values_is_uint8() {
 
     for i in 1..256 {
         e.values.push(pt::Identifier {
-            loc: pt::Loc(0, 0),
+            loc: pt::Loc(0, 0, 0),
             name: format!("val{}", i),
         })
     }
@@ -356,7 +358,7 @@ fn enum_256values_is_uint8() {
     assert_eq!(ns.enums.last().unwrap().ty, Type::Uint(8));
 
     e.values.push(pt::Identifier {
-        loc: pt::Loc(0, 0),
+        loc: pt::Loc(0, 0, 0),
         name: "another".into(),
     });
 
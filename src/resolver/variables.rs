@@ -5,30 +5,26 @@ use resolver::cfg::{ControlFlowGraph, Instr, Storage, Vartable};
 use resolver::expression::{cast, expression, Expression};
 use resolver::ContractVariableType;
 
+/// Resolve the contract variables of a contract definition. The initializers are added to
+/// the given cfg, which is shared with the contract variables of any base contracts.
 pub fn contract_variables(
     def: &pt::ContractDefinition,
     contract_no: usize,
     ns: &mut Namespace,
+    cfg: &mut ControlFlowGraph,
+    vartab: &mut Vartable,
     errors: &mut Vec<Output>,
 ) -> bool {
     let mut broken = false;
-    let mut vartab = Vartable::new();
-    let mut cfg = ControlFlowGraph::new();
 
     for parts in &def.parts {
         if let pt::ContractPart::ContractVariableDefinition(ref s) = parts {
-            if !var_decl(s, contract_no, ns, &mut cfg, &mut vartab, errors) {
+            if !var_decl(s, contract_no, ns, cfg, vartab, errors) {
                 broken = true;
             }
         }
     }
 
-    cfg.add(&mut vartab, Instr::Return { value: Vec::new() });
-
-    cfg.vars = vartab.drain();
-
-    ns.contracts[contract_no].initializer = cfg;
-
     broken
 }
 
@@ -86,7 +82,7 @@ fn var_decl(
 
     let visibility = match visibility {
         Some(v) => v,
-        None => pt::Visibility::Private(pt::Loc(0, 0)),
+        None => pt::Visibility::Private(pt::Loc(0, 0, 0)),
     };
 
     let var = if !is_constant {
@@ -0,0 +1,2244 @@
+extern crate serde_json;
+extern crate solang;
+
+use solang::file_cache::FileCache;
+use solang::output;
+use solang::resolver::casperlabs::CasperlabsContract;
+use solang::{parse_and_resolve, parse_and_resolve_file, Target};
+
+fn first_error(errors: Vec<output::Output>) -> String {
+    match errors.iter().find(|m| m.level == output::Level::Error) {
+        Some(m) => m.message.to_owned(),
+        None => panic!("no errors found"),
+    }
+}
+
+fn render(src: &str) -> String {
+    let (ns, errors) = parse_and_resolve(src, Target::Casperlabs);
+
+    let ns = match ns {
+        Some(ns) => ns,
+        None => panic!("{:?}", errors),
+    };
+
+    // base contracts are defined before the contracts which inherit from them
+    match CasperlabsContract::new(ns.contracts.last().unwrap(), &ns).render() {
+        Ok(code) => code,
+        Err(errors) => panic!("{:?}", errors),
+    }
+}
+
+fn render_errors(src: &str) -> Vec<output::Output> {
+    let (ns, errors) = parse_and_resolve(src, Target::Casperlabs);
+
+    let ns = match ns {
+        Some(ns) => ns,
+        None => panic!("{:?}", errors),
+    };
+
+    match CasperlabsContract::new(ns.contracts.last().unwrap(), &ns).render() {
+        Ok(_) => panic!("no errors found"),
+        Err(errors) => errors,
+    }
+}
+
+#[test]
+fn target() {
+    let (ns, _) = parse_and_resolve(
+        "contract c {
+            function foo() public pure returns (bytes32) {
+                return blake2_256(hex\"01\");
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    let ns = ns.unwrap();
+
+    assert_eq!(ns.address_length, 32);
+    assert_eq!(ns.value_length, 64);
+
+    let (_, errors) = parse_and_resolve(
+        "contract c {
+            constructor(int32 a) public {}
+            constructor(bool a) public {}
+        }",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(first_error(errors), "constructor already defined");
+}
+
+#[test]
+fn emit_casperlabs() {
+    let dir = std::env::temp_dir().join("caspiler-tests");
+    std::fs::create_dir_all(&dir).unwrap();
+    let src = dir.join("emit.sol");
+    std::fs::write(&src, "contract c { function f() public pure {} }").unwrap();
+
+    // the target used to be selected with `--emit casperlabs`
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_solang"))
+        .arg("--emit")
+        .arg("casperlabs")
+        .arg(&src)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "warning: --emit casperlabs is deprecated, use --target casperlabs\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("fn f() {"));
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_solang"))
+        .arg("--emit")
+        .arg("casperlabs")
+        .arg("--target")
+        .arg("ewasm")
+        .arg(&src)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn simple_contract() {
+    let code = render(
+        "contract Counter {
+            uint64 count;
+
+            function inc() public {
+                count = count + 1;
+            }
+        }",
+    );
+
+    assert!(code.contains("mod Counter"));
+    assert!(code.contains("fn inc()"));
+}
+
+#[test]
+fn msg_sender() {
+    let code = render(
+        "contract c {
+            mapping (address => uint64) balances;
+            address owner;
+
+            constructor() public {
+                owner = msg.sender;
+                balances[msg.sender] = 100;
+            }
+        }",
+    );
+
+    assert!(code.contains("runtime::get_caller()"));
+    assert!(!code.contains("unknown_expresson"));
+
+    // the sender is payable, and compares with addresses which are not
+    let code = render(
+        "contract c {
+            address owner;
+
+            modifier onlyOwner() {
+                require(msg.sender == owner);
+                _;
+            }
+
+            function isOwner() public view returns (bool) {
+                return owner != msg.sender;
+            }
+
+            function transferOwnership(address newOwner) public onlyOwner {
+                owner = newOwner;
+            }
+        }",
+    );
+
+    assert!(
+        code.contains("require(runtime::get_caller() == get_key_or(\"owner\", AccountHash::new(Default::default())), 1);")
+    );
+    assert!(code.contains("return !(get_key_or(\"owner\", AccountHash::new(Default::default())) == runtime::get_caller());"));
+
+    let (_, errors) = parse_and_resolve(
+        "contract c {
+            function foo() public returns (address) {
+                return msg.sender;
+            }
+        }",
+        Target::Substrate,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "‘msg.sender’ is not supported on target Substrate"
+    );
+}
+
+#[test]
+fn events() {
+    let code = render(
+        "contract c {
+            event Transfer(address indexed from, address indexed to, uint64 value);
+            event Paused();
+
+            function transfer(address to, uint64 value) public {
+                emit Transfer(msg.sender, to, value);
+                emit Paused();
+            }
+        }",
+    );
+
+    assert!(code.contains("emit_event(\"Transfer\", vec![(\"from\", CLValue::from_t(runtime::get_caller()).unwrap_or_revert()), (\"to\", CLValue::from_t(to).unwrap_or_revert()), (\"value\", CLValue::from_t(value).unwrap_or_revert())]);"));
+    assert!(code.contains("emit_event(\"Paused\", vec![]);"));
+
+    let (_, errors) = parse_and_resolve(
+        "contract c {
+            event Transfer(address indexed from, address indexed to, uint64 value);
+
+            function transfer(address to) public {
+                emit Transfer(msg.sender, to);
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "event ‘Transfer’ expects 3 arguments, 2 provided"
+    );
+
+    let (_, errors) = parse_and_resolve(
+        "contract c {
+            function transfer(address to) public {
+                emit Transfer(msg.sender, to);
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(first_error(errors), "event ‘Transfer’ not found");
+
+    let (_, errors) = parse_and_resolve(
+        "contract c {
+            event Foo(bool indexed a, bool indexed b, bool indexed c, bool indexed d);
+        }",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "event ‘Foo’ has 4 indexed fields where 3 is the maximum"
+    );
+
+    let (_, errors) = parse_and_resolve(
+        "contract c {
+            event Foo(bool a, int64 a);
+        }",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "event ‘Foo’ has duplicate field name ‘a’"
+    );
+}
+
+#[test]
+fn modifiers() {
+    let code = render(
+        "contract c {
+            address owner;
+            uint64 count;
+
+            modifier only(address a) {
+                count = 1;
+                _;
+                count = 2;
+            }
+
+            function inc() public only(owner) {
+                count = 3;
+            }
+        }",
+    );
+
+    // the function body is inlined at the placeholder
+    assert!(code.contains(
+        "        let a: AccountHash = get_key_or(\"owner\", AccountHash::new(Default::default()));
+        count = 1;
+        set_key(\"count\", count);
+        count = 3;
+        set_key(\"count\", count);
+        count = 2;
+        set_key(\"count\", count);
+"
+    ));
+
+    let code = render(
+        "contract c {
+            uint64 count;
+
+            modifier positive(uint64 a) {
+                require(a > 0);
+                _;
+            }
+
+            function set(uint64 a) public positive(2) {
+                count = a;
+            }
+        }",
+    );
+
+    // the parameter of the modifier does not shadow the parameter of the function
+    assert!(code.contains(
+        "    fn set(a: u64) {
+        let a_1: u64 = 2;
+        require(a_1 > 0, 1);
+        let count: u64 = a;
+"
+    ));
+
+    let (_, errors) = parse_and_resolve(
+        "contract c {
+            function inc() public onlyOwner {}
+        }",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(first_error(errors), "unknown modifier ‘onlyOwner’");
+
+    let (_, errors) = parse_and_resolve(
+        "contract c {
+            modifier only(address a) {
+                _;
+            }
+
+            function inc() public only {}
+        }",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "modifier ‘only’ expects 1 arguments, 0 provided"
+    );
+
+    let (_, errors) = parse_and_resolve(
+        "contract c {
+            function inc() public {
+                _;
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "placeholder statement ‘_’ only allowed in modifier"
+    );
+}
+
+#[test]
+fn inheritance() {
+    let code = render(
+        "contract A {
+            uint64 a;
+
+            constructor(uint64 x) public {
+                a = x;
+            }
+
+            function foo() public virtual {
+                a = 1;
+            }
+        }
+
+        contract B is A(5) {
+            uint64 b;
+
+            function foo() public override {
+                b = 2;
+                super.foo();
+            }
+        }",
+    );
+
+    // the base constructor and the overridden function are only called internally
+    assert!(code.contains(
+        "    fn constructor_0(x: u64) {
+        let a: u64 = x;
+        set_key(\"a\", a);
+"
+    ));
+    assert!(code.contains(
+        "    fn foo_0() {
+        let a: u64 = 1;
+        set_key(\"a\", a);
+"
+    ));
+    assert!(code.contains(
+        "    fn foo() {
+        let b: u64 = 2;
+        set_key(\"b\", b);
+        foo_0();
+"
+    ));
+    assert!(code.contains(
+        "    #[casperlabs_constructor]
+    fn constructor() {
+        constructor_0(5);
+"
+    ));
+
+    let (_, errors) = parse_and_resolve(
+        "contract A {
+            function foo() public {}
+        }
+
+        contract B is A {
+            function foo() public override {}
+        }",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "function ‘foo’ overrides function which is not virtual"
+    );
+
+    let (_, errors) = parse_and_resolve(
+        "contract A {
+            function foo() public virtual {}
+        }
+
+        contract B is A {
+            function foo() public {}
+        }",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "function ‘foo’ should specify ‘override’"
+    );
+
+    let (ns, errors) = parse_and_resolve(
+        "contract C is B {
+            function foo() public override {}
+        }
+
+        contract B is A {}
+
+        contract A {
+            function foo() public virtual {}
+        }",
+        Target::Casperlabs,
+    );
+
+    // base contracts may be defined after the contracts which inherit from them
+    let ns = match ns {
+        Some(ns) => ns,
+        None => panic!("{:?}", errors),
+    };
+    assert_eq!(ns.contracts[0].bases, vec![1, 2]);
+    assert_eq!(ns.contracts[1].bases, vec![2]);
+
+    let (_, errors) = parse_and_resolve(
+        "contract B is A {}
+
+        contract A is B {}",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "contract ‘A’ cannot inherit from ‘B’, which inherits from ‘A’"
+    );
+
+    let (_, errors) = parse_and_resolve(
+        "contract A {}
+
+        contract B is A {}
+
+        contract C is B, A {}",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "linearization of inheritance graph impossible for contract ‘C’"
+    );
+
+    let (_, errors) = parse_and_resolve(
+        "contract A {
+            constructor(uint64 x) public {}
+        }
+
+        contract B is A {}",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "constructor of base contract ‘A’ expects 1 arguments, 0 provided"
+    );
+
+    let (_, errors) = parse_and_resolve(
+        "contract A {
+            uint64 a;
+        }
+
+        contract B is A {
+            uint64 a;
+        }",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "a is already defined as a contract variable"
+    );
+}
+
+#[test]
+fn imports() {
+    let mut cache = FileCache::new();
+
+    cache.set_file_contents(
+        "lib/ownable.sol",
+        "import \"./counter.sol\";
+
+        contract Ownable is Counter {
+            address owner;
+
+            modifier onlyOwner() {
+                _;
+            }
+        }"
+        .to_string(),
+    );
+    cache.set_file_contents(
+        "lib/counter.sol",
+        "contract Counter {
+            uint64 count;
+        }"
+        .to_string(),
+    );
+    let file_no = cache.set_file_contents(
+        "main.sol",
+        "import \"@oz/ownable.sol\";
+        import \"lib/counter.sol\";
+
+        contract c is Ownable {
+            function inc() public onlyOwner {
+                count = count + 1;
+            }
+        }"
+        .to_string(),
+    );
+    cache.add_remapping("@oz/", "lib/");
+
+    let (ns, errors) = parse_and_resolve_file(file_no, &mut cache, Target::Casperlabs);
+
+    let ns = match ns {
+        Some(ns) => ns,
+        None => panic!("{:?}", errors),
+    };
+
+    // every file is only included once
+    assert_eq!(ns.contracts.len(), 3);
+    assert_eq!(ns.contracts[0].name, "Counter");
+
+    let code = CasperlabsContract::new(&ns.contracts[2], &ns)
+        .render()
+        .unwrap();
+
+    assert!(code.contains("fn inc()"));
+
+    let mut cache = FileCache::new();
+
+    cache.set_file_contents(
+        "lib.sol",
+        "contract A {
+            function foo() public {
+                bar();
+            }
+        }"
+        .to_string(),
+    );
+    let file_no = cache.set_file_contents(
+        "main.sol",
+        "import \"lib.sol\";
+
+        contract B is A {}"
+            .to_string(),
+    );
+
+    let (_, errors) = parse_and_resolve_file(file_no, &mut cache, Target::Casperlabs);
+
+    let json = output::message_as_json(&cache, &errors);
+
+    assert_eq!(json[0].message, "unknown function or type");
+    assert_eq!(json[0].sourceLocation.file, "lib.sol");
+
+    let (_, errors) = parse_and_resolve(
+        "import \"missing.sol\";
+
+        contract c {}",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(first_error(errors), "file ‘missing.sol’ not found");
+}
+
+#[test]
+fn control_flow() {
+    let code = render(
+        "contract c {
+            uint64 count;
+
+            function foo(uint64 n) public {
+                uint64 i = 0;
+
+                do {
+                    i = i + 1;
+
+                    if (i == 5) {
+                        continue;
+                    }
+
+                    if (i > n) {
+                        break;
+                    } else if (i == 3) {
+                        return;
+                    }
+
+                    count = i;
+                } while (i < 10);
+
+                count = 100;
+            }
+        }",
+    );
+
+    // variables which are set more than once are declared first
+    assert!(code.contains(
+        "        let mut i: u64;
+        let mut count: u64;
+        i = 0;
+"
+    ));
+
+    // the continue and the end of the body go to the condition of the do-while
+    assert!(code.contains(
+        "        'loop_1: loop {
+            i = i.wrapping_add(1);
+            'block_3: loop {
+                'block_2: loop {
+                    if i == 5 {
+                        break 'block_2;
+                    } else if i > n {
+                        break 'block_3;
+                    } else if i == 3 {
+                        return;
+                    } else {
+                        count = i;
+                        set_key(\"count\", count);
+                        break 'block_2;
+                    }
+                }
+                if i < 10 {
+                    continue 'loop_1;
+                } else {
+                    break 'block_3;
+                }
+            }
+            count = 100;
+            set_key(\"count\", count);
+            return;
+        }
+"
+    ));
+}
+
+#[test]
+fn mutable_variables() {
+    let code = render(
+        "contract c {
+            function count(uint64 n) public pure returns (uint64) {
+                uint64 i = 0;
+                while (i < n) {
+                    i = i + 1;
+                }
+                return i;
+            }
+
+            function dec(uint64 a) internal pure returns (uint64) {
+                a = a - 1;
+                return a;
+            }
+
+            function sum(uint64 n) public pure returns (uint64) {
+                uint64 s = 0;
+                for (uint64 i = 0; i < n; i++) {
+                    s += i;
+                }
+                for (uint32 i = 0; i < 3; i++) {
+                    s += i;
+                }
+                return s;
+            }
+        }",
+    );
+
+    assert!(code.contains(
+        "    fn count_inner(n: u64) -> u64 {
+        let mut i: u64;
+        i = 0;
+        'loop_1: loop {
+            if i < n {
+                i = i.wrapping_add(1);
+                continue 'loop_1;
+            } else {
+                return i;
+            }
+        }
+    }"
+    ));
+
+    // arguments which are set are mutable parameters
+    assert!(code.contains(
+        "    fn dec(mut a: u64) -> u64 {
+        a = a.wrapping_sub(1);
+        return a;
+    }"
+    ));
+
+    // variables of sibling scopes with the same name are distinct
+    assert!(code.contains("        let mut i: u64;\n"));
+    assert!(code.contains("        let mut i_4: u32;\n"));
+    assert!(code.contains("s = s.wrapping_add(i_4 as u64);"));
+
+    // variables which are set in a loop or a branch and read after it start as zero
+    let code = render(
+        "contract c {
+            function last(uint64 n) public pure returns (uint64) {
+                uint64 last;
+                for (uint64 i = 0; i < n; i++) {
+                    last = i;
+                }
+                return last;
+            }
+
+            function pick(bool c) public pure returns (uint64) {
+                uint64 x;
+                if (c) {
+                    x = 1;
+                }
+                return x;
+            }
+        }",
+    );
+
+    assert!(code.contains(
+        "    fn last_inner(n: u64) -> u64 {
+        let mut last: u64 = Default::default();
+        let mut i: u64;
+        i = 0;
+        'loop_2: loop {
+            if i < n {
+                last = i;"
+    ));
+
+    assert!(code.contains(
+        "    fn pick_inner(c: bool) -> u64 {
+        let mut x: u64 = Default::default();
+        'block_2: loop {
+            if c {
+                x = 1;
+                break 'block_2;
+            } else {
+                break 'block_2;
+            }
+        }
+        return x;
+    }"
+    ));
+
+    let code = render(
+        "contract c {
+            function get(uint64 self, uint64 self_0) public pure returns (uint64) {
+                uint64 Self = self + self_0;
+                return Self;
+            }
+        }",
+    );
+
+    // names which cannot be raw identifiers are renamed like a clash, without clashing themselves
+    assert!(code.contains(
+        "    fn get_inner(self_0_: u64, self_0: u64) -> u64 {
+        let Self_2: u64 = self_0_.wrapping_add(self_0);
+        return Self_2;
+    }"
+    ));
+
+    let errors = render_errors(
+        "contract c {
+            struct S {
+                uint64 crate;
+            }
+
+            S s;
+
+            function self() public {}
+        }",
+    );
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(
+        errors[0].message,
+        "the name ‘self’ is not supported on target CasperLabs"
+    );
+    assert_eq!(
+        errors[1].message,
+        "the name ‘crate’ is not supported on target CasperLabs"
+    );
+}
+
+#[test]
+fn expressions() {
+    let code = render(
+        "contract c {
+            uint64 a;
+
+            function foo(uint64 b, uint64 c) public {
+                a = (b + c) * (b - c) - b / c;
+                a = (b | c) & (b ^ c) | b % c;
+                a = b > c ? b - c : c - b;
+            }
+        }",
+    );
+
+    // arithmetic wraps on overflow like solidity
+    assert!(
+        code.contains("a = b.wrapping_add(c).wrapping_mul(b.wrapping_sub(c)).wrapping_sub(b / c);")
+    );
+    // parentheses are only printed where the precedence requires them
+    assert!(code.contains("a = (b | c) & (b ^ c) | b % c;"));
+    assert!(code.contains(
+        "        a = if b > c {
+            b.wrapping_sub(c)
+        } else {
+            c.wrapping_sub(b)
+        };
+"
+    ));
+}
+
+#[test]
+fn structs() {
+    let code = render(
+        "contract c {
+            struct Order {
+                uint64 price;
+                address owner;
+            }
+
+            Order best;
+            uint64 count;
+            mapping(uint64 => Order) orders;
+
+            function place(uint64 id, uint64 price) public {
+                orders[id] = Order(price, msg.sender);
+                best.price = price;
+                Order memory o = orders[id];
+                count = o.price;
+            }
+
+            function reprice(uint64 id, uint64 price) public {
+                Order storage o = orders[id];
+                o.price = price;
+            }
+        }",
+    );
+
+    assert!(code.contains(
+        "#[derive(Clone, Debug, PartialEq)]
+struct Order {
+    price: u64,
+    owner: AccountHash,
+}
+"
+    ));
+    // an account hash has no default
+    assert!(code.contains(
+        "impl Default for Order {
+    fn default() -> Order {
+        Order { price: Default::default(), owner: AccountHash::new(Default::default()) }
+    }
+}
+"
+    ));
+    assert!(code.contains(
+        "    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut result = Vec::new();
+        result.extend(self.price.to_bytes()?);
+        result.extend(self.owner.to_bytes()?);
+        Ok(result)
+    }
+"
+    ));
+    assert!(code.contains(
+        "        let (field_0, bytes) = FromBytes::from_bytes(bytes)?;
+        let (field_1, bytes) = FromBytes::from_bytes(bytes)?;
+        Ok((Order { price: field_0, owner: field_1 }, bytes))
+"
+    ));
+
+    // struct members in storage are set by updating the whole struct
+    assert!(code.contains(
+        "        {
+            let mut value = get_key::<Order>(\"best\");
+            value.price = temp5;
+            set_key(\"best\", value);
+        }
+"
+    ));
+    assert!(code.contains("let o: Order = get_key::<Order>(&new_key(\"orders\", &id));"));
+    assert!(code.contains("let count: u64 = o.price;"));
+    // best takes two storage slots, so the slot of count is not its index
+    assert!(code.contains("set_key(\"count\", count);"));
+    // a storage reference is rendered as the key of its slot where it is used
+    assert!(code.contains(
+        "            let mut value = get_key::<Order>(&new_key(\"orders\", &id));
+            value.price = temp4;
+            set_key(&new_key(\"orders\", &id), value);
+"
+    ));
+}
+
+#[test]
+fn mappings() {
+    let code = render(
+        "contract c {
+            mapping(uint256 => mapping(address => mapping(string => uint64))) deep;
+            mapping(uint64 => uint64[3]) fixed;
+
+            function f(uint256 i, string s) public {
+                deep[i][msg.sender][s] = 1;
+                fixed[5][1] = 2;
+            }
+        }",
+    );
+
+    assert!(code.contains(
+        "set_key(&new_key(&new_key(&new_key(\"deep\", &i), &runtime::get_caller()), &s), temp3);"
+    ));
+    // literal keys are serialized as the key type
+    assert!(code.contains("let mut value = get_key::<[u64; 3]>(&new_key(\"fixed\", &5u64));"));
+    assert!(code.contains("value[indextemp5 as usize] = temp6;"));
+    assert!(code.contains(
+        "fn new_key<T: ToBytes>(name: &str, key: &T) -> String {
+    let mut result = String::from(name);
+    result.push('[');
+    for byte in key.to_bytes().unwrap_or_revert() {
+        result.push_str(&format!(\"{:02x}\", byte));
+    }
+    result.push(']');
+    result
+}
+"
+    ));
+}
+
+#[test]
+fn dynamic_arrays() {
+    let code = render(
+        "contract c {
+            uint64[] values;
+            bytes data;
+
+            function add(uint64 v) public {
+                values.push(v);
+                data.push(0x01);
+            }
+
+            function remove() public {
+                values.pop();
+            }
+
+            function set(uint256 i, uint64 v) public {
+                values[i] = v;
+            }
+
+            function mem(uint32 n) public {
+                uint64[] memory a = new uint64[](n);
+                uint32 l = a.length;
+            }
+
+            function pick(uint64[] memory a, uint64 i) public pure returns (uint64) {
+                return a[i];
+            }
+
+            function owner(uint256 w) public pure returns (address, uint160) {
+                address a = address(uint160(w));
+                return (a, uint160(a));
+            }
+        }",
+    );
+
+    // the length and each element are stored under keys of their own
+    assert!(code.contains("let temp2: U256 = get_key::<U256>(\"values\");"));
+    assert!(code.contains("set_key(&format!(\"values[{}]\", temp2), temp4);"));
+    assert!(code.contains("set_key(\"values\", temp5);"));
+    assert!(code.contains("runtime::remove_key(&format!(\"values[{}]\", temp2));"));
+    assert!(code.contains("set_key(&format!(\"values[{}]\", indextemp3), temp4);"));
+    assert!(code.contains(
+        "        {
+            let mut bytes = get_key::<Vec<u8>>(\"data\");
+            bytes.push(1);
+            set_key(\"data\", bytes);
+        }
+"
+    ));
+
+    assert!(code.contains("let a: Vec<u64> = vec![Default::default(); n as usize];"));
+    assert!(code.contains("let l: u32 = a.len() as u32;"));
+    // the index is compared with the length in the wider of their types
+    assert!(code.contains("if indextemp2 >= a.len() as u32 as u64 {"));
+}
+
+#[test]
+fn error_codes() {
+    let mut cache = FileCache::new();
+    let file_no = cache.set_file_contents(
+        "test.sol",
+        "contract Child {
+    constructor(uint64 a) public {
+        require(a != 7, \"seven\");
+    }
+}
+
+contract c {
+    function f(uint64 a) public {
+        require(a > 1, \"too small\");
+        assert(a != 5);
+        if (a == 7) {
+            revert(\"seven\");
+        }
+        require(a < 100, \"too small\");
+    }
+
+    function g() public {
+        new Child(1);
+    }
+}"
+        .to_string(),
+    );
+
+    let (ns, errors) = parse_and_resolve_file(file_no, &mut cache, Target::Casperlabs);
+    let ns = match ns {
+        Some(ns) => ns,
+        None => panic!("{:?}", errors),
+    };
+    let contract = CasperlabsContract::new(&ns.contracts[1], &ns);
+    let code = contract.render().unwrap();
+
+    // the reasons are numbered from 2 in order of first appearance, and the
+    // calls without a reason share code 1
+    assert!(code.contains("        require(a > 1, 3);\n        assert(!(a == 5), 1);\n"));
+    assert!(code.contains("        if a == 7 {\n            revert(2);\n        }"));
+    assert!(code.contains("        require(a < 100, 3);\n"));
+    // so a created contract in the same crate reverts with the same code for the same reason
+    assert!(code.contains("        require(!(a == 7), 2);\n"));
+    assert!(code.contains(
+        "fn assert(condition: bool, code: u16) {
+    if !condition {
+        runtime::revert(ApiError::User(code));
+    }
+}
+"
+    ));
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&contract.render_error_manifest(&cache)).unwrap();
+
+    assert_eq!(manifest.as_array().unwrap().len(), 3);
+    assert_eq!(manifest[0]["code"], 1);
+    assert_eq!(manifest[0]["reason"], serde_json::Value::Null);
+    assert_eq!(manifest[1]["code"], 2);
+    assert_eq!(manifest[1]["reason"], "seven");
+    assert_eq!(manifest[1]["locations"][0]["location"], "test.sol:12:20-27");
+    assert_eq!(manifest[1]["locations"][1]["location"], "test.sol:3:25-32");
+    assert_eq!(manifest[2]["code"], 3);
+    assert_eq!(manifest[2]["reason"], "too small");
+    assert_eq!(manifest[2]["locations"].as_array().unwrap().len(), 2);
+    assert_eq!(manifest[2]["locations"][0]["location"], "test.sol:9:24-35");
+
+    // the created contract is numbered after the reasons of the crate it is part of
+    let child = CasperlabsContract::new(&ns.contracts[0], &ns);
+
+    assert!(child
+        .render()
+        .unwrap()
+        .contains("        require(!(a == 7), 2);\n"));
+
+    let code = render(
+        "contract c {
+            function f(uint64 a) public pure {
+                require(a > 1, \"error 174\");
+                require(a > 2, \"error 198\");
+                require(a > 3, \"error 174\");
+            }
+        }",
+    );
+
+    // different reasons never share a code
+    assert!(code.contains(
+        "        require(a > 1, 2);\n        require(a > 2, 3);\n        require(a > 3, 2);\n"
+    ));
+
+    let code = render(
+        "contract c {
+            function f(uint64 a) public pure {
+                require(a > 1, \"zebra\");
+                require(a > 2, \"apple\");
+                require(a > 3, \"aardvark\");
+            }
+        }",
+    );
+
+    // a reason keeps its code when reasons are added after it
+    assert!(code.contains(
+        "        require(a > 1, 2);\n        require(a > 2, 3);\n        require(a > 3, 4);\n"
+    ));
+
+    let code = render(
+        "contract c {
+            function f(uint64 a) public pure returns (uint64) {
+                if (a == 7) {
+                    revert(\"seven\");
+                }
+                return a + 1;
+            }
+        }",
+    );
+
+    // `revert()` does not return, so it can end a branch of a function which returns a value
+    assert!(code.contains("fn revert(code: u16) -> ! {"));
+    assert!(code.contains(
+        "        if a == 7 {
+            revert(2);
+        } else {
+            return a.wrapping_add(1);
+        }
+"
+    ));
+}
+
+#[test]
+fn too_many_reasons() {
+    let requires: String = (0..65535)
+        .map(|i| format!("require(a > 1, \"{}\");\n", i))
+        .collect();
+    let errors = render_errors(&format!(
+        "contract c {{
+            function f(uint64 a) public pure {{
+                {}
+            }}
+        }}",
+        requires
+    ));
+
+    // codes 0 and 1 are taken, which leaves one code too few
+    assert_eq!(
+        first_error(errors),
+        "crate of contract ‘c’ has 65535 distinct reason strings, but only 65534 user error codes"
+    );
+}
+
+#[test]
+fn external_calls() {
+    let code = render(
+        "interface Token {
+            function transfer(address to, uint64 amount) external returns (bool);
+            function reserves() external view returns (uint64, uint64);
+            function burn() external;
+        }
+
+        contract Shop {
+            Token token;
+
+            function pay(address to, uint64 amount) public {
+                require(token.transfer(to, amount), \"transfer failed\");
+            }
+
+            function total(address t) public view returns (uint64) {
+                (uint64 a, uint64 b) = Token(t).reserves();
+                return a + b;
+            }
+
+            function burn() public {
+                token.burn();
+            }
+        }",
+    );
+
+    // contracts are called by their contract hash, and their entry points
+    // read the arguments by name
+    assert!(code.contains(
+        "let return_data: bool = runtime::call_contract(get_key::<ContractHash>(\"token\"), \"transfer\", runtime_args! { \"to\" => to, \"amount\" => amount });"
+    ));
+    assert!(code.contains(
+        "let return_data: (u64, u64) = runtime::call_contract(t.value(), \"reserves\", runtime_args! {});"
+    ));
+    assert!(code.contains("let (temp1, temp2): (u64, u64) = return_data;"));
+    assert!(code.contains(
+        "runtime::call_contract::<()>(get_key::<ContractHash>(\"token\"), \"burn\", runtime_args! {});"
+    ));
+}
+
+#[test]
+fn create_contracts() {
+    let code = render(
+        "contract Child {
+            uint64 x;
+
+            constructor(uint64 v) public {
+                x = v;
+            }
+
+            function inc(uint64 by) public {
+                x = x + by;
+            }
+        }
+
+        contract Factory {
+            Child child;
+
+            function make(uint64 v) public {
+                child = new Child(v);
+            }
+        }",
+    );
+
+    assert!(code.contains("let temp1: ContractHash = new_Child(v);"));
+
+    // the created contract is part of the crate of its creator
+    assert!(
+        code.contains("mod Child {\n    use super::*;\n\n    pub(super) fn constructor(v: u64) {")
+    );
+    assert!(code.contains(
+        "#[no_mangle]
+pub extern \"C\" fn inc() {
+    Child::inc(runtime::get_named_arg(\"by\"));
+}"
+    ));
+    assert!(code.contains(
+        "entry_points.add_entry_point(EntryPoint::new(\"Child_constructor\", vec![Parameter::new(\"v\", cl_type::<u64>())], CLType::Unit, EntryPointAccess::Groups(vec![Group::new(\"constructor\")]), EntryPointType::Contract));"
+    ));
+    assert!(code.contains(
+        "    runtime::call_contract::<()>(contract_hash, \"Child_constructor\", runtime_args! { \"v\" => v });
+    contract_hash
+}"
+    ));
+
+    let errors = render_errors(
+        "contract Child {
+            function get() public pure returns (uint64) {
+                return 1;
+            }
+        }
+
+        contract Factory {
+            function get() public pure returns (uint64) {
+                return 2;
+            }
+
+            function make() public {
+                new Child();
+            }
+        }",
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "entry point ‘get’ of contract ‘Child’ clashes with another entry point of the crate of contract ‘Factory’"
+    );
+}
+
+#[test]
+fn return_values() {
+    let code = render(
+        "contract c {
+            uint64 x;
+
+            function pair(uint64 a) internal pure returns (uint64, uint64) {
+                return (a, a + 1);
+            }
+
+            function get() public view returns (uint64) {
+                return x;
+            }
+
+            function sum(uint64 a) public view returns (uint64) {
+                (uint64 p, uint64 q) = pair(a);
+                return p + q + get();
+            }
+        }",
+    );
+
+    assert!(code.contains(
+        "    fn pair(a: u64) -> (u64, u64) {
+        return (a, a.wrapping_add(1));
+    }"
+    ));
+
+    // the contract macro does not return values, so the method does
+    assert!(code.contains(
+        "    #[casperlabs_method]
+    fn get() {
+        runtime::ret(CLValue::from_t(get_inner()).unwrap_or_revert());
+    }
+
+    fn get_inner() -> u64 {
+        return get_key::<u64>(\"x\");
+    }"
+    ));
+    assert!(code.contains("let (temp1, temp2): (u64, u64) = pair(a);"));
+    assert!(code.contains("let temp5: u64 = get_inner();"));
+
+    let errors = render_errors(
+        "contract c {
+            function four() public pure returns (uint64, uint64, uint64, uint64) {
+                return (1, 2, 3, 4);
+            }
+        }",
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "returning more than three values from an entry point is not supported on target CasperLabs"
+    );
+}
+
+#[test]
+fn payable() {
+    let code = render(
+        "contract Bank {
+            mapping(address => uint64) deposits;
+
+            function credit(address who) internal {
+                deposits[who] = deposits[who] + uint64(msg.value);
+            }
+
+            function deposit() public payable {
+                credit(msg.sender);
+            }
+
+            function withdraw(uint64 amount) public {
+                deposits[msg.sender] = deposits[msg.sender] - amount;
+                payable(msg.sender).transfer(amount);
+            }
+
+            function total() public view returns (uint64) {
+                return uint64(address(this).balance);
+            }
+        }",
+    );
+
+    // the entry point takes a purse, whose motes are moved to the contract purse
+    assert!(code.contains(
+        "    #[casperlabs_method]
+    fn deposit(purse: URef) {
+        deposit_inner(deposit_purse(purse));
+    }
+
+    fn deposit_inner(value: U512) {
+        credit(runtime::get_caller(), value);"
+    ));
+    assert!(code.contains("fn credit(who: AccountHash, value: U512) {"));
+    assert!(code.contains(".wrapping_add(value.low_u64());"));
+    assert!(code.contains(
+        "system::transfer_from_purse_to_account(contract_purse(), runtime::get_caller(), U512::from(amount)).unwrap_or_revert();"
+    ));
+    assert!(
+        code.contains("return system::get_balance(contract_purse()).unwrap_or_revert().low_u64();")
+    );
+
+    let code = render(
+        "interface Vault {
+            function store() external payable;
+        }
+
+        contract User {
+            Vault vault;
+
+            function put(uint64 amount) public {
+                vault.store{value: amount}();
+            }
+        }",
+    );
+
+    assert!(code.contains(
+        "runtime::call_contract::<()>(get_key::<ContractHash>(\"vault\"), \"store\", runtime_args! { \"purse\" => new_purse(U512::from(amount)) });"
+    ));
+
+    let (_, errors) = parse_and_resolve(
+        "contract c {
+            function foo() public returns (uint64) {
+                return uint64(msg.value);
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "‘msg.value’ can only be used in payable public functions"
+    );
+}
+
+#[test]
+fn chain_globals() {
+    let code = render(
+        "contract Vesting {
+            uint64 start;
+
+            constructor() public {
+                start = block.timestamp;
+            }
+
+            function vested() public view returns (bool) {
+                return now >= start + 1 days;
+            }
+        }",
+    );
+
+    // the block time is in milliseconds
+    assert!(code.contains("let start: u64 = Into::<u64>::into(runtime::get_blocktime()) / 1000;"));
+    assert!(code.contains(
+        "return Into::<u64>::into(runtime::get_blocktime()) / 1000 >= get_key::<u64>(\"start\").wrapping_add(86400);"
+    ));
+
+    let (ns, _) = parse_and_resolve(
+        "contract Registry {
+            Registry me;
+
+            constructor() public {
+                me = this;
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    let ns = ns.unwrap();
+    let mut contract = CasperlabsContract::new(&ns.contracts[0], &ns);
+
+    assert_eq!(
+        first_error(contract.render().unwrap_err()),
+        "‘this’ requires --no-contract-macro on target CasperLabs"
+    );
+
+    // the installer stores the contract hash for the contract
+    contract.contract_macro = false;
+    let code = contract.render().unwrap();
+
+    assert!(code.contains("let me: ContractHash = get_key::<ContractHash>(\"__this\");"));
+    assert!(code.contains(
+        "    let (contract_hash, _) = storage::add_contract_version(package_hash, entry_points, named_keys);
+    storage::write(this, contract_hash);"
+    ));
+
+    let (_, errors) = parse_and_resolve(
+        "contract c {
+            function foo() public view returns (address) {
+                return block.coinbase;
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "‘block.coinbase’ is not supported on target CasperLabs"
+    );
+
+    // the caller of a contract called by another contract is that contract,
+    // and not the account which signed the deploy
+    let (_, errors) = parse_and_resolve(
+        "contract c {
+            function foo() public view returns (address) {
+                return tx.origin;
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "‘tx.origin’ is not supported on target CasperLabs"
+    );
+}
+
+#[test]
+fn hash_functions() {
+    let (ns, _) = parse_and_resolve(
+        "contract Commit {
+            mapping(address => bytes32) commits;
+
+            function reveal(bytes memory secret) public view returns (bool) {
+                return keccak256(secret) == commits[msg.sender];
+            }
+
+            function id(bytes memory b) public pure returns (bytes32) {
+                return blake2_256(b);
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    let ns = ns.unwrap();
+
+    let files = CasperlabsContract::new(&ns.contracts[0], &ns)
+        .render_crate()
+        .unwrap();
+
+    // only the hash functions which are used are linked
+    assert!(files[0]
+        .1
+        .contains("sha3 = { version = \"=0.9.0\", default-features = false }\n"));
+    assert!(files[0]
+        .1
+        .contains("blake2 = { version = \"=0.9.0\", default-features = false }\n"));
+    assert!(!files[0].1.contains("sha2"));
+
+    // as the casperlabs crates depend on another version of blake2, its
+    // version is given
+    assert!(files[1].1.contains(
+        "dependencies = [
+ \"blake2 0.9.0\",
+ \"casperlabs-contract\",
+ \"casperlabs-types\",
+ \"digest 0.9.0\",
+ \"sha3\",
+]
+"
+    ));
+    assert!(!files[1].1.contains("name = \"sha2\""));
+
+    let code = &files[3].1;
+
+    assert!(code.contains("let hashtemp1: [u8; 32] = keccak256(&secret);"));
+    assert!(code.contains("let hashtemp1: [u8; 32] = blake2_256(&b);"));
+    assert!(code.contains(
+        "fn keccak256(bs: &[u8]) -> [u8; 32] {
+    sha3::Keccak256::digest(bs).into()
+}"
+    ));
+    assert!(code.contains(
+        "fn blake2_256(bs: &[u8]) -> [u8; 32] {
+    let mut hasher = blake2::VarBlake2b::new(32).ok().unwrap_or_revert();
+    hasher.update(bs);
+    let mut result: [u8; 32] = Default::default();
+    hasher.finalize_variable(|hash| result.copy_from_slice(hash));
+    result
+}"
+    ));
+    assert!(code.contains("use digest::{Digest, Update, VariableOutput};"));
+    assert!(!code.contains("fn sha256"));
+}
+
+#[test]
+fn enums() {
+    let code = render(
+        "contract Machine {
+            enum State { Idle, Running, Stopped }
+            State state;
+            mapping(State => uint64) counts;
+
+            function start(State s) public returns (State) {
+                State old = state;
+                state = State.Running;
+                counts[s] += 1;
+                return old;
+            }
+
+            function number(State s) public pure returns (uint64) {
+                return uint64(s);
+            }
+
+            function state_of(uint8 n) public pure returns (State) {
+                return State(n);
+            }
+
+            function idle(State s) public pure returns (bool) {
+                return s == State.Idle;
+            }
+        }",
+    );
+
+    assert!(code.contains(
+        "#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
+enum State {
+    Idle,
+    Running,
+    Stopped,
+}"
+    ));
+    // arguments out of range do not deserialize
+    assert!(code.contains(
+        "        let value = match value {
+            0 => State::Idle,
+            1 => State::Running,
+            2 => State::Stopped,
+            _ => return Err(bytesrepr::Error::Formatting),
+        };"
+    ));
+    assert!(code.contains("fn start(s: State) {"));
+    assert!(code.contains("let state: State = State::Running;"));
+    assert!(code.contains("get_key::<u64>(&new_key(\"counts\", &s)).wrapping_add(1);"));
+    assert!(code.contains("return s as u64;"));
+    assert!(code.contains("return State::from(n);"));
+    assert!(code.contains("return s == State::Idle;"));
+}
+
+#[test]
+fn schema() {
+    let (ns, errors) = parse_and_resolve(
+        "contract Child {
+            enum Size { Small, Large }
+            event Made(Size size, uint64);
+
+            constructor(Size s) public {
+                emit Made(s, 1);
+            }
+        }
+
+        contract Machine {
+            enum State { Idle, Running, Stopped }
+
+            function start(State s) public payable returns (State old) {
+                new Child(Child.Size.Large);
+                return s;
+            }
+        }",
+        Target::Casperlabs,
+    );
+    let ns = match ns {
+        Some(ns) => ns,
+        None => panic!("{:?}", errors),
+    };
+    let mut contract = CasperlabsContract::new(&ns.contracts[1], &ns);
+    contract.contract_macro = false;
+
+    let schema: serde_json::Value = serde_json::from_str(&contract.render_schema()).unwrap();
+
+    // enums are passed as a u8, so their variants are listed by value
+    assert_eq!(
+        schema["enums"],
+        serde_json::json!([
+            { "name": "Child.Size", "variants": ["Small", "Large"] },
+            { "name": "Machine.State", "variants": ["Idle", "Running", "Stopped"] },
+        ])
+    );
+    assert_eq!(
+        schema["contracts"][0],
+        serde_json::json!({
+            "name": "Machine",
+            "entryPoints": [{
+                "name": "start",
+                "inputs": [
+                    { "name": "s", "type": "enum Machine.State" },
+                    { "name": "purse", "type": "URef" },
+                ],
+                "outputs": [{ "name": "old", "type": "enum Machine.State" }],
+            }],
+            "events": [],
+        })
+    );
+    // the contracts created with `new` are part of the crate
+    assert_eq!(schema["contracts"][1]["name"], "Child");
+    assert_eq!(
+        schema["contracts"][1]["entryPoints"][0]["name"],
+        "Child_constructor"
+    );
+    assert_eq!(
+        schema["contracts"][1]["events"],
+        serde_json::json!([{
+            "name": "Made",
+            "fields": [
+                { "name": "size", "type": "enum Child.Size" },
+                { "name": "1", "type": "uint64" },
+            ],
+        }])
+    );
+}
+
+#[test]
+fn fixed_bytes() {
+    let code = render(
+        "contract Flags {
+            bytes4 flags;
+
+            function set(bytes4 mask, uint8 bits) public {
+                flags = (flags | mask) & ~(mask << bits);
+            }
+
+            function get(uint8 i) public view returns (bytes1) {
+                bytes4 f = flags;
+                return f[i];
+            }
+
+            function resize() public view returns (bytes8, bytes2) {
+                return (bytes8(flags), bytes2(flags));
+            }
+
+            function number(uint256 n) public view returns (uint32, bytes32, address) {
+                bytes32 b = bytes32(n);
+                return (uint32(flags), b, address(b));
+            }
+
+            function empty() public view returns (bool) {
+                return flags == hex\"00000000\";
+            }
+        }",
+    );
+
+    assert!(code.contains("fn set(mask: [u8; 4], bits: u8) {"));
+    assert!(code.contains(
+        "bytes_and(bytes_or(get_key::<[u8; 4]>(\"flags\"), mask), bytes_not(bytes_shl(mask, bits as usize)))"
+    ));
+    assert!(code.contains("return [f[indextemp3 as usize]];"));
+    assert!(code.contains(
+        "return (bytes_resize::<_, [u8; 8]>(get_key::<[u8; 4]>(\"flags\")), bytes_resize::<_, [u8; 2]>(get_key::<[u8; 4]>(\"flags\")));"
+    ));
+    // byte arrays are big-endian numbers
+    assert!(code.contains("let b: [u8; 32] = u256_bytes(n);"));
+    assert!(code.contains(
+        "return (u32::from_be_bytes(get_key::<[u8; 4]>(\"flags\")), b, AccountHash::new(b));"
+    ));
+    assert!(code.contains("return get_key::<[u8; 4]>(\"flags\") == [0, 0, 0, 0];"));
+    assert!(code.contains(
+        "fn bytes_shl<T: AsRef<[u8]> + AsMut<[u8]> + Default>(value: T, bits: usize) -> T {"
+    ));
+
+    // so are addresses
+    let code = render(
+        "contract c {
+            function toAddress(uint256 w) public pure returns (address) {
+                return address(uint160(w));
+            }
+
+            function toInt(address a) public pure returns (uint160) {
+                return uint160(a);
+            }
+
+            function zero() public pure returns (address) {
+                return address(0);
+            }
+        }",
+    );
+
+    assert!(code.contains("return AccountHash::new(u256_bytes(w & U256([18446744073709551615, 18446744073709551615, 4294967295, 0])));"));
+    assert!(code.contains("return U256::from_big_endian(&a.value()) & U256([18446744073709551615, 18446744073709551615, 4294967295, 0]);"));
+    assert!(code.contains("return AccountHash::new(u256_bytes(U256::from(0u64)));"));
+    assert!(code.contains("fn u256_bytes(value: U256) -> [u8; 32] {"));
+}
+
+#[test]
+fn integers() {
+    let code = render(
+        "contract Ints {
+            int256 total;
+
+            function add(uint24 a, uint24 b) public pure returns (uint24) {
+                return a + b;
+            }
+
+            function mul(int8 a, int8 b) public pure returns (int8) {
+                return a * b;
+            }
+
+            function shift(uint64 a, uint8 n, int32 s) public pure returns (uint64, int32) {
+                return (a << n, s >> n);
+            }
+
+            function far(uint64 a, uint64 n, uint256 w) public pure returns (uint64, uint256) {
+                return (a << n, w >> n);
+            }
+
+            function wide(int256 a, uint256 b) public returns (uint128, int256) {
+                total = -a / int256(b) + 1;
+                return (uint128(b), total);
+            }
+
+            function motes() public payable returns (uint256) {
+                return uint256(msg.value) * 2;
+            }
+
+            function narrow(int64 a) public pure returns (int16) {
+                return int16(a);
+            }
+
+            function big(uint256 a) public pure returns (uint256, int256) {
+                return (a + 2**200, -1180591620717411303424);
+            }
+        }",
+    );
+
+    // integers are held by the next wider rust type, and wrap at their own width
+    assert!(code.contains("fn add_inner(a: u32, b: u32) -> u32 {"));
+    assert!(code.contains("return a.wrapping_add(b) & 16777215u32;"));
+    assert!(code.contains("return a.wrapping_mul(b) << 24 >> 24;"));
+    // `a as i32 << 16` would parse as the start of generic arguments
+    assert!(code.contains("return (a as i32) << 16 >> 16;"));
+    // shifting by the width or more does not panic
+    assert!(
+        code.contains("return (a.checked_shl(n as u32).unwrap_or(0), s >> (n as u32).min(31));")
+    );
+    // amounts which may not fit are clamped, rather than truncated
+    assert!(code.contains(
+        "return (a.checked_shl(n.min(64u64) as u32).unwrap_or(0), w >> n.min(256u64) as usize);"
+    ));
+    // signed integers wider than 64 bits are two's complement `U256` values
+    assert!(code.contains("fn wide_inner(a: I256, b: U256) -> (U128, I256) {"));
+    assert!(code.contains(
+        "let total: I256 = a.wrapping_neg().wrapping_div(I256::from(b)).wrapping_add(I256::from(1i64));"
+    ));
+    assert!(code.contains("return (U128::from(b.low_u128()), get_key::<I256>(\"total\"));"));
+    assert!(code.contains("struct I256 {\n    value: U256,\n}"));
+    assert!(code.contains("return u512_to_u256(value).overflowing_mul(U256::from(2u64)).0;"));
+    assert!(code.contains("fn u512_to_u256(value: U512) -> U256 {"));
+    assert!(!code.contains("fn u256_to_u512"));
+    // literals which do not fit in 64 bits are built from their words
+    assert!(code.contains("return (a.overflowing_add(U256([0, 0, 0, 256])).0, I256::from(U256([0, 64, 0, 0])).wrapping_neg());"));
+}
+
+#[test]
+fn unsupported() {
+    let errors = render_errors(
+        "contract c {
+            mapping(uint64 => uint64) x;
+
+            function foo() public {
+                bar(x);
+            }
+
+            function bar(mapping(uint64 => uint64) storage m) internal {
+                m[1] = 2;
+            }
+        }",
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "type ‘mapping(uint64 => uint64)’ is not supported on target CasperLabs"
+    );
+
+    let errors = render_errors(
+        "contract c {
+            uint64 a;
+
+            function foo() public {
+                selfdestruct(msg.sender);
+            }
+        }",
+    );
+
+    assert_eq!(
+        first_error(errors),
+        "‘selfdestruct(address)’ is not supported on target CasperLabs"
+    );
+
+    let src = "contract c {
+            function foo(string a, string b) public returns (bool) {
+                return a == b;
+            }
+        }";
+
+    let errors = render_errors(src);
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message,
+        "string comparison is not supported on target CasperLabs"
+    );
+    assert_eq!(errors[0].pos.1, src.find("== b").unwrap());
+}
+
+#[test]
+fn cargo_project() {
+    let (ns, _) = parse_and_resolve(
+        "contract Counter {
+            uint64 count;
+
+            function inc(uint64 n) public {
+                while (count < n) {
+                    count = count + 1;
+                }
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    let ns = ns.unwrap();
+
+    let files = CasperlabsContract::new(&ns.contracts[0], &ns)
+        .render_crate()
+        .unwrap();
+
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+
+    assert_eq!(
+        names,
+        vec!["Cargo.toml", "Cargo.lock", "rust-toolchain", "src/main.rs"]
+    );
+
+    assert!(files[0].1.contains("name = \"counter\"\n"));
+    assert!(files[0].1.contains("casperlabs-types = \"=0.6.1\"\n"));
+
+    // the yanked casperlabs crates are locked, without the hash crates
+    assert!(files[1].1.contains(
+        "[[package]]
+name = \"casperlabs-types\"
+version = \"0.6.1\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+checksum = \"d468c1c017e5ef6713bf73431a57fa744702be3c3ad5ef53e39cac2e26177e5c\"
+dependencies = [
+ \"base16\",
+ \"bitflags\",
+ \"blake2\",
+"
+    ));
+    assert!(files[1].1.contains(
+        "[[package]]
+name = \"counter\"
+version = \"0.1.0\"
+dependencies = [
+ \"casperlabs-contract\",
+ \"casperlabs-types\",
+]
+"
+    ));
+    assert!(!files[1].1.contains("name = \"sha3\""));
+
+    assert_eq!(files[2].1, "nightly-2020-03-19\n");
+
+    assert!(files[3].1.contains(
+        "mod Counter {
+    #[casperlabs_method]
+    fn inc(n: u64) {
+        'loop_1: loop {
+            if get_key::<u64>(\"count\") < n {
+                let count: u64 = get_key::<u64>(\"count\").wrapping_add(1);
+                set_key(\"count\", count);
+                continue 'loop_1;
+            } else {
+                return;
+            }
+        }
+    }
+}
+"
+    ));
+}
+
+#[test]
+fn explicit_entry_points() {
+    let (ns, _) = parse_and_resolve(
+        "contract Counter {
+            uint64 count;
+
+            constructor(uint64 start) public {
+                count = start;
+            }
+
+            function inc(uint64 n) public {
+                count = count + n;
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    let ns = ns.unwrap();
+
+    let mut contract = CasperlabsContract::new(&ns.contracts[0], &ns);
+    contract.contract_macro = false;
+
+    let files = contract.render_crate().unwrap();
+
+    assert!(!files[0].1.contains("casperlabs_contract_macro"));
+    assert!(!files[3].1.contains("casperlabs_contract_macro"));
+
+    assert!(files[3].1.contains(
+        "#[no_mangle]
+pub extern \"C\" fn inc() {
+    Counter::inc(runtime::get_named_arg(\"n\"));
+}"
+    ));
+    assert!(files[3].1.contains(
+        "entry_points.add_entry_point(EntryPoint::new(\"inc\", vec![Parameter::new(\"n\", cl_type::<u64>())], CLType::Unit, EntryPointAccess::Public, EntryPointType::Contract));"
+    ));
+    assert!(files[3].1.contains(
+        "#[no_mangle]
+pub extern \"C\" fn call() {
+    let contract_hash = new_Counter(runtime::get_named_arg(\"start\"));
+    runtime::put_key(\"Counter\", Key::Hash(contract_hash));
+}"
+    ));
+
+    let (ns, _) = parse_and_resolve(
+        "contract Counter {
+            uint64 count;
+
+            function call(uint64 n) public {
+                count = n;
+            }
+
+            function get_key() public view returns (uint64) {
+                return count;
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    let ns = ns.unwrap();
+
+    let mut contract = CasperlabsContract::new(&ns.contracts[0], &ns);
+    contract.contract_macro = false;
+
+    // the installer and the helpers share the root of the crate with the entry points
+    let errors = contract.render().unwrap_err();
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(
+        errors[0].message,
+        "function ‘call’ of contract ‘Counter’ clashes with the generated function ‘call’ of the crate of contract ‘Counter’"
+    );
+    assert_eq!(
+        errors[1].message,
+        "function ‘get_key’ of contract ‘Counter’ clashes with the generated function ‘get_key’ of the crate of contract ‘Counter’"
+    );
+
+    contract.contract_macro = true;
+
+    assert_eq!(contract.render().unwrap_err().len(), 2);
+
+    let (ns, _) = parse_and_resolve(
+        "contract key {
+            uint64 count;
+        }
+
+        contract purse {
+            function open() public {
+                new key();
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    let ns = ns.unwrap();
+
+    // the installers of the contract and of the contracts it creates are at the root of the crate too
+    let mut contract = CasperlabsContract::new(&ns.contracts[1], &ns);
+    contract.contract_macro = false;
+    let errors = contract.render().unwrap_err();
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(
+        errors[0].message,
+        "installer ‘new_purse’ of contract ‘purse’ clashes with the generated function ‘new_purse’ of the crate of contract ‘purse’"
+    );
+    assert_eq!(
+        errors[1].message,
+        "installer ‘new_key’ of contract ‘key’ clashes with the generated function ‘new_key’ of the crate of contract ‘purse’"
+    );
+}
+
+#[test]
+fn unused_helpers() {
+    let (ns, _) = parse_and_resolve(
+        "contract c {
+            uint64 count;
+
+            function inc() public {
+                count = count + 1;
+            }
+
+            function neg(int128 a) public pure returns (int128) {
+                return -a;
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    let ns = ns.unwrap();
+
+    let mut contract = CasperlabsContract::new(&ns.contracts[0], &ns);
+    contract.contract_macro = false;
+
+    let code = contract.render().unwrap();
+
+    // only what the code refers to is imported
+    assert!(!code.contains("#![allow(unused_imports)]"));
+    assert!(code.contains("use casperlabs_contract::contract_api::{runtime, storage};"));
+    assert!(!code.contains("AccountHash"));
+    assert!(!code.contains("digest"));
+
+    // only the helpers which are called are generated
+    assert!(code.contains("fn get_key<"));
+    assert!(code.contains("fn set_key<"));
+    assert!(!code.contains("fn require("));
+    assert!(!code.contains("fn new_purse("));
+    assert!(!code.contains("fn emit_event("));
+    assert!(!code.contains("fn bytes_shl<"));
+
+    // and of the methods of I256, only those which are called
+    assert!(code.contains("    fn wrapping_neg(&self) -> I256 {"));
+    assert!(!code.contains("    fn bitxor(&self, other: I256) -> I256 {"));
+    assert!(code.contains("impl ToBytes for I256 {"));
+
+    // the code of the macros refers to the imports
+    let code = CasperlabsContract::new(&ns.contracts[0], &ns)
+        .render()
+        .unwrap();
+
+    assert!(code.contains("#![allow(unused_imports)]"));
+    assert!(code.contains("use casperlabs_types::account::AccountHash;"));
+    assert!(!code.contains("fn new_purse("));
+}
+
+/// Write the crate of the contract to a temporary directory, and type check it
+/// with the toolchain and the dependencies which the crate pins
+fn cargo_check(contract: &CasperlabsContract, dir: &str) {
+    let crate_dir = std::env::temp_dir().join("caspiler-tests").join(dir);
+
+    for (name, contents) in contract.render_crate().unwrap() {
+        let path = crate_dir.join(name);
+
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    // the toolchain of the crate is given by its rust-toolchain file, unless
+    // rustup is told the toolchain of these tests
+    let output = std::process::Command::new("cargo")
+        .arg("check")
+        .arg("--locked")
+        .arg("--target")
+        .arg("wasm32-unknown-unknown")
+        .env_remove("RUSTUP_TOOLCHAIN")
+        .current_dir(&crate_dir)
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+// needs the network for the dependencies, and the toolchain of the crate with
+// the wasm32-unknown-unknown target; run with `cargo test -- --ignored`, as the
+// casperlabs job of the CI workflow does
+#[test]
+#[ignore]
+fn generated_crates_compile() {
+    let (ns, errors) = parse_and_resolve(
+        "contract Child {
+            uint64 value;
+
+            constructor(uint64 v) public {
+                value = v;
+            }
+        }
+
+        contract Bank {
+            enum State { Open, Closed }
+
+            struct Account {
+                uint64 balance;
+                bytes20 id;
+                address referrer;
+            }
+
+            State state;
+            mapping(address => Account) accounts;
+            uint256[] history;
+            address admin;
+
+            event Deposit(address who, uint64 amount);
+
+            function deposit(uint64 amount) public payable returns (uint64) {
+                require(state == State.Open, \"closed\");
+                Account storage account = accounts[msg.sender];
+                account.balance += amount;
+                history.push(uint256(amount) << 8);
+                emit Deposit(msg.sender, amount);
+                return account.balance;
+            }
+
+            function withdraw(uint64 amount) public returns (uint64, bool) {
+                if (accounts[msg.sender].balance < amount) {
+                    revert(\"insufficient\");
+                }
+                accounts[msg.sender].balance -= amount;
+                return (amount, block.timestamp > 0);
+            }
+
+            function hashes(bytes memory b) public pure returns (bytes32, bytes32, bytes32) {
+                return (sha256(b), blake2_256(b), keccak256(b));
+            }
+
+            function identify(bytes memory b) public {
+                accounts[msg.sender].id = ripemd160(b);
+            }
+
+            function arith(int64 a, uint64 n, int256 w) public pure returns (int64, int256) {
+                assert(n != 0);
+                return (a >> n, ((w / int256(a)) << n) + 2**200);
+            }
+
+            function last() public view returns (uint256) {
+                return history[history.length - 1];
+            }
+
+            function pick(uint64[] memory a, uint64 i) public pure returns (uint64) {
+                return a[i];
+            }
+
+            function owner(uint256 w) public pure returns (address, uint160) {
+                address a = address(uint160(w));
+                return (a, uint160(a));
+            }
+
+            function transferAdmin(address next) public {
+                require(msg.sender == admin, \"not the admin\");
+                admin = next;
+            }
+
+            function close() public {
+                state = State.Closed;
+                new Child(1);
+            }
+        }",
+        Target::Casperlabs,
+    );
+
+    let ns = match ns {
+        Some(ns) => ns,
+        None => panic!("{:?}", errors),
+    };
+
+    // casperlabs_contract_macro is not published, so only the crate without
+    // the macros can be built
+    let mut contract = CasperlabsContract::new(&ns.contracts[1], &ns);
+    contract.contract_macro = false;
+
+    cargo_check(&contract, "explicit");
+}
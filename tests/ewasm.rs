@@ -15,6 +15,7 @@ use std::fmt;
 use wasmi::memory_units::Pages;
 use wasmi::*;
 
+use solang::file_cache::FileCache;
 use solang::output;
 use solang::{compile, Target};
 
@@ -660,14 +661,17 @@ impl TestRuntime {
 }
 
 fn build_solidity(src: &'static str) -> TestRuntime {
+    let mut cache = FileCache::new();
+    let file_no = cache.set_file_contents("test.sol", src.to_string());
+
     let (res, errors) = compile(
-        src,
-        "test.sol",
+        file_no,
+        &mut cache,
         inkwell::OptimizationLevel::Default,
         Target::Ewasm,
     );
 
-    output::print_messages("test.sol", src, &errors, false);
+    output::print_messages(&cache, &errors, false);
 
     for v in &res {
         println!("contract size:{}", v.0.len());
@@ -25,6 +25,7 @@ use wasmi::memory_units::Pages;
 use wasmi::*;
 
 use solang::abi;
+use solang::file_cache::FileCache;
 use solang::output;
 use solang::{compile, Target};
 
@@ -804,14 +805,17 @@ impl TestRuntime {
 }
 
 pub fn build_solidity(src: &'static str) -> TestRuntime {
+    let mut cache = FileCache::new();
+    let file_no = cache.set_file_contents("test.sol", src.to_string());
+
     let (res, errors) = compile(
-        src,
-        "test.sol",
+        file_no,
+        &mut cache,
         inkwell::OptimizationLevel::Default,
         Target::Substrate,
     );
 
-    output::print_messages("test.sol", src, &errors, false);
+    output::print_messages(&cache, &errors, false);
 
     assert!(!res.is_empty());
 